@@ -1,4 +1,5 @@
-use crate::core::{FileDetector, Planner, Pipeline, Registry};
+use crate::core::scheduler::SchedulerLimits;
+use crate::core::{FileDetector, Planner, Pipeline, PluginManager, Registry, SettingsStore};
 use crate::error::ErrorResponse;
 use crate::types::*;
 use serde_json::Value;
@@ -23,17 +24,26 @@ pub async fn detect_file(file_path: String) -> std::result::Result<FileDetection
 
 /// 规划目标格式
 #[tauri::command]
-pub async fn plan_targets(file_path: String) -> std::result::Result<Vec<ConversionTarget>, ErrorResponse> {
+pub async fn plan_targets(
+    file_path: String,
+    options: Option<ConversionOptions>,
+) -> std::result::Result<Vec<ConversionTarget>, ErrorResponse> {
     // 探测文件格式
     let path = Path::new(&file_path);
     let detection = FileDetector::detect(path)
         .map_err(|e| -> ErrorResponse { e.into() })?;
 
+    // 未指定选项时使用设置中的质量优先级
+    let options = options.unwrap_or_else(|| ConversionOptions {
+        quality_priority: SettingsStore::global().get().quality_priority,
+        ..Default::default()
+    });
+
     let planner = Planner::new();
-    planner
-        .get_available_targets(&detection.mime_type)
-        .pipe(Ok)
-        .map_err(|e: crate::error::AppError| e.into())
+    Planner::by_detected_source(&detection, |source| {
+        Ok(planner.get_available_targets(source, &options))
+    })
+    .map_err(|e| e.into())
 }
 
 /// 创建转换任务
@@ -45,9 +55,8 @@ pub async fn create_job(
     options: Value,
 ) -> std::result::Result<String, ErrorResponse> {
     let job = Job::new(inputs, target, recipe_id, options);
-    let job_id = job.id.clone();
 
-    let pipeline = Pipeline::new();
+    let pipeline = Pipeline::global();
     pipeline
         .submit(job)
         .await
@@ -57,7 +66,7 @@ pub async fn create_job(
 /// 列出任务
 #[tauri::command]
 pub async fn list_jobs() -> std::result::Result<Vec<Job>, ErrorResponse> {
-    let pipeline = Pipeline::new();
+    let pipeline = Pipeline::global();
     pipeline
         .list_jobs()
         .await
@@ -67,7 +76,7 @@ pub async fn list_jobs() -> std::result::Result<Vec<Job>, ErrorResponse> {
 /// 获取任务详情
 #[tauri::command]
 pub async fn get_job(job_id: String) -> std::result::Result<Option<Job>, ErrorResponse> {
-    let pipeline = Pipeline::new();
+    let pipeline = Pipeline::global();
     pipeline
         .get_job(&job_id)
         .await
@@ -77,44 +86,57 @@ pub async fn get_job(job_id: String) -> std::result::Result<Option<Job>, ErrorRe
 /// 控制任务
 #[tauri::command]
 pub async fn control_job(job_id: String, action: JobAction) -> std::result::Result<(), ErrorResponse> {
-    let pipeline = Pipeline::new();
+    let pipeline = Pipeline::global();
 
     match action {
+        JobAction::Pause => pipeline.pause(&job_id).await,
+        JobAction::Resume => pipeline.resume(&job_id).await,
         JobAction::Cancel => pipeline.cancel(&job_id).await,
-        _ => Ok(()), // TODO: 实现其他动作
+        JobAction::Retry => pipeline.retry(&job_id).await,
+        JobAction::MoveToTop => pipeline.move_to_top(&job_id).await,
+        JobAction::Defer => pipeline.defer(&job_id).await,
     }
     .map_err(|e| e.into())
 }
 
+/// 调整任务优先级
+#[tauri::command]
+pub async fn set_job_priority(job_id: String, priority: i32) -> std::result::Result<(), ErrorResponse> {
+    Pipeline::global()
+        .set_priority(&job_id, priority)
+        .await
+        .map_err(|e| e.into())
+}
+
 /// 获取产物列表
 #[tauri::command]
 pub async fn get_artifacts(job_id: String) -> std::result::Result<Vec<Artifact>, ErrorResponse> {
-    // TODO: 实现
-    Ok(vec![])
+    Pipeline::global()
+        .get_artifacts(&job_id)
+        .await
+        .map_err(|e| e.into())
 }
 
 /// 获取任务日志
 #[tauri::command]
 pub async fn get_logs(job_id: String, cursor: Option<i64>) -> std::result::Result<Vec<LogEntry>, ErrorResponse> {
-    // TODO: 实现
-    Ok(vec![])
+    Pipeline::global()
+        .get_logs(&job_id, cursor)
+        .await
+        .map_err(|e| e.into())
 }
 
 /// 获取设置
 #[tauri::command]
-pub async fn get_settings() -> std::result::Result<Value, ErrorResponse> {
-    // TODO: 实现
-    Ok(serde_json::json!({
-        "theme": "system",
-        "language": "zh-CN",
-        "quality_priority": "balanced"
-    }))
+pub async fn get_settings() -> std::result::Result<AppSettings, ErrorResponse> {
+    Ok(SettingsStore::global().get())
 }
 
 /// 设置配置
 #[tauri::command]
 pub async fn set_settings(settings: Value) -> std::result::Result<(), ErrorResponse> {
-    // TODO: 实现
+    let settings = SettingsStore::global().update(settings)?;
+    Pipeline::global().set_limits(SchedulerLimits::from_settings(&settings));
     Ok(())
 }
 
@@ -127,6 +149,46 @@ pub async fn run_health_check() -> std::result::Result<HealthReport, ErrorRespon
         .map_err(|e: crate::error::AppError| e.into())
 }
 
+/// 从本地插件包安装插件
+#[tauri::command]
+pub async fn install_plugin(package_path: String) -> std::result::Result<PluginInfo, ErrorResponse> {
+    PluginManager::global()
+        .install(Path::new(&package_path), &SettingsStore::global().get())
+        .map_err(|e| e.into())
+}
+
+/// 列出已安装插件
+#[tauri::command]
+pub async fn list_plugins() -> std::result::Result<Vec<PluginInfo>, ErrorResponse> {
+    PluginManager::global()
+        .list()
+        .map_err(|e| e.into())
+}
+
+/// 启用插件
+#[tauri::command]
+pub async fn enable_plugin(plugin_id: String) -> std::result::Result<PluginInfo, ErrorResponse> {
+    PluginManager::global()
+        .set_enabled(&plugin_id, true)
+        .map_err(|e| e.into())
+}
+
+/// 禁用插件
+#[tauri::command]
+pub async fn disable_plugin(plugin_id: String) -> std::result::Result<PluginInfo, ErrorResponse> {
+    PluginManager::global()
+        .set_enabled(&plugin_id, false)
+        .map_err(|e| e.into())
+}
+
+/// 卸载插件
+#[tauri::command]
+pub async fn uninstall_plugin(plugin_id: String) -> std::result::Result<(), ErrorResponse> {
+    PluginManager::global()
+        .uninstall(&plugin_id)
+        .map_err(|e| e.into())
+}
+
 // 辅助 trait 用于 pipe 语法
 trait Pipe: Sized {
     fn pipe<F, R>(self, f: F) -> R
@@ -1,40 +1,215 @@
-use crate::error::Result;
-use std::path::Path;
-use std::process::{Command, Output};
+use crate::core::invocation::ToolInvocation;
+use crate::core::process::{kill_process_group, ProcessTable};
+use crate::core::progress::{self, OutputStream, ProgressSink, ToolProgress};
+use crate::core::sandbox::{ResourceLimits, ToolIsolation};
+use crate::core::version::ToolVersion;
+use crate::error::{AppError, Result};
+use crate::types::ToolHealth;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::time::Duration;
+use tokio::runtime::RuntimeFlavor;
+
+/// 没有进度输出时检查输出文件大小的间隔
+const OUTPUT_GROWTH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 计算超时时检查任务是否暂停的间隔
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 已知外部工具
+pub struct ToolSpec {
+    /// 工具 ID，与 `Requirements.tools` 中的名称一致
+    pub id: &'static str,
+    /// 显示名称
+    pub name: &'static str,
+    /// 候选可执行文件名，按顺序查找
+    pub binaries: &'static [&'static str],
+    /// 输出版本号的参数，按顺序尝试
+    pub version_args: &'static [&'static str],
+}
+
+/// 未知工具依次尝试的版本参数
+const DEFAULT_VERSION_ARGS: &[&str] = &["--version", "-version", "-v"];
+
+/// 健康检查时探测的外部工具
+pub const KNOWN_TOOLS: &[ToolSpec] = &[
+    ToolSpec {
+        id: "pandoc",
+        name: "Pandoc",
+        binaries: &["pandoc"],
+        version_args: &["--version"],
+    },
+    ToolSpec {
+        id: "magick",
+        name: "ImageMagick",
+        binaries: &["magick"],
+        version_args: &["--version"],
+    },
+    ToolSpec {
+        id: "ffmpeg",
+        name: "FFmpeg",
+        binaries: &["ffmpeg"],
+        version_args: &["-version"],
+    },
+    ToolSpec {
+        id: "ffprobe",
+        name: "FFprobe",
+        binaries: &["ffprobe"],
+        version_args: &["-version"],
+    },
+    ToolSpec {
+        id: "tesseract",
+        name: "Tesseract OCR",
+        binaries: &["tesseract"],
+        version_args: &["--version"],
+    },
+    ToolSpec {
+        id: "libreoffice",
+        name: "LibreOffice",
+        binaries: &["libreoffice", "soffice"],
+        version_args: &["--version"],
+    },
+];
 
 /// 工具适配器 - 沙箱调用外部工具
 pub struct Adapter;
 
 impl Adapter {
-    /// 在沙箱中执行命令
+    /// 在沙箱中执行命令，阻塞直到工具结束
+    ///
+    /// 在多线程 tokio 运行时中调用时复用该运行时，工作线程通过 `block_in_place` 让出调度；
+    /// 单线程运行时无法在其中阻塞等待，改为在独立线程上用临时运行时执行，并沿用当前线程
+    /// 所属的任务与进度接收端；不在运行时中时直接创建临时运行时。
     pub fn execute_sandboxed(
-        tool: &str,
-        args: &[&str],
-        _input_path: &Path,
-        _output_path: &Path,
-        _timeout_s: Option<u32>,
-        _max_mem_mb: Option<u32>,
+        invocation: &ToolInvocation,
+        input_path: &Path,
+        output_path: &Path,
+        timeout_s: Option<u32>,
+        max_mem_mb: Option<u32>,
     ) -> Result<Output> {
-        // TODO: 实现沙箱机制
-        // 1. 资源限制（CPU/内存/时间）
-        // 2. 文件系统白名单
-        // 3. 错误处理
-        // 4. 日志记录
-
-        tracing::info!(
-            "Executing tool: {} with args: {:?}",
-            tool,
-            args
-        );
-
-        let output = Command::new(tool)
-            .args(args)
-            .output()
-            .map_err(|e| crate::error::AppError::ToolError(format!("Failed to execute {}: {}", tool, e)))?;
+        let run = || Self::execute_sandboxed_async(invocation, input_path, output_path, timeout_s, max_mem_mb);
+        let block_on = |run| tokio::runtime::Builder::new_current_thread().enable_all().build()?.block_on(run);
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(|| handle.block_on(run()))
+            }
+            Ok(_) => {
+                let current = ProcessTable::current_job().zip(ProcessTable::current_token());
+                let sink = progress::current_sink();
+                let in_job = || match &current {
+                    Some((job_id, token)) => ProcessTable::scope(job_id, token, || block_on(run())),
+                    None => block_on(run()),
+                };
+                std::thread::scope(|scope| {
+                    scope
+                        .spawn(|| match sink {
+                            Some(sink) => progress::scope(sink, in_job),
+                            None => in_job(),
+                        })
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+            }
+            Err(_) => block_on(run()),
+        }
+    }
+
+    /// 在沙箱中执行命令
+    ///
+    /// 运行超过 `timeout_s` 时结束整个进程组，所属任务暂停的时间不计入；`max_mem_mb` 与
+    /// `timeout_s` 同时作为内存与 CPU 时间的硬限制。超出限制时返回 `AppError::ResourceLimit`。
+    ///
+    /// 工具在私有工作目录中运行，支持 Landlock 时只能读取输入文件、写入输出文件所在目录，
+    /// 因此输入与输出应使用绝对路径。
+    pub async fn execute_sandboxed_async(
+        invocation: &ToolInvocation,
+        input_path: &Path,
+        output_path: &Path,
+        timeout_s: Option<u32>,
+        max_mem_mb: Option<u32>,
+    ) -> Result<Output> {
+        let tool = invocation.program.as_str();
+        tracing::info!("Executing tool: {}", invocation);
+
+        // 所属任务已取消时不再启动新进程
+        let token = ProcessTable::current_token();
+        let job_id = ProcessTable::current_job();
+        if token.as_ref().is_some_and(|t| t.is_cancelled()) {
+            return Err(AppError::Cancelled);
+        }
+
+        let limits = ResourceLimits::new(timeout_s, max_mem_mb);
+        let mut command = tokio::process::Command::new(tool);
+        command
+            .args(&invocation.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        // 独立进程组，暂停/恢复/取消/超时时信号作用于工具及其子进程
+        #[cfg(unix)]
+        command.process_group(0);
+        let cgroup = limits.apply(&mut command);
+
+        let program = match Path::new(tool) {
+            path if path.is_absolute() => path.to_path_buf(),
+            _ => Self::find_executable(tool, std::env::var_os("PATH").as_deref())
+                .unwrap_or_else(|| PathBuf::from(tool)),
+        };
+        let output_dir = output_path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let isolation = ToolIsolation::apply(&mut command, &program, &[input_path], output_dir)?;
+        tracing::debug!("Tool {} isolation: {:?}", tool, isolation.level());
+
+        let child = command
+            .spawn()
+            .map_err(|e| AppError::ToolError(format!("Failed to execute {}: {}", tool, e)))?;
+        let pid = child.id();
+        let _guard = pid.and_then(|pid| ProcessTable::global().register(pid));
+
+        let wait = capture_output(child, &program, input_path, output_path);
+        tokio::pin!(wait);
+        let waited = match limits.timeout() {
+            Some(limit) => match wait_within(limit, job_id.as_deref(), wait.as_mut()).await {
+                Some(waited) => waited,
+                None => {
+                    if let Some(pid) = pid {
+                        kill_process_group(pid);
+                    }
+                    let _ = wait.await;
+                    return Err(AppError::ResourceLimit(format!(
+                        "工具 {} 运行超过 {} 秒，已被终止",
+                        tool,
+                        timeout_s.unwrap_or_default()
+                    )));
+                }
+            },
+            None => wait.await,
+        };
+        let output = waited.map_err(|e| AppError::ToolError(format!("Failed to execute {}: {}", tool, e)))?;
+
+        if token.is_some_and(|t| t.is_cancelled()) {
+            return Err(AppError::Cancelled);
+        }
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(crate::error::AppError::ToolError(format!(
+
+            if let Some(violation) = limits.violation(tool, &output.status, &stderr, cgroup.as_ref()) {
+                return Err(violation);
+            }
+
+            // 没有退出码说明进程被信号终止，视为崩溃
+            if output.status.code().is_none() {
+                return Err(AppError::ToolCrashed(format!(
+                    "Tool {} terminated ({}): {}",
+                    tool, output.status, stderr
+                )));
+            }
+            return Err(AppError::ToolError(format!(
                 "Tool {} failed: {}",
                 tool, stderr
             )));
@@ -43,25 +218,414 @@ impl Adapter {
         Ok(output)
     }
 
-    /// 检查工具是否可用
+    /// 检查工具是否可用：能在 PATH 中找到，且能识别出版本号或版本参数正常退出
     pub fn check_tool(tool: &str) -> bool {
-        Command::new(tool)
-            .arg("--version")
-            .output()
-            .is_ok()
+        Self::probe_tool(tool, None, std::env::var_os("PATH").as_deref()).available
     }
 
-    /// 获取工具版本
+    /// 获取工具版本号，如 "3.1.2"
     pub fn get_tool_version(tool: &str) -> Option<String> {
-        let output = Command::new(tool)
-            .arg("--version")
-            .output()
-            .ok()?;
+        Self::probe_tool(tool, None, std::env::var_os("PATH").as_deref()).version
+    }
 
-        if output.status.success() {
-            Some(String::from_utf8_lossy(&output.stdout).to_string())
+    /// 列出 Tesseract 已安装的 OCR 语言包
+    pub fn list_ocr_languages() -> Vec<String> {
+        let Ok(output) = Command::new("tesseract").arg("--list-langs").output() else {
+            return Vec::new();
+        };
+
+        // 首行为 "List of available languages ..."，其后每行一个语言
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .skip(1)
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+
+    /// 通过 fontconfig 列出系统字体族，不可用时返回 None
+    pub fn list_fonts() -> Option<Vec<String>> {
+        let output = Command::new("fc-list").args([":", "family"]).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        let mut fonts: Vec<String> = Vec::new();
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            for family in line.split(',') {
+                let family = family.trim();
+                if !family.is_empty() && !fonts.iter().any(|f| f == family) {
+                    fonts.push(family.to_string());
+                }
+            }
+        }
+
+        Some(fonts)
+    }
+
+    /// 探测工具的安装位置与版本
+    ///
+    /// `binary_path` 不为空时只检查该路径，否则在 `search_path`（PATH 格式）
+    /// 中依次查找该工具的候选可执行文件。
+    pub fn probe_tool(
+        tool: &str,
+        binary_path: Option<&str>,
+        search_path: Option<&OsStr>,
+    ) -> ToolHealth {
+        let spec = KNOWN_TOOLS.iter().find(|spec| spec.id == tool);
+        let name = spec.map(|spec| spec.name).unwrap_or(tool).to_string();
+        let version_args = spec.map(|spec| spec.version_args).unwrap_or(DEFAULT_VERSION_ARGS);
+
+        let path = match binary_path {
+            Some(path) => Some(PathBuf::from(path)).filter(|p| is_executable(p)),
+            None => {
+                let binaries = spec.map(|spec| spec.binaries).unwrap_or(&[]);
+                std::iter::once(tool)
+                    .chain(binaries.iter().copied())
+                    .find_map(|binary| Self::find_executable(binary, search_path))
+            }
+        };
+
+        let Some(path) = path else {
+            return ToolHealth {
+                name,
+                available: false,
+                version: None,
+                path: binary_path.map(str::to_string),
+                error: Some("未安装".to_string()),
+            };
+        };
+
+        let (version, error) = probe_version(&path, version_args);
+
+        ToolHealth {
+            name,
+            available: error.is_none(),
+            version,
+            path: Some(path.to_string_lossy().to_string()),
+            error,
+        }
+    }
+
+    /// 在搜索路径（PATH 格式）中查找可执行文件
+    pub fn find_executable(binary: &str, search_path: Option<&OsStr>) -> Option<PathBuf> {
+        let search_path = search_path?;
+
+        for dir in std::env::split_paths(search_path) {
+            let candidate = dir.join(binary);
+            if is_executable(&candidate) {
+                return Some(candidate);
+            }
+
+            if cfg!(windows) {
+                for ext in ["exe", "cmd", "bat"] {
+                    let candidate = candidate.with_extension(ext);
+                    if candidate.is_file() {
+                        return Some(candidate);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// 在 `limit` 内等待 `wait` 完成，所属任务暂停期间不计时；超时返回 None
+async fn wait_within<F: std::future::Future>(
+    limit: Duration,
+    job_id: Option<&str>,
+    mut wait: std::pin::Pin<&mut F>,
+) -> Option<F::Output> {
+    let table = ProcessTable::global();
+    let mut remaining = limit;
+    loop {
+        let started = tokio::time::Instant::now();
+        tokio::select! {
+            output = wait.as_mut() => return Some(output),
+            _ = tokio::time::sleep(remaining.min(PAUSE_POLL_INTERVAL)) => {}
+        }
+        if !job_id.is_some_and(|job_id| table.is_paused(job_id)) {
+            remaining = remaining.saturating_sub(started.elapsed());
+            if remaining.is_zero() {
+                return None;
+            }
+        }
+    }
+}
+
+/// 等待工具结束并收集输出
+///
+/// 输出逐行交给该工具的进度解析器；工具没有报告完成比例时按输出文件的增长估算进度。
+/// 进度发送到当前线程的进度接收端，没有接收端时只收集输出。
+async fn capture_output(
+    mut child: tokio::process::Child,
+    program: &Path,
+    input_path: &Path,
+    output_path: &Path,
+) -> std::io::Result<Output> {
+    use tokio::io::AsyncReadExt;
+
+    let mut emitter = ProgressEmitter::new(progress::current_sink());
+    let mut parser = progress::parser_for(&program.to_string_lossy());
+    let input_size = std::fs::metadata(input_path).map(|m| m.len()).unwrap_or(0);
+    let mut growth = tokio::time::interval(OUTPUT_GROWTH_INTERVAL);
+    let mut reported_fraction = false;
+
+    let mut stdout = child.stdout.take();
+    let mut stderr = child.stderr.take();
+    let (mut stdout_bytes, mut stderr_bytes) = (Vec::new(), Vec::new());
+    let (mut stdout_line, mut stderr_line) = (Vec::new(), Vec::new());
+    let (mut stdout_chunk, mut stderr_chunk) = ([0u8; 4096], [0u8; 4096]);
+
+    while stdout.is_some() || stderr.is_some() {
+        let (stream, chunk) = tokio::select! {
+            read = async { stdout.as_mut().unwrap().read(&mut stdout_chunk).await }, if stdout.is_some() => {
+                match read? {
+                    0 => {
+                        stdout = None;
+                        (OutputStream::Stdout, &stdout_chunk[..0])
+                    }
+                    n => (OutputStream::Stdout, &stdout_chunk[..n]),
+                }
+            }
+            read = async { stderr.as_mut().unwrap().read(&mut stderr_chunk).await }, if stderr.is_some() => {
+                match read? {
+                    0 => {
+                        stderr = None;
+                        (OutputStream::Stderr, &stderr_chunk[..0])
+                    }
+                    n => (OutputStream::Stderr, &stderr_chunk[..n]),
+                }
+            }
+            _ = growth.tick(), if emitter.is_active() && !reported_fraction => {
+                let written = std::fs::metadata(output_path).map(|m| m.len()).unwrap_or(0);
+                if let Some(mut update) = progress::output_growth(input_size, written) {
+                    if parser.is_some() {
+                        update.stage = None;
+                    }
+                    emitter.emit(update);
+                }
+                continue;
+            }
+        };
+
+        let (bytes, line) = match stream {
+            OutputStream::Stdout => (&mut stdout_bytes, &mut stdout_line),
+            OutputStream::Stderr => (&mut stderr_bytes, &mut stderr_line),
+        };
+        bytes.extend_from_slice(chunk);
+        let finished = chunk.is_empty();
+        for text in split_lines(line, chunk, finished) {
+            if let Some(update) = parser.as_mut().and_then(|parser| parser.feed(stream, &text)) {
+                reported_fraction |= update.fraction.is_some();
+                emitter.emit(update);
+            }
+        }
+    }
+
+    let status = child.wait().await?;
+    Ok(Output {
+        status,
+        stdout: stdout_bytes,
+        stderr: stderr_bytes,
+    })
+}
+
+/// 追加输出片段，返回其中完整的行；`\r` 同样视为行结束，流结束时返回剩余内容
+fn split_lines(pending: &mut Vec<u8>, chunk: &[u8], finished: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    for &byte in chunk {
+        if byte == b'\n' || byte == b'\r' {
+            if !pending.is_empty() {
+                lines.push(String::from_utf8_lossy(pending).into_owned());
+                pending.clear();
+            }
         } else {
-            None
+            pending.push(byte);
+        }
+    }
+    if finished && !pending.is_empty() {
+        lines.push(String::from_utf8_lossy(pending).into_owned());
+        pending.clear();
+    }
+    lines
+}
+
+/// 过滤重复的进度：只在阶段变化或完成比例变化达到 1% 时发送
+struct ProgressEmitter {
+    sink: Option<ProgressSink>,
+    last: Option<ToolProgress>,
+}
+
+impl ProgressEmitter {
+    fn new(sink: Option<ProgressSink>) -> Self {
+        Self { sink, last: None }
+    }
+
+    fn is_active(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    fn emit(&mut self, update: ToolProgress) {
+        let Some(sink) = &self.sink else {
+            return;
+        };
+        if let Some(last) = &self.last {
+            let moved = match (last.fraction, update.fraction) {
+                (Some(previous), Some(current)) => (current - previous).abs() >= 0.01 || current >= 1.0,
+                (_, current) => current.is_some(),
+            };
+            let staged = update.stage.is_some() && update.stage != last.stage;
+            if !moved && !staged {
+                return;
+            }
+        }
+        self.last = Some(update.clone());
+        sink(update);
+    }
+}
+
+/// 依次用版本参数运行工具，返回识别出的版本号与工具不可用的原因
+///
+/// 输出中识别出版本号即视为可用；都无法识别时，只要有一个参数正常退出也视为可用。
+fn probe_version(path: &Path, version_args: &[&str]) -> (Option<String>, Option<String>) {
+    let mut exited_ok = false;
+    let mut failure = None;
+
+    for arg in version_args {
+        let output = match Command::new(path).arg(arg).stdin(Stdio::null()).output() {
+            Ok(output) => output,
+            Err(e) => return (None, Some(format!("无法运行: {}", e))),
+        };
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if let Some(version) = parse_version(&stdout).or_else(|| parse_version(&stderr)) {
+            return (Some(version), None);
         }
+
+        if output.status.success() {
+            exited_ok = true;
+        } else if failure.is_none() {
+            let detail = stderr.lines().find(|line| !line.trim().is_empty()).unwrap_or_default();
+            failure = Some(format!("{} 执行失败 ({}): {}", arg, output.status, detail.trim()));
+        }
+    }
+
+    match exited_ok {
+        true => (None, None),
+        false => (None, failure.or_else(|| Some("无法识别版本".to_string()))),
+    }
+}
+
+/// 判断路径是否为可执行文件
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    }
+
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+/// 从版本输出中提取版本号，优先取靠前的行，如 "pandoc 3.1.2" -> "3.1.2"、
+/// "ffmpeg version n6.1.1-3ubuntu5" -> "6.1.1-3ubuntu5"。去掉 `v`/`n` 前缀，保留发行版后缀，
+/// 比较版本时由 `ToolVersion` 忽略后缀。
+pub fn parse_version(output: &str) -> Option<String> {
+    output.split_whitespace().find_map(|token| {
+        let token = token.trim_end_matches(|c: char| !c.is_ascii_alphanumeric());
+        let token = token
+            .strip_prefix(['v', 'V', 'n'])
+            .filter(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+            .unwrap_or(token);
+        let numeric = token.split(|c: char| !c.is_ascii_digit() && c != '.').next()?;
+        let dotted = numeric.contains('.') && ToolVersion::parse(numeric).is_some();
+        (token.starts_with(|c: char| c.is_ascii_digit()) && dotted).then(|| token.to_string())
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::core::process::CancellationToken;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_execute_sandboxed_on_runtime_worker() {
+        let invocation = ToolInvocation::new("echo", ["worker"]);
+        let output =
+            Adapter::execute_sandboxed(&invocation, Path::new("/dev/null"), Path::new("/dev/null"), None, None)
+                .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "worker");
+    }
+
+    #[tokio::test]
+    async fn test_execute_sandboxed_on_current_thread_runtime() {
+        let invocation = ToolInvocation::new("echo", ["current"]);
+        let output =
+            Adapter::execute_sandboxed(&invocation, Path::new("/dev/null"), Path::new("/dev/null"), None, None)
+                .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "current");
+
+        // 在独立线程上执行时仍属于调用方的任务
+        let token = CancellationToken::new();
+        token.cancel();
+        let result = ProcessTable::scope("current-thread-job", &token, || {
+            Adapter::execute_sandboxed(&invocation, Path::new("/dev/null"), Path::new("/dev/null"), None, None)
+        });
+        assert!(matches!(result, Err(AppError::Cancelled)), "{:?}", result);
+    }
+
+    #[test]
+    fn test_parse_version() {
+        let cases = [
+            ("pandoc 3.1.2\nCompiled with pandoc-types 1.23", Some("3.1.2")),
+            ("ffmpeg version n6.1.1-3ubuntu5 Copyright (c) 2000-2023", Some("6.1.1-3ubuntu5")),
+            ("Version: ImageMagick 7.1.1-15 Q16 x86_64", Some("7.1.1-15")),
+            ("tesseract v5.3.0", Some("5.3.0")),
+            ("LibreOffice 7.6.4.1 60(Build:1)", Some("7.6.4.1")),
+            ("usage: tool [options]", None),
+        ];
+        for (output, expected) in cases {
+            assert_eq!(parse_version(output).as_deref(), expected, "{}", output);
+        }
+    }
+
+    #[test]
+    fn test_probe_tries_version_args() {
+        let bin_dir = tempfile::tempdir().unwrap();
+        let search_path = Some(bin_dir.path().as_os_str());
+
+        // 只认 -v 的工具，其他参数报错退出
+        let path = bin_dir.path().join("f2f-probe-v");
+        std::fs::write(
+            &path,
+            "#!/bin/sh\n[ \"$1\" = -v ] || { echo \"unknown option $1\" >&2; exit 2; }\necho 'probe v1.4.2' >&2\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let health = Adapter::probe_tool("f2f-probe-v", None, search_path);
+        assert!(health.available, "{:?}", health.error);
+        assert_eq!(health.version.as_deref(), Some("1.4.2"));
+
+        // 能启动但所有版本参数都失败的工具不可用
+        let path = bin_dir.path().join("f2f-probe-broken");
+        std::fs::write(&path, "#!/bin/sh\necho 'library not loaded' >&2\nexit 127\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let health = Adapter::probe_tool("f2f-probe-broken", None, search_path);
+        assert!(!health.available);
+        assert!(health.error.unwrap().contains("library not loaded"));
+
+        let path = bin_dir.path().join("ffmpeg");
+        std::fs::write(&path, "#!/bin/sh\necho 'ffmpeg version n6.1.1-3ubuntu5 Copyright (c) 2000-2023'\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let health = Adapter::probe_tool("ffmpeg", None, search_path);
+        assert_eq!(health.version.as_deref(), Some("6.1.1-3ubuntu5"));
     }
 }
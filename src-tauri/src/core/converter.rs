@@ -0,0 +1,57 @@
+use crate::core::adapter::Adapter;
+use crate::core::invocation::ToolInvocation;
+use crate::error::{AppError, Result};
+use crate::types::CapabilityRecord;
+use serde_json::Value;
+use std::path::Path;
+
+/// 转换器插件 - 所有可执行转换器（内置 Rust 实现与外部工具）的统一接口
+pub trait ConverterPlugin: Send + Sync {
+    /// 转换器 ID，与能力记录的 ID 一致
+    fn id(&self) -> String {
+        self.capability().id
+    }
+
+    /// 转换器声明的能力
+    fn capability(&self) -> CapabilityRecord;
+
+    /// 将 `input` 转换为 `output`
+    fn convert(&self, input: &Path, output: &Path, params: &Value) -> Result<()>;
+}
+
+/// 外部工具转换器 - 按能力记录中的命令模板调用外部工具
+pub struct CommandConverter {
+    capability: CapabilityRecord,
+}
+
+impl CommandConverter {
+    /// 由带命令模板的能力记录创建，没有命令模板时返回 None
+    pub fn new(capability: CapabilityRecord) -> Option<Self> {
+        capability.command.as_ref()?;
+        Some(Self { capability })
+    }
+}
+
+impl ConverterPlugin for CommandConverter {
+    fn capability(&self) -> CapabilityRecord {
+        self.capability.clone()
+    }
+
+    fn convert(&self, input: &Path, output: &Path, params: &Value) -> Result<()> {
+        let command = self.capability.command.as_ref().ok_or_else(|| {
+            AppError::Internal(format!("转换器 {} 缺少命令模板", self.capability.id))
+        })?;
+        let program = self.capability.binary_path.as_deref().unwrap_or(&command.tool);
+
+        let invocation = ToolInvocation::render(command, program, input, output, params)?;
+        Adapter::execute_sandboxed(
+            &invocation,
+            input,
+            output,
+            self.capability.timeout_s,
+            self.capability.max_mem_mb,
+        )?;
+
+        Ok(())
+    }
+}
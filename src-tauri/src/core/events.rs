@@ -0,0 +1,54 @@
+use crate::types::{ArtifactReady, JobProgress, JobStateChanged};
+use tauri::{AppHandle, Emitter};
+
+/// 任务进度事件名
+pub const JOB_PROGRESS: &str = "job_progress";
+/// 任务状态变更事件名
+pub const JOB_STATE_CHANGED: &str = "job_state_changed";
+/// 产物就绪事件名
+pub const ARTIFACT_READY: &str = "artifact_ready";
+
+/// 流水线事件
+#[derive(Debug, Clone)]
+pub enum PipelineEvent {
+    JobProgress(JobProgress),
+    JobStateChanged(JobStateChanged),
+    ArtifactReady(ArtifactReady),
+}
+
+/// 事件接收端 - 流水线通过它对外发布事件，测试中可替换为收集器
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: PipelineEvent);
+}
+
+/// 不发送任何事件，用于尚未连接前端时
+pub struct NoopEventSink;
+
+impl EventSink for NoopEventSink {
+    fn emit(&self, _event: PipelineEvent) {}
+}
+
+/// 通过 Tauri 向前端窗口广播事件
+pub struct TauriEventSink {
+    app_handle: AppHandle,
+}
+
+impl TauriEventSink {
+    pub fn new(app_handle: AppHandle) -> Self {
+        Self { app_handle }
+    }
+}
+
+impl EventSink for TauriEventSink {
+    fn emit(&self, event: PipelineEvent) {
+        let result = match event {
+            PipelineEvent::JobProgress(payload) => self.app_handle.emit(JOB_PROGRESS, payload),
+            PipelineEvent::JobStateChanged(payload) => self.app_handle.emit(JOB_STATE_CHANGED, payload),
+            PipelineEvent::ArtifactReady(payload) => self.app_handle.emit(ARTIFACT_READY, payload),
+        };
+
+        if let Err(e) = result {
+            tracing::warn!("Failed to emit pipeline event: {}", e);
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use crate::error::{AppError, Result};
+use crate::types::{CommandTemplate, OptionSpec, OptionType};
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+
+/// 工具调用 - 程序与逐个传给进程的参数，不经过 shell
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolInvocation {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl ToolInvocation {
+    pub fn new<I, S>(program: impl Into<String>, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            program: program.into(),
+            args: args.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// 按命令模板渲染调用，`params` 为 `ConversionOptions.custom_params`
+    ///
+    /// 参数值必须符合模板中声明的类型与范围；字符串参数不能包含 NUL，单独成为一个参数时
+    /// 不能以 `-` 开头，避免被工具解析为选项。输入与输出路径统一转为绝对路径，同样不会以
+    /// `-` 开头。
+    pub fn render(
+        template: &CommandTemplate,
+        program: &str,
+        input: &Path,
+        output: &Path,
+        params: &Value,
+    ) -> Result<Self> {
+        let input = path_arg(input)?;
+        let output = path_arg(output)?;
+        let mut args = Vec::with_capacity(template.args.len());
+
+        for arg in &template.args {
+            let parsed = parse_arg(arg).map_err(AppError::ValidationError)?;
+            if let Some(name) = parsed.condition {
+                if !is_enabled(option_value(template, name, params)?.as_ref()) {
+                    continue;
+                }
+            }
+
+            let mut rendered = String::new();
+            for segment in &parsed.segments {
+                match segment {
+                    Segment::Literal(text) => rendered.push_str(text),
+                    Segment::Input => rendered.push_str(&input),
+                    Segment::Output => rendered.push_str(&output),
+                    Segment::Opt(name) => {
+                        let value = option_value(template, name, params)?
+                            .ok_or_else(|| AppError::ValidationError(format!("缺少参数 {}", name)))?;
+                        let text = format_value(&value);
+                        if text.contains('\0') {
+                            return Err(AppError::ValidationError(format!("参数 {} 包含非法字符", name)));
+                        }
+                        if parsed.segments.len() == 1 && text.starts_with('-') && value.is_string() {
+                            return Err(AppError::ValidationError(format!(
+                                "参数 {} 的值不能以 - 开头: {}",
+                                name, text
+                            )));
+                        }
+                        rendered.push_str(&text);
+                    }
+                }
+            }
+            args.push(rendered);
+        }
+
+        Ok(Self::new(program, args))
+    }
+}
+
+/// 转为绝对路径后的路径参数，如 `-o/tmp/x.md` 变为 `/当前目录/-o/tmp/x.md`
+fn path_arg(path: &Path) -> Result<String> {
+    let absolute = std::path::absolute(path)?;
+    let text = absolute.to_string_lossy().into_owned();
+    if text.starts_with('-') || text.contains('\0') {
+        return Err(AppError::ValidationError(format!("非法的文件路径: {}", path.display())));
+    }
+    Ok(text)
+}
+
+/// 输出可直接在 shell 中复现的命令行
+impl fmt::Display for ToolInvocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", shell_quote(&self.program))?;
+        for arg in &self.args {
+            write!(f, " {}", shell_quote(arg))?;
+        }
+        Ok(())
+    }
+}
+
+fn shell_quote(arg: &str) -> String {
+    let safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+    if safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// 校验命令模板：占位符必须可识别，引用的参数必须已声明，默认值必须符合声明
+pub fn validate_template(template: &CommandTemplate) -> std::result::Result<(), String> {
+    for (name, spec) in &template.options {
+        if spec.kind == OptionType::Enum && spec.values.is_empty() {
+            return Err(format!("枚举参数 {} 缺少 values", name));
+        }
+        if let Some(default) = &spec.default {
+            check_value(name, spec, default)?;
+        }
+    }
+
+    let mut has_input = false;
+    let mut has_output = false;
+    for arg in &template.args {
+        let parsed = parse_arg(arg)?;
+        let referenced = parsed.segments.iter().filter_map(|segment| match segment {
+            Segment::Opt(name) => Some(*name),
+            _ => None,
+        });
+        for name in parsed.condition.into_iter().chain(referenced) {
+            if !template.options.contains_key(name) {
+                return Err(format!("参数 {} 未在 command.options 中声明", name));
+            }
+        }
+
+        let unconditional = parsed.condition.is_none();
+        has_input |= unconditional && parsed.segments.contains(&Segment::Input);
+        has_output |= unconditional && parsed.segments.contains(&Segment::Output);
+    }
+
+    if !has_input {
+        return Err("command.args 缺少 {input}".to_string());
+    }
+    if !has_output {
+        return Err("command.args 缺少 {output}".to_string());
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment<'a> {
+    Literal(&'a str),
+    Input,
+    Output,
+    Opt(&'a str),
+}
+
+struct ParsedArg<'a> {
+    /// `{if:name}` 前缀引用的参数
+    condition: Option<&'a str>,
+    segments: Vec<Segment<'a>>,
+}
+
+fn parse_arg(arg: &str) -> std::result::Result<ParsedArg<'_>, String> {
+    let mut condition = None;
+    let mut segments = Vec::new();
+    let mut rest = arg;
+
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            return Err(format!("参数 \"{}\" 中的占位符未闭合", arg));
+        };
+        if start > 0 {
+            segments.push(Segment::Literal(&rest[..start]));
+        }
+
+        let placeholder = &rest[start..start + len + 1];
+        let inner = &placeholder[1..placeholder.len() - 1];
+        match inner.split_once(':') {
+            None if inner == "input" => segments.push(Segment::Input),
+            None if inner == "output" => segments.push(Segment::Output),
+            Some(("opt", name)) if is_option_name(name) => segments.push(Segment::Opt(name)),
+            Some(("if", name)) if is_option_name(name) => {
+                if condition.is_some() || !segments.is_empty() {
+                    return Err(format!("{} 只能出现在参数开头", placeholder));
+                }
+                condition = Some(name);
+            }
+            _ => return Err(format!("未知的占位符 {}", placeholder)),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Literal(rest));
+    }
+    if condition.is_some() && segments.is_empty() {
+        return Err(format!("参数 \"{}\" 只有条件没有内容", arg));
+    }
+
+    Ok(ParsedArg { condition, segments })
+}
+
+fn is_option_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// 参数值：优先取 `custom_params`，否则取默认值；值为 null 视为未提供
+fn option_value(template: &CommandTemplate, name: &str, params: &Value) -> Result<Option<Value>> {
+    let spec = template
+        .options
+        .get(name)
+        .ok_or_else(|| AppError::ValidationError(format!("参数 {} 未在 command.options 中声明", name)))?;
+
+    match params.get(name).filter(|value| !value.is_null()) {
+        Some(value) => {
+            check_value(name, spec, value).map_err(AppError::ValidationError)?;
+            Ok(Some(value.clone()))
+        }
+        None => Ok(spec.default.clone()),
+    }
+}
+
+fn check_value(name: &str, spec: &OptionSpec, value: &Value) -> std::result::Result<(), String> {
+    let valid = match spec.kind {
+        OptionType::Bool => value.is_boolean(),
+        OptionType::Integer => value.is_i64() || value.is_u64(),
+        OptionType::Number => value.is_number(),
+        OptionType::String => value.is_string(),
+        OptionType::Enum => value
+            .as_str()
+            .is_some_and(|text| spec.values.iter().any(|v| v == text)),
+    };
+    if !valid {
+        return Err(match spec.kind {
+            OptionType::Enum => format!("参数 {} 必须是 {} 之一，实际为 {}", name, spec.values.join("/"), value),
+            kind => format!("参数 {} 应为 {:?} 类型，实际为 {}", name, kind, value),
+        });
+    }
+
+    if let Some(number) = value.as_f64() {
+        let below = spec.min.is_some_and(|min| number < min);
+        let above = spec.max.is_some_and(|max| number > max);
+        if below || above {
+            return Err(format!(
+                "参数 {} 超出范围 [{}, {}]: {}",
+                name,
+                spec.min.map(|v| v.to_string()).unwrap_or_default(),
+                spec.max.map(|v| v.to_string()).unwrap_or_default(),
+                number
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn is_enabled(value: Option<&Value>) -> bool {
+    match value {
+        Some(Value::Bool(enabled)) => *enabled,
+        Some(_) => true,
+        None => false,
+    }
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn template() -> CommandTemplate {
+        toml::from_str(
+            r#"
+            tool = "magick"
+            args = ["{input}", "{if:quality}-quality", "{if:quality}{opt:quality}",
+                    "{if:strip}-strip", "-define", "webp:method={opt:method}", "{output}"]
+
+            [options.quality]
+            type = "integer"
+            min = 0
+            max = 100
+
+            [options.strip]
+            type = "bool"
+            default = false
+
+            [options.method]
+            type = "enum"
+            values = ["0", "4", "6"]
+            default = "4"
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn render(params: Value) -> Result<ToolInvocation> {
+        ToolInvocation::render(&template(), "magick", Path::new("/in/a b.png"), Path::new("/out/a.webp"), &params)
+    }
+
+    #[test]
+    fn test_render_template() {
+        assert_eq!(validate_template(&template()), Ok(()));
+
+        // 未提供的可选参数连同条件参数一起省略
+        let invocation = render(json!({})).unwrap();
+        assert_eq!(
+            invocation.args,
+            vec!["/in/a b.png", "-define", "webp:method=4", "/out/a.webp"]
+        );
+        assert_eq!(invocation.to_string(), "magick '/in/a b.png' -define webp:method=4 /out/a.webp");
+
+        let invocation = render(json!({ "quality": 80, "strip": true, "method": "6", "other": "x" })).unwrap();
+        assert_eq!(
+            invocation.args,
+            vec!["/in/a b.png", "-quality", "80", "-strip", "-define", "webp:method=6", "/out/a.webp"]
+        );
+    }
+
+    #[test]
+    fn test_render_rejects_invalid_params() {
+        let cases = [
+            (json!({ "quality": "80" }), "quality"),
+            (json!({ "quality": 101 }), "超出范围"),
+            (json!({ "quality": 8.5 }), "quality"),
+            (json!({ "strip": "yes" }), "strip"),
+            (json!({ "method": "9; rm -rf /" }), "method"),
+        ];
+        for (params, expected) in cases {
+            let error = render(params).unwrap_err().to_string();
+            assert!(error.contains(expected), "{}", error);
+        }
+
+        // 字符串参数不能伪装成选项
+        let mut template = template();
+        template.args.push("{opt:label}".to_string());
+        template.options.insert(
+            "label".to_string(),
+            OptionSpec {
+                kind: OptionType::String,
+                default: None,
+                min: None,
+                max: None,
+                values: vec![],
+            },
+        );
+        let params = json!({ "label": "--delete" });
+        let result = ToolInvocation::render(&template, "magick", Path::new("/a"), Path::new("/b"), &params);
+        assert!(matches!(result, Err(AppError::ValidationError(_))));
+        let params = json!({});
+        let error = ToolInvocation::render(&template, "magick", Path::new("/a"), Path::new("/b"), &params)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("缺少参数 label"), "{}", error);
+    }
+
+    #[test]
+    fn test_render_paths_cannot_become_options() {
+        // 以 - 开头的相对路径转为绝对路径，不会被工具当作选项
+        let cwd = std::env::current_dir().unwrap();
+        let invocation = ToolInvocation::render(
+            &template(),
+            "magick",
+            Path::new("-o/tmp/x.md"),
+            Path::new("--output=a.webp"),
+            &json!({}),
+        )
+        .unwrap();
+        assert_eq!(invocation.args[0], cwd.join("-o/tmp/x.md").to_string_lossy());
+        assert_eq!(invocation.args.last().unwrap(), &cwd.join("--output=a.webp").to_string_lossy());
+        assert!(invocation.args.iter().all(|arg| !arg.starts_with('-') || arg == "-define"));
+    }
+
+    #[test]
+    fn test_validate_template() {
+        let cases = [
+            (vec!["{input}", "{opt:missing}", "{output}"], "missing"),
+            (vec!["{input}", "x{if:strip}-strip", "{output}"], "开头"),
+            (vec!["{input}", "{if:strip}{output}"], "{output}"),
+            (vec!["{input}", "{opt}", "{output}"], "{opt}"),
+            (vec!["{input}", "{output"], "未闭合"),
+        ];
+        for (args, expected) in cases {
+            let mut template = template();
+            template.args = args.into_iter().map(String::from).collect();
+            let error = validate_template(&template).unwrap_err();
+            assert!(error.contains(expected), "{}", error);
+        }
+
+        let mut template = template();
+        template.options.get_mut("quality").unwrap().default = Some(json!(500));
+        assert!(validate_template(&template).unwrap_err().contains("超出范围"));
+    }
+}
@@ -0,0 +1,532 @@
+use crate::error::{AppError, Result};
+use crate::types::{Artifact, ConversionPath, ConversionStep, Job, LogEntry, StepState};
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::Path;
+
+/// 数据库迁移脚本，下标 + 1 即迁移后的 schema 版本 (PRAGMA user_version)
+///
+/// 只能在末尾追加新迁移，已发布的迁移不可修改。
+const MIGRATIONS: &[&str] = &[
+    // v1: 任务、步骤、产物与日志
+    r#"
+    CREATE TABLE jobs (
+        id TEXT PRIMARY KEY,
+        state TEXT NOT NULL,
+        inputs TEXT NOT NULL,
+        target_format TEXT NOT NULL,
+        recipe_id TEXT,
+        options TEXT NOT NULL,
+        progress REAL NOT NULL DEFAULT 0,
+        stage TEXT,
+        eta_ms INTEGER,
+        created_at INTEGER NOT NULL,
+        started_at INTEGER,
+        finished_at INTEGER,
+        error TEXT
+    );
+    CREATE INDEX idx_jobs_state ON jobs(state);
+
+    CREATE TABLE job_steps (
+        job_id TEXT NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+        step_index INTEGER NOT NULL,
+        converter_id TEXT NOT NULL,
+        from_format TEXT NOT NULL,
+        to_format TEXT NOT NULL,
+        quality REAL NOT NULL,
+        state TEXT NOT NULL,
+        output_path TEXT,
+        error TEXT,
+        PRIMARY KEY (job_id, step_index)
+    );
+
+    CREATE TABLE artifacts (
+        id TEXT PRIMARY KEY,
+        job_id TEXT NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+        path TEXT NOT NULL,
+        filename TEXT NOT NULL,
+        size_bytes INTEGER NOT NULL,
+        format TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_artifacts_job ON artifacts(job_id);
+
+    CREATE TABLE logs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        job_id TEXT NOT NULL REFERENCES jobs(id) ON DELETE CASCADE,
+        timestamp INTEGER NOT NULL,
+        level TEXT NOT NULL,
+        message TEXT NOT NULL,
+        context TEXT
+    );
+    CREATE INDEX idx_logs_job ON logs(job_id);
+    "#,
+    // v2: 保存完整执行路径，用于从失败步骤重试
+    r#"
+    ALTER TABLE jobs ADD COLUMN path TEXT;
+    "#,
+    // v3: 路线尝试记录
+    r#"
+    ALTER TABLE jobs ADD COLUMN attempts TEXT NOT NULL DEFAULT '[]';
+    "#,
+    // v4: 优先级与排队顺序，已有任务按创建时间排队
+    r#"
+    ALTER TABLE jobs ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE jobs ADD COLUMN queue_position INTEGER NOT NULL DEFAULT 0;
+    UPDATE jobs SET queue_position = created_at;
+    "#,
+];
+
+/// 持久化的转换步骤记录
+#[derive(Debug, Clone)]
+pub struct StepRecord {
+    pub step: ConversionStep,
+    pub state: StepState,
+    pub output_path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// 任务存储 - jobs.db 中的任务、步骤、产物与日志
+pub struct JobStore {
+    pool: Pool<SqliteConnectionManager>,
+}
+
+impl JobStore {
+    /// 打开 (或创建) 数据库并执行未完成的迁移
+    pub fn open(path: &Path) -> Result<Self> {
+        let manager = SqliteConnectionManager::file(path)
+            .with_init(|conn| conn.execute_batch("PRAGMA foreign_keys = ON; PRAGMA journal_mode = WAL;"));
+        let pool = Pool::builder()
+            .max_size(4)
+            .build(manager)
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let store = Self { pool };
+        store.migrate()?;
+
+        tracing::info!("Job store opened at {:?} (schema v{})", path, store.schema_version()?);
+        Ok(store)
+    }
+
+    fn conn(&self) -> Result<PooledConnection<SqliteConnectionManager>> {
+        self.pool
+            .get()
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 当前 schema 版本
+    pub fn schema_version(&self) -> Result<usize> {
+        let version: i64 = self.conn()?.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        Ok(version as usize)
+    }
+
+    /// 依次执行尚未应用的迁移，每个迁移在独立事务中完成
+    fn migrate(&self) -> Result<()> {
+        let mut conn = self.conn()?;
+        let current = self.schema_version()?;
+        if current > MIGRATIONS.len() {
+            return Err(AppError::DatabaseError(format!(
+                "数据库版本 v{} 高于当前应用支持的 v{}",
+                current,
+                MIGRATIONS.len()
+            )));
+        }
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(current) {
+            let tx = conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", (index + 1) as i64)?;
+            tx.commit()?;
+            tracing::info!("Applied job store migration v{}", index + 1);
+        }
+
+        Ok(())
+    }
+
+    /// 插入或更新任务
+    pub fn save_job(&self, job: &Job) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT INTO jobs (id, state, inputs, target_format, recipe_id, options, progress, stage,
+                               eta_ms, created_at, started_at, finished_at, error, attempts, priority,
+                               queue_position)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+             ON CONFLICT(id) DO UPDATE SET
+                state = excluded.state, progress = excluded.progress, stage = excluded.stage,
+                eta_ms = excluded.eta_ms, started_at = excluded.started_at,
+                finished_at = excluded.finished_at, error = excluded.error,
+                attempts = excluded.attempts, priority = excluded.priority,
+                queue_position = excluded.queue_position",
+            params![
+                job.id,
+                to_text(&job.state)?,
+                serde_json::to_string(&job.inputs)?,
+                job.target_format,
+                job.recipe_id,
+                serde_json::to_string(&job.options)?,
+                job.progress,
+                job.stage,
+                job.eta_ms.map(|eta| eta as i64),
+                job.created_at,
+                job.started_at,
+                job.finished_at,
+                job.error,
+                serde_json::to_string(&job.attempts)?,
+                job.priority,
+                job.queue_position,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 按创建顺序加载所有任务
+    pub fn load_jobs(&self) -> Result<Vec<Job>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, state, inputs, target_format, recipe_id, options, progress, stage, eta_ms,
+                    created_at, started_at, finished_at, error, attempts, priority, queue_position
+             FROM jobs ORDER BY created_at, rowid",
+        )?;
+        let jobs = stmt
+            .query_map([], |row| Ok(job_from_row(row)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        jobs.into_iter().collect()
+    }
+
+    /// 记录任务的执行路径，替换之前的步骤记录
+    pub fn save_path(&self, job_id: &str, path: &ConversionPath) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "UPDATE jobs SET path = ?2 WHERE id = ?1",
+            params![job_id, serde_json::to_string(path)?],
+        )?;
+        tx.execute("DELETE FROM job_steps WHERE job_id = ?1", params![job_id])?;
+        for (index, step) in path.steps.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO job_steps (job_id, step_index, converter_id, from_format, to_format, quality, state)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    job_id,
+                    index as i64,
+                    step.converter_id,
+                    step.from_format,
+                    step.to_format,
+                    step.quality,
+                    to_text(&StepState::Pending)?,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 加载任务的执行路径
+    pub fn load_path(&self, job_id: &str) -> Result<Option<ConversionPath>> {
+        let path: Option<String> = self
+            .conn()?
+            .query_row("SELECT path FROM jobs WHERE id = ?1", params![job_id], |row| row.get(0))
+            .optional()?
+            .flatten();
+
+        Ok(path.map(|p| serde_json::from_str(&p)).transpose()?)
+    }
+
+    /// 更新单个步骤的状态
+    pub fn update_step(
+        &self,
+        job_id: &str,
+        index: usize,
+        state: StepState,
+        output_path: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        self.conn()?.execute(
+            "UPDATE job_steps SET state = ?3, output_path = ?4, error = ?5
+             WHERE job_id = ?1 AND step_index = ?2",
+            params![job_id, index as i64, to_text(&state)?, output_path, error],
+        )?;
+        Ok(())
+    }
+
+    /// 加载任务的步骤记录
+    pub fn load_steps(&self, job_id: &str) -> Result<Vec<StepRecord>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT converter_id, from_format, to_format, quality, state, output_path, error
+             FROM job_steps WHERE job_id = ?1 ORDER BY step_index",
+        )?;
+        let steps = stmt
+            .query_map(params![job_id], |row| {
+                let state: String = row.get(4)?;
+                Ok((
+                    ConversionStep {
+                        converter_id: row.get(0)?,
+                        from_format: row.get(1)?,
+                        to_format: row.get(2)?,
+                        quality: row.get(3)?,
+                    },
+                    state,
+                    row.get(5)?,
+                    row.get(6)?,
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        steps
+            .into_iter()
+            .map(|(step, state, output_path, error)| {
+                Ok(StepRecord {
+                    step,
+                    state: from_text(&state)?,
+                    output_path,
+                    error,
+                })
+            })
+            .collect()
+    }
+
+    /// 保存产物
+    pub fn save_artifact(&self, artifact: &Artifact) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT OR REPLACE INTO artifacts (id, job_id, path, filename, size_bytes, format, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                artifact.id,
+                artifact.job_id,
+                artifact.path,
+                artifact.filename,
+                artifact.size_bytes as i64,
+                artifact.format,
+                artifact.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 加载所有产物
+    pub fn load_artifacts(&self) -> Result<Vec<Artifact>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, job_id, path, filename, size_bytes, format, created_at
+             FROM artifacts ORDER BY created_at, rowid",
+        )?;
+        let artifacts = stmt
+            .query_map([], |row| {
+                Ok(Artifact {
+                    id: row.get(0)?,
+                    job_id: row.get(1)?,
+                    path: row.get(2)?,
+                    filename: row.get(3)?,
+                    size_bytes: row.get::<_, i64>(4)? as u64,
+                    format: row.get(5)?,
+                    created_at: row.get(6)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(artifacts)
+    }
+
+    /// 追加任务日志
+    pub fn append_log(&self, job_id: &str, entry: &LogEntry) -> Result<()> {
+        let context = entry.context.as_ref().map(serde_json::to_string).transpose()?;
+        self.conn()?.execute(
+            "INSERT INTO logs (job_id, timestamp, level, message, context) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![job_id, entry.timestamp, to_text(&entry.level)?, entry.message, context],
+        )?;
+        Ok(())
+    }
+
+    /// 读取任务日志，`cursor` 为已读取的条数
+    pub fn list_logs(&self, job_id: &str, cursor: Option<i64>) -> Result<Vec<LogEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT timestamp, level, message, context FROM logs
+             WHERE job_id = ?1 ORDER BY id LIMIT -1 OFFSET ?2",
+        )?;
+        let rows = stmt
+            .query_map(params![job_id, cursor.unwrap_or(0).max(0)], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        rows.into_iter()
+            .map(|(timestamp, level, message, context)| {
+                Ok(LogEntry {
+                    timestamp,
+                    level: from_text(&level)?,
+                    message,
+                    context: context.map(|c| serde_json::from_str(&c)).transpose()?,
+                })
+            })
+            .collect()
+    }
+}
+
+fn job_from_row(row: &Row) -> Result<Job> {
+    let state: String = row.get(1)?;
+    let inputs: String = row.get(2)?;
+    let options: String = row.get(5)?;
+    let attempts: String = row.get(13)?;
+
+    Ok(Job {
+        id: row.get(0)?,
+        state: from_text(&state)?,
+        inputs: serde_json::from_str(&inputs)?,
+        target_format: row.get(3)?,
+        recipe_id: row.get(4)?,
+        options: serde_json::from_str(&options)?,
+        progress: row.get(6)?,
+        stage: row.get(7)?,
+        eta_ms: row.get::<_, Option<i64>>(8)?.map(|eta| eta as u64),
+        created_at: row.get(9)?,
+        started_at: row.get(10)?,
+        finished_at: row.get(11)?,
+        error: row.get(12)?,
+        attempts: serde_json::from_str(&attempts)?,
+        priority: row.get(14)?,
+        queue_position: row.get(15)?,
+    })
+}
+
+/// 枚举按其 serde 表示存为文本
+fn to_text<T: Serialize>(value: &T) -> Result<String> {
+    match serde_json::to_value(value)? {
+        serde_json::Value::String(text) => Ok(text),
+        other => Ok(other.to_string()),
+    }
+}
+
+fn from_text<T: DeserializeOwned>(text: &str) -> Result<T> {
+    Ok(serde_json::from_value(serde_json::Value::String(text.to_string()))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::*;
+
+    #[test]
+    fn test_migrations_and_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jobs.db");
+
+        let store = JobStore::open(&path).unwrap();
+        assert_eq!(store.schema_version().unwrap(), MIGRATIONS.len());
+
+        let mut job = Job::new(
+            vec!["/tmp/doc.md".to_string()],
+            "html".to_string(),
+            None,
+            serde_json::json!({ "quality_priority": "speed" }),
+        );
+        store.save_job(&job).unwrap();
+        job.state = JobState::Failed;
+        job.error = Some("boom".to_string());
+        job.eta_ms = Some(1500);
+        job.priority = 5;
+        job.queue_position = -3;
+        store.save_job(&job).unwrap();
+
+        let conversion = ConversionPath {
+            steps: vec![ConversionStep {
+                converter_id: "md-html".to_string(),
+                from_format: "text/markdown".to_string(),
+                to_format: "text/html".to_string(),
+                quality: 0.9,
+            }],
+            total_quality: 0.9,
+            estimated_time_ms: 500,
+            risks: vec![],
+            score: 0.5,
+            preserve: PreserveFeatures {
+                styles: PreserveLevel::True,
+                links: PreserveLevel::True,
+                footnotes: PreserveLevel::False,
+                headers: PreserveLevel::True,
+                lists: PreserveLevel::True,
+                tables: PreserveLevel::Partial,
+                images: PreserveLevel::True,
+                formulas: PreserveLevel::False,
+            },
+            losses: vec![],
+            missing_requirements: vec![],
+        };
+        store.save_path(&job.id, &conversion).unwrap();
+        store
+            .update_step(&job.id, 0, StepState::Failed, Some("/ws/step-1.html"), Some("boom"))
+            .unwrap();
+        store
+            .append_log(
+                &job.id,
+                &LogEntry {
+                    timestamp: 1,
+                    level: LogLevel::Error,
+                    message: "step failed".to_string(),
+                    context: Some(serde_json::json!({ "step": 1 })),
+                },
+            )
+            .unwrap();
+        drop(store);
+
+        // 重新打开时不会重复执行迁移
+        let store = JobStore::open(&path).unwrap();
+        let jobs = store.load_jobs().unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].state, JobState::Failed);
+        assert_eq!(jobs[0].error.as_deref(), Some("boom"));
+        assert_eq!(jobs[0].eta_ms, Some(1500));
+        assert_eq!(jobs[0].options["quality_priority"], "speed");
+        assert_eq!((jobs[0].priority, jobs[0].queue_position), (5, -3));
+
+        assert_eq!(store.load_path(&job.id).unwrap().unwrap().estimated_time_ms, 500);
+        let steps = store.load_steps(&job.id).unwrap();
+        assert_eq!(steps[0].state, StepState::Failed);
+        assert_eq!(steps[0].output_path.as_deref(), Some("/ws/step-1.html"));
+
+        let logs = store.list_logs(&job.id, None).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert!(matches!(logs[0].level, LogLevel::Error));
+        assert!(store.list_logs(&job.id, Some(1)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_load_path_saved_before_scoring_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = JobStore::open(&dir.path().join("jobs.db")).unwrap();
+        let job = Job::new(vec!["/tmp/doc.md".to_string()], "html".to_string(), None, serde_json::Value::Null);
+        store.save_job(&job).unwrap();
+
+        // 早期版本记录的路径没有得分、保留能力与缺失依赖
+        let legacy = serde_json::json!({
+            "steps": [{
+                "converter_id": "md-html",
+                "from_format": "text/markdown",
+                "to_format": "text/html",
+                "quality": 0.9,
+            }],
+            "total_quality": 0.9,
+            "estimated_time_ms": 500,
+            "risks": [],
+        });
+        store
+            .conn()
+            .unwrap()
+            .execute("UPDATE jobs SET path = ?1 WHERE id = ?2", params![legacy.to_string(), job.id])
+            .unwrap();
+
+        let path = store.load_path(&job.id).unwrap().unwrap();
+        assert_eq!(path.steps[0].converter_id, "md-html");
+        assert_eq!(path.score, 0.0);
+        assert_eq!(path.preserve, PreserveFeatures::default());
+        assert!(path.losses.is_empty() && path.missing_requirements.is_empty());
+    }
+}
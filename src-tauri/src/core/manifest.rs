@@ -0,0 +1,279 @@
+use crate::core::invocation;
+use crate::core::version::VersionRange;
+use crate::error::{AppError, Result};
+use crate::types::CapabilityRecord;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// 转换器清单加载器 - 从 TOML/JSON 文件读取能力声明
+pub struct ManifestLoader;
+
+impl ManifestLoader {
+    /// 内置清单目录：打包后位于资源目录，调试构建中回退到源码目录
+    ///
+    /// 发布构建中资源目录缺失时返回错误，避免读取构建机器上的路径。
+    pub fn builtin_dir(app_handle: &AppHandle) -> Result<PathBuf> {
+        let bundled = app_handle.path().resource_dir().map(|dir| dir.join("converters"));
+        match bundled {
+            Ok(dir) if dir.is_dir() => Ok(dir),
+            #[cfg(debug_assertions)]
+            _ => Ok(Path::new(env!("CARGO_MANIFEST_DIR")).join("converters")),
+            #[cfg(not(debug_assertions))]
+            Ok(dir) => Err(AppError::Internal(format!("内置转换器清单目录不存在: {}", dir.display()))),
+            #[cfg(not(debug_assertions))]
+            Err(e) => Err(AppError::Internal(e.to_string())),
+        }
+    }
+
+    /// 用户清单目录
+    pub fn user_dir(app_handle: &AppHandle) -> Result<PathBuf> {
+        let app_data_dir = app_handle
+            .path()
+            .app_data_dir()
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+
+        Ok(app_data_dir.join("converters"))
+    }
+
+    /// 加载目录下所有 `.toml` / `.json` 清单
+    ///
+    /// 单个清单无效不影响其它清单，错误与有效记录一并返回。
+    pub fn load_dir(dir: &Path) -> (Vec<CapabilityRecord>, Vec<AppError>) {
+        let mut records = Vec::new();
+        let mut errors = Vec::new();
+
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (records, errors),
+            Err(e) => {
+                errors.push(AppError::IoError(format!("{}: {}", dir.display(), e)));
+                return (records, errors);
+            }
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                matches!(
+                    path.extension().and_then(|ext| ext.to_str()),
+                    Some("toml") | Some("json")
+                )
+            })
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            match Self::load_file(&path) {
+                Ok(record) => {
+                    if records.iter().any(|r: &CapabilityRecord| r.id == record.id) {
+                        errors.push(AppError::ValidationError(format!(
+                            "{}: 重复的转换器 ID \"{}\"",
+                            path.display(),
+                            record.id
+                        )));
+                    } else {
+                        records.push(record);
+                    }
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+
+        (records, errors)
+    }
+
+    /// 加载并校验单个清单文件
+    pub fn load_file(path: &Path) -> Result<CapabilityRecord> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| AppError::IoError(format!("{}: {}", path.display(), e)))?;
+
+        let record: CapabilityRecord = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&content).map_err(|e| e.to_string()),
+            Some("json") => serde_json::from_str(&content).map_err(|e| e.to_string()),
+            _ => Err("不支持的清单格式，仅支持 .toml 与 .json".to_string()),
+        }
+        .map_err(|e| AppError::ValidationError(format!("{}: {}", path.display(), e)))?;
+
+        Self::validate(&record)
+            .map_err(|e| AppError::ValidationError(format!("{}: {}", path.display(), e)))?;
+
+        Ok(record)
+    }
+
+    /// 校验能力声明的取值范围与命令模板
+    pub fn validate(record: &CapabilityRecord) -> std::result::Result<(), String> {
+        if record.id.trim().is_empty() {
+            return Err("id 不能为空".to_string());
+        }
+        if record.inputs.is_empty() || record.outputs.is_empty() {
+            return Err("inputs 与 outputs 至少各包含一项".to_string());
+        }
+        for spec in record.inputs.iter().chain(record.outputs.iter()) {
+            if !spec.mime.contains('/') {
+                return Err(format!("无效的 MIME 类型 \"{}\"", spec.mime));
+            }
+        }
+
+        let ratios = [
+            ("quality", record.quality),
+            ("speed", record.speed),
+            ("cost.cpu", record.cost.cpu),
+            ("cost.memory", record.cost.memory),
+            ("cost.gpu", record.cost.gpu),
+        ];
+        for (field, value) in ratios {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("{} 必须在 0 到 1 之间，实际为 {}", field, value));
+            }
+        }
+
+        if record.parallelism == 0 {
+            return Err("parallelism 必须大于 0".to_string());
+        }
+
+        let requires = &record.requires;
+        for tool in requires.min_version.keys().chain(requires.max_version.keys()) {
+            if !requires.tools.contains(tool) {
+                return Err(format!("版本要求中的工具 \"{}\" 未在 requires.tools 中声明", tool));
+            }
+            VersionRange::for_tool(requires, tool).map_err(|e| format!("工具 {} 的版本要求无效: {}", tool, e))?;
+        }
+
+        if let Some(command) = &record.command {
+            if !record.requires.tools.contains(&command.tool) {
+                return Err(format!(
+                    "command.tool \"{}\" 未在 requires.tools 中声明",
+                    command.tool
+                ));
+            }
+
+            invocation::validate_template(command)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+id = "test-md-html"
+name = "Test Markdown to HTML"
+version = "1.0.0"
+license = "MIT"
+vendor = "test"
+quality = 0.9
+speed = 0.8
+risks = []
+parallelism = 1
+streaming = false
+
+[[inputs]]
+mime = "text/markdown"
+extensions = ["md"]
+
+[[outputs]]
+mime = "text/html"
+extensions = ["html"]
+
+[cost]
+cpu = 0.3
+memory = 0.2
+gpu = 0.0
+
+[requires]
+tools = ["pandoc"]
+lang_packs = []
+fonts = []
+
+[preserve]
+styles = "true"
+links = "true"
+footnotes = "partial"
+headers = "true"
+lists = "true"
+tables = "true"
+images = "true"
+formulas = "false"
+
+[command]
+tool = "pandoc"
+args = ["{input}", "-o", "{output}"]
+"#;
+
+    #[test]
+    fn test_builtin_manifests_are_valid() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("converters");
+        let (records, errors) = ManifestLoader::load_dir(&dir);
+
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(records.iter().any(|r| r.id == "pandoc-docx-md"));
+        assert!(records.iter().all(|r| r.command.is_some()));
+    }
+
+    #[test]
+    fn test_load_toml_and_json() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.toml"), MANIFEST).unwrap();
+
+        let mut json: serde_json::Value = toml::from_str(MANIFEST).unwrap();
+        json["id"] = "test-json".into();
+        std::fs::write(dir.path().join("b.json"), json.to_string()).unwrap();
+        std::fs::write(dir.path().join("README.md"), "ignored").unwrap();
+
+        let (records, errors) = ManifestLoader::load_dir(dir.path());
+        assert!(errors.is_empty(), "{:?}", errors);
+        let ids: Vec<_> = records.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["test-md-html", "test-json"]);
+        assert_eq!(records[0].timeout_s, None);
+        assert_eq!(records[0].command.as_ref().unwrap().tool, "pandoc");
+    }
+
+    #[test]
+    fn test_primary_tool_is_command_tool() {
+        let manifest = MANIFEST.replace("tools = [\"pandoc\"]", "tools = [\"rsvg-convert\", \"pandoc\"]");
+        let record: CapabilityRecord = toml::from_str(&manifest).unwrap();
+        ManifestLoader::validate(&record).unwrap();
+        assert_eq!(record.primary_tool().map(String::as_str), Some("pandoc"));
+
+        let mut record = record;
+        record.command = None;
+        assert_eq!(record.primary_tool().map(String::as_str), Some("rsvg-convert"));
+    }
+
+    #[test]
+    fn test_invalid_manifests_report_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let cases = [
+            ("quality.toml", MANIFEST.replace("quality = 0.9", "quality = 1.5"), "quality"),
+            ("tool.toml", MANIFEST.replace("tool = \"pandoc\"", "tool = \"magick\""), "magick"),
+            ("placeholder.toml", MANIFEST.replace("\"-o\"", "\"{opt}\""), "{opt}"),
+            ("output.toml", MANIFEST.replace("\"{output}\"", "\"out.html\""), "{output}"),
+            ("missing.toml", MANIFEST.replace("speed = 0.8\n", ""), "speed"),
+            (
+                "version-tool.toml",
+                MANIFEST.replace("fonts = []", "fonts = []\nmin_version = { magick = \"7.0\" }"),
+                "magick",
+            ),
+            (
+                "version-range.toml",
+                MANIFEST.replace(
+                    "fonts = []",
+                    "fonts = []\nmin_version = { pandoc = \"3.1\" }\nmax_version = { pandoc = \"3.0\" }",
+                ),
+                "高于",
+            ),
+        ];
+
+        for (name, content, expected) in cases {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+
+            let error = ManifestLoader::load_file(&path).unwrap_err().to_string();
+            assert!(error.contains(name), "{}", error);
+            assert!(error.contains(expected), "{}", error);
+        }
+    }
+}
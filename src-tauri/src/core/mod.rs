@@ -5,9 +5,27 @@ pub mod adapter;
 pub mod storage;
 pub mod quality;
 pub mod detector;
+pub mod settings;
+pub mod manifest;
+pub mod converter;
+pub mod wasm;
+pub mod plugin;
+pub mod events;
+pub mod job_store;
+pub mod process;
+pub mod scheduler;
+pub mod queue;
+pub mod sandbox;
+pub mod invocation;
+pub mod progress;
+pub mod version;
 
 pub use registry::Registry;
 pub use planner::Planner;
 pub use pipeline::Pipeline;
 pub use storage::Storage;
 pub use detector::FileDetector;
+pub use settings::SettingsStore;
+pub use converter::ConverterPlugin;
+pub use plugin::PluginManager;
+pub use job_store::JobStore;
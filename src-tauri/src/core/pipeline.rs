@@ -1,77 +1,1126 @@
-use crate::error::Result;
-use crate::types::{Job, JobState, ConversionPath};
+use crate::core::detector::FileDetector;
+use crate::core::events::{EventSink, NoopEventSink, PipelineEvent};
+use crate::core::job_store::JobStore;
+use crate::core::planner::Planner;
+use crate::core::process::{CancellationToken, ProcessTable};
+use crate::core::progress::{self, ProgressSink, ToolProgress};
+use crate::core::queue::JobQueue;
+use crate::core::quality::{DefaultQualityCheck, QualityAssessor, QualityCheck};
+use crate::core::registry::Registry;
+use crate::core::scheduler::{Scheduler, SchedulerLimits, StepDemand};
+use crate::core::settings::SettingsStore;
+use crate::core::storage::Storage;
+use crate::error::{AppError, Result};
+use crate::types::{
+    Artifact, ArtifactReady, AttemptOutcome, ConversionOptions, ConversionPath, ConversionStep, Job, JobProgress,
+    JobState, JobStateChanged, LogEntry, LogLevel, PathAttempt, RunningJobRecovery, StepState,
+};
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Notify, RwLock};
+
+/// 取消任务后等待工具进程自行退出的时间，超时后强制结束
+const CANCEL_GRACE_PERIOD: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// 步骤在路线中的位置，用于把步骤内进度换算为任务进度
+#[derive(Debug, Clone, Copy)]
+pub struct StepSpan {
+    pub index: usize,
+    pub total: usize,
+    /// 整条路线的预计耗时
+    pub estimated_time_ms: u64,
+}
+
+impl StepSpan {
+    /// 步骤完成 `fraction` 时的任务进度
+    fn progress(&self, fraction: f32) -> f32 {
+        (self.index as f32 + fraction.clamp(0.0, 1.0)) / self.total.max(1) as f32
+    }
+
+    /// 步骤完成 `fraction` 时的剩余时间
+    fn eta_ms(&self, fraction: f32) -> u64 {
+        (self.estimated_time_ms as f32 * (1.0 - self.progress(fraction))) as u64
+    }
+}
+
+/// 执行步骤时检查任务是否暂停的间隔，暂停期间步骤让出调度资源
+const PAUSE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// 全局流水线实例
+static PIPELINE: Lazy<Arc<Pipeline>> = Lazy::new(|| Arc::new(Pipeline::new()));
 
 /// 流水线执行器 - DAG 调度、并发、重试、回退
 pub struct Pipeline {
+    registry: Arc<Registry>,
     jobs: Arc<RwLock<Vec<Job>>>,
+    artifacts: RwLock<Vec<Artifact>>,
+    /// 未完成任务的执行路径，重试时沿用
+    plans: RwLock<HashMap<String, ConversionPath>>,
+    /// 排队中的任务，按优先级与排队顺序分派
+    queue: std::sync::Mutex<JobQueue>,
+    /// 有新任务入队时唤醒工作循环
+    queued: Notify,
+    /// 任务恢复或取消时唤醒等待中的执行
+    resumed: Notify,
+    /// 步骤级资源调度
+    scheduler: Arc<Scheduler>,
+    /// 下一个提交的任务的排队顺序，单调递增
+    next_position: AtomicI64,
+    /// 已交给执行的任务，其中未暂停的数量不超过 `max_concurrent_jobs`
+    dispatched: std::sync::Mutex<HashSet<String>>,
+    /// 执行中任务的取消令牌
+    cancellations: std::sync::Mutex<HashMap<String, CancellationToken>>,
+    events: std::sync::RwLock<Arc<dyn EventSink>>,
+    quality: std::sync::RwLock<Arc<dyn QualityCheck>>,
+    /// 任务数据库，未连接时任务只保存在内存中
+    store: std::sync::RwLock<Option<Arc<JobStore>>>,
+    /// 任务工作区的根目录，未设置时使用存储管理器的工作区
+    workspace_dir: std::sync::RwLock<Option<PathBuf>>,
 }
 
 impl Pipeline {
     pub fn new() -> Self {
+        Self::with_registry(Registry::global())
+    }
+
+    /// 使用指定注册表创建流水线
+    pub fn with_registry(registry: Arc<Registry>) -> Self {
         Self {
+            registry,
             jobs: Arc::new(RwLock::new(Vec::new())),
+            artifacts: RwLock::new(Vec::new()),
+            plans: RwLock::new(HashMap::new()),
+            queue: std::sync::Mutex::new(JobQueue::new()),
+            queued: Notify::new(),
+            resumed: Notify::new(),
+            scheduler: Arc::new(Scheduler::new(SchedulerLimits::default())),
+            next_position: AtomicI64::new(0),
+            dispatched: std::sync::Mutex::new(HashSet::new()),
+            cancellations: std::sync::Mutex::new(HashMap::new()),
+            events: std::sync::RwLock::new(Arc::new(NoopEventSink)),
+            quality: std::sync::RwLock::new(Arc::new(DefaultQualityCheck)),
+            store: std::sync::RwLock::new(None),
+            workspace_dir: std::sync::RwLock::new(None),
+        }
+    }
+
+    /// 连接任务数据库并恢复上次运行留下的任务
+    ///
+    /// 仍处于 `Running` 的任务说明应用在执行中退出，按 `recovery` 标记为失败或重新排队；
+    /// 执行中暂停的任务重新排队时保持暂停，恢复后从头执行。连接前已提交的任务会一并写入数据库，
+    /// 排在恢复的任务之后。
+    pub async fn attach_store(&self, store: Arc<JobStore>, recovery: RunningJobRecovery) -> Result<()> {
+        let mut restored = store.load_jobs()?;
+        let interrupted = |job: &Job| {
+            job.state == JobState::Running || (job.state == JobState::Paused && job.started_at.is_some())
+        };
+        for job in restored.iter_mut().filter(|j| interrupted(j)) {
+            match recovery {
+                RunningJobRecovery::Fail => {
+                    job.state = JobState::Failed;
+                    job.error = Some("应用退出时任务仍在运行".to_string());
+                    job.finished_at = Some(chrono::Utc::now().timestamp_millis());
+                }
+                RunningJobRecovery::Requeue => {
+                    if job.state == JobState::Running {
+                        job.state = JobState::Queued;
+                    }
+                    job.progress = 0.0;
+                    job.stage = None;
+                    job.eta_ms = None;
+                    job.started_at = None;
+                }
+            }
+            store.save_job(job)?;
+            tracing::info!("Recovered interrupted job {} as {:?}", job.id, job.state);
+        }
+        let restored_artifacts = store.load_artifacts()?;
+
+        let mut jobs = self.jobs.write().await;
+        // 排队顺序接着数据库中的最大值继续分配
+        let offset = restored.iter().map(|j| j.queue_position + 1).max().unwrap_or(0);
+        self.next_position.fetch_add(offset, Ordering::SeqCst);
+        for job in jobs.iter_mut() {
+            job.queue_position += offset;
+            store.save_job(job)?;
+        }
+        restored.retain(|r| !jobs.iter().any(|j| j.id == r.id));
+        let pending = restored.len();
+        jobs.splice(0..0, restored);
+        let has_queued = {
+            let mut queue = self.queue.lock().unwrap();
+            for job in jobs.iter().filter(|j| j.state == JobState::Queued) {
+                queue.push(job);
+            }
+            !queue.is_empty()
+        };
+        drop(jobs);
+
+        let mut artifacts = self.artifacts.write().await;
+        for artifact in artifacts.iter() {
+            store.save_artifact(artifact)?;
+        }
+        artifacts.splice(0..0, restored_artifacts);
+        drop(artifacts);
+
+        *self.store.write().unwrap() = Some(store);
+        if has_queued {
+            self.queued.notify_one();
         }
+
+        tracing::info!("Restored {} jobs from job store", pending);
+        Ok(())
+    }
+
+    /// 写入任务数据库，写入失败只记录警告，不影响任务执行
+    fn persist(&self, write: impl FnOnce(&JobStore) -> Result<()>) {
+        let store = self.store.read().unwrap().clone();
+        if let Some(store) = store {
+            if let Err(e) = write(&store) {
+                tracing::warn!("Failed to persist job state: {}", e);
+            }
+        }
+    }
+
+    /// 记录任务日志
+    fn log(&self, job_id: &str, level: LogLevel, message: String) {
+        let entry = LogEntry {
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            level,
+            message,
+            context: None,
+        };
+        self.persist(|store| store.append_log(job_id, &entry));
+    }
+
+    /// 设置事件接收端
+    pub fn set_event_sink(&self, sink: Arc<dyn EventSink>) {
+        *self.events.write().unwrap() = sink;
+    }
+
+    /// 设置并发与资源限制
+    pub fn set_limits(&self, limits: SchedulerLimits) {
+        self.scheduler.set_limits(limits);
+        self.queued.notify_one();
+    }
+
+    /// 设置发布产物前使用的质量检查
+    pub fn set_quality_check(&self, check: Arc<dyn QualityCheck>) {
+        *self.quality.write().unwrap() = check;
+    }
+
+    /// 设置任务工作区的根目录
+    #[cfg(test)]
+    pub fn set_workspace_dir(&self, dir: PathBuf) {
+        *self.workspace_dir.write().unwrap() = Some(dir);
+    }
+
+    /// 创建任务工作区，用于存放各步骤的中间文件
+    fn job_workspace(&self, job_id: &str) -> Result<PathBuf> {
+        let root = self.workspace_dir.read().unwrap().clone();
+        match root {
+            Some(root) => {
+                let dir = root.join(job_id);
+                std::fs::create_dir_all(&dir)?;
+                Ok(dir)
+            }
+            None => Storage::global().job_workspace(job_id),
+        }
+    }
+
+    fn emit(&self, event: PipelineEvent) {
+        let sink = self.events.read().unwrap().clone();
+        sink.emit(event);
+    }
+
+    /// 获取全局流水线实例
+    pub fn global() -> Arc<Pipeline> {
+        PIPELINE.clone()
     }
 
     /// 提交任务到队列
     pub async fn submit(&self, mut job: Job) -> Result<String> {
         let job_id = job.id.clone();
         job.state = JobState::Queued;
+        job.queue_position = self.next_position.fetch_add(1, Ordering::SeqCst);
 
+        self.persist(|store| store.save_job(&job));
         let mut jobs = self.jobs.write().await;
+        self.queue.lock().unwrap().push(&job);
         jobs.push(job);
+        drop(jobs);
+        self.queued.notify_one();
 
         tracing::info!("Job {} submitted to queue", job_id);
         Ok(job_id)
     }
 
-    /// 执行任务
+    /// 后台工作循环：按优先级分派排队任务，同时执行的任务数不超过 `max_concurrent_jobs`，
+    /// 没有可分派的任务时等待新任务入队或已有任务结束
+    ///
+    /// 已暂停的任务不占用并发名额。
+    pub async fn run_worker(self: Arc<Self>) {
+        tracing::info!("Pipeline worker started");
+
+        loop {
+            let next = {
+                let jobs = self.jobs.read().await;
+                let mut dispatched = self.dispatched.lock().unwrap();
+                let mut queue = self.queue.lock().unwrap();
+                let active = dispatched
+                    .iter()
+                    .filter(|id| jobs.iter().any(|j| &j.id == *id && j.state != JobState::Paused))
+                    .count();
+                let mut next = None;
+                while next.is_none() && active < self.scheduler.limits().max_concurrent_jobs {
+                    let Some(job_id) = queue.pop() else {
+                        break;
+                    };
+                    next = jobs
+                        .iter()
+                        .find(|j| j.id == job_id && j.state == JobState::Queued && !dispatched.contains(&j.id))
+                        .cloned();
+                }
+                if let Some(job) = &next {
+                    dispatched.insert(job.id.clone());
+                }
+                next
+            };
+
+            match next {
+                Some(job) => {
+                    let pipeline = self.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = pipeline.run_job(&job).await {
+                            tracing::error!("Job {} failed: {}", job.id, e);
+                        }
+                        pipeline.dispatched.lock().unwrap().remove(&job.id);
+                        pipeline.queued.notify_one();
+                    });
+                }
+                None => self.queued.notified().await,
+            }
+        }
+    }
+
+    /// 为任务规划候选路径并依次尝试，规划失败时任务直接标记为失败
+    ///
+    /// 重试或重启恢复的任务沿用之前的执行路径。
+    async fn run_job(&self, job: &Job) -> Result<()> {
+        if let Some(path) = self.saved_plan(&job.id).await {
+            let route = job.attempts.last().map(|a| a.route).unwrap_or(1);
+            return self.execute_routes(&job.id, vec![(route, path)]).await;
+        }
+
+        let paths = match self.plan_job(job) {
+            Ok(paths) => paths,
+            Err(e) => {
+                self.fail_job(&job.id, e.to_string()).await?;
+                return Err(e);
+            }
+        };
+        self.execute_routes(&job.id, (1..).zip(paths).collect()).await
+    }
+
+    /// 之前执行时使用的路径，内存中没有时从任务数据库读取
+    async fn saved_plan(&self, job_id: &str) -> Option<ConversionPath> {
+        if let Some(path) = self.plans.read().await.get(job_id) {
+            return Some(path.clone());
+        }
+
+        let store = self.store.read().unwrap().clone()?;
+        match store.load_path(job_id) {
+            Ok(path) => path,
+            Err(e) => {
+                tracing::warn!("Failed to load saved path for job {}: {}", job_id, e);
+                None
+            }
+        }
+    }
+
+    /// 规划从输入文件到目标格式的可执行路径，按评分从高到低排列
+    fn plan_job(&self, job: &Job) -> Result<Vec<ConversionPath>> {
+        let input = job
+            .inputs
+            .first()
+            .ok_or_else(|| AppError::ValidationError("任务没有输入文件".to_string()))?;
+        let detection = FileDetector::detect(Path::new(input))?;
+        let options = job_options(job)?;
+
+        let planner = Planner::with_registry(self.registry.clone());
+        let paths = Planner::by_detected_source(&detection, |source| {
+            Ok(planner
+                .plan(source, &job.target_format, 0.0, &options)?
+                .into_iter()
+                .filter(|p| p.missing_requirements.is_empty())
+                .collect())
+        })?;
+        if paths.is_empty() {
+            return Err(AppError::ValidationError(format!(
+                "没有从 {} 到 {} 的可执行转换路径",
+                detection.mime_type, job.target_format
+            )));
+        }
+
+        Ok(paths)
+    }
+
+    /// 沿单条路径执行任务，失败时不尝试其他路线
     pub async fn execute(&self, job_id: &str, path: ConversionPath) -> Result<()> {
-        // TODO: 实现实际的执行逻辑
-        // 1. 更新任务状态为 Running
-        // 2. 按 path.steps 顺序执行每个转换步骤
-        // 3. 处理错误和重试
-        // 4. 更新进度
-        // 5. 生成产物
+        self.execute_routes(job_id, vec![(1, path)]).await
+    }
+
+    /// 在取消令牌下执行任务，任务被取消时清理中间文件
+    async fn execute_routes(&self, job_id: &str, routes: Vec<(u32, ConversionPath)>) -> Result<()> {
+        let token = CancellationToken::new();
+        self.cancellations
+            .lock()
+            .unwrap()
+            .insert(job_id.to_string(), token.clone());
+
+        let result = self.run_routes(job_id, routes, &token).await;
+
+        self.cancellations.lock().unwrap().remove(job_id);
+        if matches!(result, Err(AppError::Cancelled)) {
+            self.discard_workspace(job_id).await;
+        }
+        result
+    }
 
+    /// 依次尝试候选路线 `(序号, 路径)`，直到某条路线的输出通过质量检查
+    ///
+    /// 路线失败 (重试耗尽或不可重试的错误) 或输出被质量检查拒绝时清空工作区并改用
+    /// 下一条路线，每次尝试都记录在任务的 `attempts` 中。所有路线都失败时任务标记为失败。
+    async fn run_routes(
+        &self,
+        job_id: &str,
+        routes: Vec<(u32, ConversionPath)>,
+        token: &CancellationToken,
+    ) -> Result<()> {
         tracing::info!("Executing job {}", job_id);
 
-        // 简化实现
-        self.update_job_state(job_id, JobState::Running).await?;
+        let job = self.require_job(job_id).await?;
+        if job.state == JobState::Canceled {
+            return Err(AppError::Cancelled);
+        }
+        let source = job
+            .inputs
+            .first()
+            .map(PathBuf::from)
+            .ok_or_else(|| AppError::ValidationError("任务没有输入文件".to_string()))?;
+        let options = job_options(&job)?;
+        if routes.iter().any(|(_, path)| path.steps.is_empty()) {
+            return Err(AppError::ValidationError("转换路径为空".to_string()));
+        }
+
+        // 只从排队状态开始执行：分派后、开始前被暂停的任务保持暂停，恢复时重新排队
+        match self.transition(job_id, Some(JobState::Queued), JobState::Running).await? {
+            JobState::Queued => {}
+            JobState::Canceled => return Err(AppError::Cancelled),
+            state => {
+                tracing::info!("Job {} not started, state is {:?}", job_id, state);
+                return Ok(());
+            }
+        }
+
+        let total_routes = routes.len();
+        for (attempt, (route, path)) in routes.into_iter().enumerate() {
+            let started_at = chrono::Utc::now().timestamp_millis();
+            let result = self.run_route(job_id, &path, &source, token).await;
+            let (outcome, error) = match &result {
+                Ok(_) => (AttemptOutcome::Succeeded, None),
+                Err(failure) => (failure.outcome, Some(failure.message.clone())),
+            };
+            self.record_attempt(
+                job_id,
+                PathAttempt {
+                    route,
+                    converters: path.steps.iter().map(|s| s.converter_id.clone()).collect(),
+                    outcome,
+                    error,
+                    started_at,
+                    finished_at: chrono::Utc::now().timestamp_millis(),
+                },
+            )
+            .await;
 
-        // 模拟执行
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            let failure = match result {
+                Ok(produced) => return self.finish(job_id, &path, &produced, &source, &options, token).await,
+                Err(failure) => failure,
+            };
+            if failure.outcome == AttemptOutcome::Canceled || token.is_cancelled() {
+                return Err(AppError::Cancelled);
+            }
+            if attempt + 1 < total_routes {
+                tracing::warn!("Job {} route #{} failed, falling back: {}", job_id, route, failure.message);
+                self.log(
+                    job_id,
+                    LogLevel::Warn,
+                    format!("路线 #{} 失败，改用下一条路线: {}", route, failure.message),
+                );
+                // 不同路线的中间文件不能复用
+                let _ = std::fs::remove_dir_all(self.job_workspace(job_id)?);
+                continue;
+            }
 
-        self.update_job_state(job_id, JobState::Succeeded).await?;
+            let message = match total_routes {
+                1 => failure.message,
+                _ => format!("路线 #{}: {}", route, failure.message),
+            };
+            self.fail_job(job_id, message).await?;
+            return Err(failure.error);
+        }
 
         Ok(())
     }
 
-    /// 更新任务状态
-    async fn update_job_state(&self, job_id: &str, new_state: JobState) -> Result<()> {
+    /// 执行一条路线，返回最后一步的输出文件
+    ///
+    /// 按 `path.steps` 顺序把每一步分派给注册表中的转换器。每步先写入工作区的
+    /// `.partial/` 目录，成功后移入工作区作为下一步的输入。工作区中已有输出的步骤
+    /// 视为已完成并跳过，重试时从第一个未完成的步骤继续。步骤失败时保留工作区，
+    /// 输出未通过质量检查时清空工作区。
+    async fn run_route(
+        &self,
+        job_id: &str,
+        path: &ConversionPath,
+        source: &Path,
+        token: &CancellationToken,
+    ) -> std::result::Result<PathBuf, RouteFailure> {
+        let job = self.require_job(job_id).await?;
+        let options = job_options(&job)?;
+        let workspace = self.job_workspace(job_id)?;
+        let partial_dir = workspace.join(".partial");
+        std::fs::create_dir_all(&partial_dir).map_err(AppError::from)?;
+
+        let completed = path
+            .steps
+            .iter()
+            .enumerate()
+            .take_while(|(index, step)| workspace.join(self.step_file(*index, step)).is_file())
+            .count();
+        if completed == 0 {
+            self.persist(|store| store.save_path(job_id, path));
+        } else {
+            tracing::info!("Job {} resumes after {} completed step(s)", job_id, completed);
+        }
+        self.plans.write().await.insert(job_id.to_string(), path.clone());
+
+        let mut input = match completed {
+            0 => source.to_path_buf(),
+            n => workspace.join(self.step_file(n - 1, &path.steps[n - 1])),
+        };
+        let total_steps = path.steps.len();
+        for (index, step) in path.steps.iter().enumerate().skip(completed) {
+            self.wait_while_paused(job_id).await?;
+            let span = StepSpan {
+                index,
+                total: total_steps,
+                estimated_time_ms: path.estimated_time_ms,
+            };
+            self.report_progress(job_id, span.progress(0.0), &step.converter_id, Some(span.eta_ms(0.0)))
+                .await;
+
+            let file_name = self.step_file(index, step);
+            let partial = partial_dir.join(&file_name);
+            let output = workspace.join(&file_name);
+            let output_path = output.to_string_lossy().to_string();
+            self.persist(|store| store.update_step(job_id, index, StepState::Running, Some(&output_path), None));
+            self.log(job_id, LogLevel::Info, format!("步骤 {}/{} 开始: {}", index + 1, total_steps, step.converter_id));
+            let result = self
+                .run_step_with_retry(job_id, span, step, &input, &partial, &options.custom_params, token)
+                .await
+                .and_then(|()| Ok(std::fs::rename(&partial, &output)?));
+
+            if let Err(e) = result {
+                let _ = std::fs::remove_file(&partial);
+                tracing::error!("Job {} failed at step {} ({}): {}", job_id, index + 1, step.converter_id, e);
+                if matches!(e, AppError::Cancelled) {
+                    return Err(e.into());
+                }
+                let error = e.to_string();
+                self.persist(|store| {
+                    store.update_step(job_id, index, StepState::Failed, Some(&output_path), Some(&error))
+                });
+                return Err(RouteFailure {
+                    message: format!(
+                        "步骤 {}/{} ({}) 失败: {}",
+                        index + 1,
+                        total_steps,
+                        step.converter_id,
+                        e
+                    ),
+                    outcome: AttemptOutcome::Failed,
+                    error: e,
+                });
+            }
+            self.persist(|store| store.update_step(job_id, index, StepState::Succeeded, Some(&output_path), None));
+            input = output;
+        }
+        self.wait_while_paused(job_id).await?;
+
+        // 发布前检查最终输出的质量，评估本身出错时不拒绝产物
+        let last_step = &path.steps[total_steps - 1];
+        let check = self.quality.read().unwrap().clone();
+        match check.assess(source, &input, &last_step.to_format) {
+            Ok(metrics) if !QualityAssessor::meets_threshold(&metrics, &check.thresholds()) => {
+                let message = format!(
+                    "输出未通过质量检查: {}",
+                    serde_json::to_string(&metrics).unwrap_or_default()
+                );
+                // 被拒绝的输出不能留在工作区，否则重试会跳过所有步骤直接再次检查
+                let _ = std::fs::remove_dir_all(&workspace);
+                return Err(RouteFailure {
+                    error: AppError::ValidationError(message.clone()),
+                    message,
+                    outcome: AttemptOutcome::Rejected,
+                });
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Quality assessment failed for job {}: {}", job_id, e),
+        }
+
+        Ok(input)
+    }
+
+    /// 发布产物并将任务标记为成功
+    ///
+    /// 发布前后都检查任务是否已取消，发布期间被取消时删除已写出的产物。
+    async fn finish(
+        &self,
+        job_id: &str,
+        path: &ConversionPath,
+        produced: &Path,
+        source: &Path,
+        options: &ConversionOptions,
+        token: &CancellationToken,
+    ) -> Result<()> {
+        if token.is_cancelled() {
+            return Err(AppError::Cancelled);
+        }
+        let last_step = &path.steps[path.steps.len() - 1];
+        let extension = self.output_extension(last_step);
+        let output_dir = match &options.output_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => source.parent().map(Path::to_path_buf).unwrap_or_default(),
+        };
+        let artifact = match self.publish_artifact(job_id, produced, source, &output_dir, &extension) {
+            Ok(artifact) => artifact,
+            Err(_) if token.is_cancelled() => return Err(AppError::Cancelled),
+            Err(e) => {
+                self.fail_job(job_id, format!("保存产物失败: {}", e)).await?;
+                return Err(e);
+            }
+        };
+
+        // 状态转换与取消互斥：取消先生效时转换被拒绝，撤回产物
+        if token.is_cancelled() || self.update_job_state(job_id, JobState::Succeeded).await.is_err() {
+            let _ = std::fs::remove_file(&artifact.path);
+            return Err(AppError::Cancelled);
+        }
+        self.persist(|store| store.save_artifact(&artifact));
+        self.artifacts.write().await.push(artifact.clone());
+        let _ = std::fs::remove_dir_all(self.job_workspace(job_id)?);
+        self.plans.write().await.remove(job_id);
+
+        tracing::info!("Job {} produced {}", job_id, artifact.path);
+        self.log(job_id, LogLevel::Info, format!("产物已保存: {}", artifact.path));
+        self.report_progress(job_id, 1.0, "完成", Some(0)).await;
+        self.emit(PipelineEvent::ArtifactReady(ArtifactReady {
+            job_id: job_id.to_string(),
+            path: artifact.path,
+            size_bytes: artifact.size_bytes,
+            format: artifact.format,
+        }));
+
+        Ok(())
+    }
+
+    /// 追加路线尝试记录
+    async fn record_attempt(&self, job_id: &str, attempt: PathAttempt) {
         let mut jobs = self.jobs.write().await;
+        let snapshot = jobs.iter_mut().find(|j| j.id == job_id).map(|job| {
+            job.attempts.push(attempt);
+            job.clone()
+        });
+        drop(jobs);
 
-        if let Some(job) = jobs.iter_mut().find(|j| j.id == job_id) {
-            job.state = new_state;
-            match job.state {
-                JobState::Running => {
-                    job.started_at = Some(chrono::Utc::now().timestamp_millis());
+        if let Some(job) = snapshot {
+            self.persist(|store| store.save_job(&job));
+        }
+    }
+
+    /// 将最终中间文件移动到输出目录，文件名沿用输入文件名并避免覆盖已有文件
+    fn publish_artifact(
+        &self,
+        job_id: &str,
+        produced: &Path,
+        source: &Path,
+        output_dir: &Path,
+        extension: &str,
+    ) -> Result<Artifact> {
+        std::fs::create_dir_all(output_dir)?;
+        let stem = source
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "output".to_string());
+
+        let mut target = output_dir.join(format!("{}.{}", stem, extension));
+        let mut suffix = 1;
+        while target.exists() {
+            target = output_dir.join(format!("{} ({}).{}", stem, suffix, extension));
+            suffix += 1;
+        }
+
+        // 工作区与输出目录可能不在同一文件系统，rename 失败时复制
+        if std::fs::rename(produced, &target).is_err() {
+            std::fs::copy(produced, &target)?;
+        }
+
+        let artifact = Artifact {
+            id: uuid::Uuid::new_v4().to_string(),
+            job_id: job_id.to_string(),
+            path: target.to_string_lossy().to_string(),
+            filename: target
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            size_bytes: std::fs::metadata(&target)?.len(),
+            format: extension.to_string(),
+            created_at: chrono::Utc::now().timestamp_millis(),
+        };
+        Ok(artifact)
+    }
+
+    /// 执行单个步骤，可重试的错误按转换器或全局的重试策略指数退避后重试
+    async fn run_step_with_retry(
+        &self,
+        job_id: &str,
+        span: StepSpan,
+        step: &ConversionStep,
+        input: &Path,
+        output: &Path,
+        params: &Value,
+        token: &CancellationToken,
+    ) -> Result<()> {
+        let index = span.index;
+        let capability = self.registry.get(&step.converter_id);
+        let policy = capability
+            .as_ref()
+            .and_then(|cap| cap.retry.clone())
+            .unwrap_or_else(|| SettingsStore::global().get().retry);
+
+        let mut retry = 0;
+        loop {
+            if token.is_cancelled() {
+                return Err(AppError::Cancelled);
+            }
+            let result = self
+                .run_step(job_id, span, step, input, output, params, token)
+                .await
+                .and_then(|()| match output.is_file() {
+                    true => Ok(()),
+                    false => Err(AppError::ToolError(format!("未生成输出文件 {}", output.display()))),
+                });
+
+            // 进程内转换器无法中途终止，结束后再检查是否已取消
+            if token.is_cancelled() {
+                return Err(AppError::Cancelled);
+            }
+            match result {
+                Err(e) if e.is_retriable() && retry < policy.max_retries => {
+                    retry += 1;
+                    let delay = policy.backoff(retry);
+                    let _ = std::fs::remove_file(output);
+                    tracing::warn!("Job {} step {} failed, retrying in {:?}: {}", job_id, index + 1, delay, e);
+                    self.log(
+                        job_id,
+                        LogLevel::Warn,
+                        format!(
+                            "步骤 {} ({}) 失败，{} 毫秒后第 {} 次重试: {}",
+                            index + 1,
+                            step.converter_id,
+                            delay.as_millis(),
+                            retry,
+                            e
+                        ),
+                    );
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = token.cancelled() => return Err(AppError::Cancelled),
+                    }
+                    self.wait_while_paused(job_id).await?;
                 }
-                JobState::Succeeded | JobState::Failed | JobState::Canceled => {
-                    job.finished_at = Some(chrono::Utc::now().timestamp_millis());
+                result => return result,
+            }
+        }
+    }
+
+    /// 执行单个转换步骤，期间启动的外部进程登记到该任务并受 `token` 控制
+    ///
+    /// 执行前向调度器申请资源，任务暂停期间让出资源类别的占用。外部工具报告的步骤内进度
+    /// 按 `span` 换算为任务进度后实时发送。
+    pub async fn run_step(
+        &self,
+        job_id: &str,
+        span: StepSpan,
+        step: &ConversionStep,
+        input: &Path,
+        output: &Path,
+        params: &Value,
+        token: &CancellationToken,
+    ) -> Result<()> {
+        let converter = self.registry.converter(&step.converter_id).ok_or_else(|| {
+            AppError::ToolError(format!("转换器未注册: {}", step.converter_id))
+        })?;
+
+        // 等待调度器分配资源，未注册的转换器上面已报错
+        let mut permit = match self.registry.get(&step.converter_id) {
+            Some(cap) => tokio::select! {
+                permit = self.scheduler.acquire(StepDemand::for_capability(&cap)) => Some(permit),
+                _ = token.cancelled() => return Err(AppError::Cancelled),
+            },
+            None => None,
+        };
+
+        let (sender, mut updates) = tokio::sync::mpsc::unbounded_channel::<ToolProgress>();
+        let sink: ProgressSink = Arc::new(move |update| {
+            let _ = sender.send(update);
+        });
+
+        let input = input.to_path_buf();
+        let output = output.to_path_buf();
+        let params = params.clone();
+        let owner = job_id.to_string();
+        let token = token.clone();
+        let task = tokio::task::spawn_blocking(move || {
+            ProcessTable::scope(&owner, &token, || {
+                progress::scope(sink, || converter.convert(&input, &output, &params))
+            })
+        });
+        tokio::pin!(task);
+
+        let mut fraction = 0.0;
+        let mut stage = step.converter_id.clone();
+        let mut pause_check = tokio::time::interval(PAUSE_CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                result = &mut task => return result.map_err(|e| AppError::Internal(e.to_string()))?,
+                _ = pause_check.tick(), if permit.is_some() => {
+                    let permit = permit.as_mut().unwrap();
+                    match ProcessTable::global().is_paused(job_id) {
+                        true => permit.suspend(),
+                        false => permit.resume(),
+                    }
+                }
+                Some(update) = updates.recv() => {
+                    if let Some(value) = update.fraction {
+                        fraction = value;
+                    }
+                    if let Some(detail) = update.stage {
+                        stage = format!("{}: {}", step.converter_id, detail);
+                    }
+                    self.report_progress(job_id, span.progress(fraction), &stage, Some(span.eta_ms(fraction)))
+                        .await;
                 }
-                _ => {}
             }
         }
+    }
 
-        Ok(())
+    /// 步骤在工作区中的输出文件名
+    fn step_file(&self, index: usize, step: &ConversionStep) -> String {
+        format!("step-{}.{}", index + 1, self.output_extension(step))
+    }
+
+    /// 步骤输出文件的扩展名，取自转换器声明的输出格式
+    fn output_extension(&self, step: &ConversionStep) -> String {
+        self.registry
+            .get(&step.converter_id)
+            .and_then(|cap| {
+                cap.outputs
+                    .into_iter()
+                    .find(|spec| spec.mime == step.to_format)
+                    .and_then(|spec| spec.extensions.into_iter().next())
+            })
+            .unwrap_or_else(|| "out".to_string())
+    }
+
+    /// 更新任务状态
+    async fn update_job_state(&self, job_id: &str, new_state: JobState) -> Result<()> {
+        self.transition(job_id, None, new_state).await.map(|_| ())
+    }
+
+    /// 更新任务状态，给出 `expected` 时只在任务处于该状态时更新
+    ///
+    /// 返回更新前的状态；与 `expected` 不符时不做任何修改。
+    async fn transition(&self, job_id: &str, expected: Option<JobState>, new_state: JobState) -> Result<JobState> {
+        let mut jobs = self.jobs.write().await;
+
+        let Some(job) = jobs.iter_mut().find(|j| j.id == job_id) else {
+            return Ok(new_state);
+        };
+        if expected.as_ref().is_some_and(|expected| *expected != job.state) {
+            return Ok(job.state.clone());
+        }
+        // 终态只能经由重试离开，避免取消后被执行结果覆盖为成功或失败
+        let retrying = job.state == JobState::Failed && new_state == JobState::Queued;
+        if job.state.is_terminal() && !retrying {
+            return Err(AppError::ValidationError(format!(
+                "任务状态为 {:?}，无法变更为 {:?}",
+                job.state, new_state
+            )));
+        }
+        let old_state = std::mem::replace(&mut job.state, new_state.clone());
+        let previous = old_state.clone();
+        match job.state {
+            JobState::Running if job.started_at.is_none() => {
+                job.started_at = Some(chrono::Utc::now().timestamp_millis());
+            }
+            JobState::Succeeded | JobState::Failed | JobState::Canceled => {
+                job.finished_at = Some(chrono::Utc::now().timestamp_millis());
+            }
+            _ => {}
+        }
+        let mut queue = self.queue.lock().unwrap();
+        if job.state == JobState::Queued {
+            queue.push(job);
+        } else {
+            queue.remove(job_id);
+        }
+        drop(queue);
+        let event = JobStateChanged {
+            job_id: job_id.to_string(),
+            old_state,
+            new_state,
+            error: job.error.clone(),
+        };
+        let snapshot = job.clone();
+        drop(jobs);
+
+        self.persist(|store| store.save_job(&snapshot));
+        self.emit(PipelineEvent::JobStateChanged(event));
+        Ok(previous)
+    }
+
+    /// 更新任务进度并发送进度事件
+    async fn report_progress(&self, job_id: &str, progress: f32, stage: &str, eta_ms: Option<u64>) {
+        let mut jobs = self.jobs.write().await;
+        let snapshot = jobs.iter_mut().find(|j| j.id == job_id).map(|job| {
+            job.progress = progress;
+            job.stage = Some(stage.to_string());
+            job.eta_ms = eta_ms;
+            job.clone()
+        });
+        drop(jobs);
+
+        if let Some(job) = snapshot {
+            self.persist(|store| store.save_job(&job));
+        }
+
+        self.emit(PipelineEvent::JobProgress(JobProgress {
+            job_id: job_id.to_string(),
+            progress,
+            stage: stage.to_string(),
+            eta_ms,
+        }));
+    }
+
+    /// 将任务标记为失败并记录错误
+    async fn fail_job(&self, job_id: &str, error: String) -> Result<()> {
+        self.log(job_id, LogLevel::Error, error.clone());
+        let mut jobs = self.jobs.write().await;
+        if let Some(job) = jobs.iter_mut().find(|j| j.id == job_id) {
+            job.error = Some(error);
+        }
+        drop(jobs);
+
+        self.update_job_state(job_id, JobState::Failed).await
     }
 
     /// 取消任务
+    ///
+    /// 执行中的任务通过取消令牌结束：外部工具进程组先收到 SIGTERM，宽限期后仍未退出的
+    /// 收到 SIGKILL，执行随后清理中间文件并返回 `AppError::Cancelled`。
     pub async fn cancel(&self, job_id: &str) -> Result<()> {
-        self.update_job_state(job_id, JobState::Canceled).await
+        let job = self.require_job(job_id).await?;
+        if job.state.is_terminal() {
+            return Err(AppError::ValidationError(format!("任务状态为 {:?}，无法取消", job.state)));
+        }
+
+        self.log(job_id, LogLevel::Info, "任务已取消".to_string());
+        self.update_job_state(job_id, JobState::Canceled).await?;
+
+        let token = self.cancellations.lock().unwrap().get(job_id).cloned();
+        match token {
+            Some(token) => {
+                token.cancel();
+                let terminated = ProcessTable::global().terminate(job_id, CANCEL_GRACE_PERIOD);
+                tracing::info!("Job {} canceled, {} process(es) terminated", job_id, terminated);
+            }
+            None => self.discard_workspace(job_id).await,
+        }
+        self.resumed.notify_waiters();
+        Ok(())
+    }
+
+    /// 删除任务工作区及保存的执行路径
+    async fn discard_workspace(&self, job_id: &str) {
+        self.plans.write().await.remove(job_id);
+        if let Ok(workspace) = self.job_workspace(job_id) {
+            let _ = std::fs::remove_dir_all(workspace);
+        }
+    }
+
+    /// 暂停任务
+    ///
+    /// 排队中的任务不再被调度；执行中的任务在当前步骤结束后停止调度后续步骤，
+    /// 正在运行的外部工具进程在支持的平台上被挂起 (SIGSTOP)。
+    pub async fn pause(&self, job_id: &str) -> Result<()> {
+        let job = self.require_job(job_id).await?;
+        match job.state {
+            JobState::Queued => {}
+            JobState::Running => {
+                let suspended = ProcessTable::global().suspend(job_id);
+                tracing::info!("Job {} paused, {} process(es) suspended", job_id, suspended);
+            }
+            state => {
+                return Err(AppError::ValidationError(format!("任务状态为 {:?}，无法暂停", state)));
+            }
+        }
+
+        self.log(job_id, LogLevel::Info, "任务已暂停".to_string());
+        self.update_job_state(job_id, JobState::Paused).await?;
+        // 暂停的任务让出并发名额
+        self.queued.notify_one();
+        Ok(())
+    }
+
+    /// 恢复暂停的任务，从下一个未完成的步骤继续执行
+    ///
+    /// 尚未开始执行的任务重新排队。
+    pub async fn resume(&self, job_id: &str) -> Result<()> {
+        let job = self.require_job(job_id).await?;
+        if job.state != JobState::Paused {
+            return Err(AppError::ValidationError(format!("任务状态为 {:?}，无法恢复", job.state)));
+        }
+
+        self.log(job_id, LogLevel::Info, "任务已恢复".to_string());
+        if job.started_at.is_some() {
+            ProcessTable::global().resume(job_id);
+            self.update_job_state(job_id, JobState::Running).await?;
+            self.resumed.notify_waiters();
+        } else {
+            self.update_job_state(job_id, JobState::Queued).await?;
+            self.queued.notify_one();
+        }
+        Ok(())
+    }
+
+    /// 重新排队失败的任务，执行时从第一个失败的步骤继续，已完成步骤的中间文件直接复用
+    pub async fn retry(&self, job_id: &str) -> Result<()> {
+        let mut jobs = self.jobs.write().await;
+        let job = jobs
+            .iter_mut()
+            .find(|j| j.id == job_id)
+            .ok_or_else(|| AppError::ValidationError(format!("任务不存在: {}", job_id)))?;
+        if job.state != JobState::Failed {
+            return Err(AppError::ValidationError(format!("任务状态为 {:?}，无法重试", job.state)));
+        }
+        job.error = None;
+        job.progress = 0.0;
+        job.stage = None;
+        job.eta_ms = None;
+        job.started_at = None;
+        job.finished_at = None;
+        drop(jobs);
+
+        self.log(job_id, LogLevel::Info, "任务已重新排队".to_string());
+        self.update_job_state(job_id, JobState::Queued).await?;
+        self.queued.notify_one();
+        Ok(())
+    }
+
+    /// 将排队中的任务移到队首，优先级随之提升到队首任务的优先级
+    pub async fn move_to_top(&self, job_id: &str) -> Result<()> {
+        self.reorder(job_id, |queue, job| queue.front_slot(job)).await?;
+        self.log(job_id, LogLevel::Info, "任务已移到队首".to_string());
+        Ok(())
+    }
+
+    /// 将排队中的任务移到队尾，优先级随之降低到队尾任务的优先级
+    pub async fn defer(&self, job_id: &str) -> Result<()> {
+        self.reorder(job_id, |queue, job| queue.back_slot(job)).await?;
+        self.log(job_id, LogLevel::Info, "任务已推迟".to_string());
+        Ok(())
+    }
+
+    async fn reorder(&self, job_id: &str, slot: impl FnOnce(&JobQueue, &Job) -> (i32, i64)) -> Result<()> {
+        let mut jobs = self.jobs.write().await;
+        let job = jobs
+            .iter_mut()
+            .find(|j| j.id == job_id)
+            .ok_or_else(|| AppError::ValidationError(format!("任务不存在: {}", job_id)))?;
+        if job.state != JobState::Queued {
+            return Err(AppError::ValidationError(format!("任务状态为 {:?}，无法调整排队顺序", job.state)));
+        }
+
+        let mut queue = self.queue.lock().unwrap();
+        (job.priority, job.queue_position) = slot(&queue, job);
+        queue.push(job);
+        drop(queue);
+        let snapshot = job.clone();
+        drop(jobs);
+
+        self.persist(|store| store.save_job(&snapshot));
+        Ok(())
+    }
+
+    /// 调整排队中或暂停任务的优先级
+    pub async fn set_priority(&self, job_id: &str, priority: i32) -> Result<()> {
+        let mut jobs = self.jobs.write().await;
+        let job = jobs
+            .iter_mut()
+            .find(|j| j.id == job_id)
+            .ok_or_else(|| AppError::ValidationError(format!("任务不存在: {}", job_id)))?;
+        if !matches!(job.state, JobState::Queued | JobState::Paused) {
+            return Err(AppError::ValidationError(format!("任务状态为 {:?}，无法调整优先级", job.state)));
+        }
+
+        job.priority = priority;
+        if job.state == JobState::Queued {
+            self.queue.lock().unwrap().push(job);
+        }
+        let snapshot = job.clone();
+        drop(jobs);
+
+        self.persist(|store| store.save_job(&snapshot));
+        self.log(job_id, LogLevel::Info, format!("优先级调整为 {}", priority));
+        Ok(())
+    }
+
+    /// 任务暂停期间等待恢复，任务已取消时返回 `AppError::Cancelled`
+    async fn wait_while_paused(&self, job_id: &str) -> Result<()> {
+        loop {
+            let resumed = self.resumed.notified();
+            tokio::pin!(resumed);
+            resumed.as_mut().enable();
+
+            match self.require_job(job_id).await?.state {
+                JobState::Paused => resumed.await,
+                JobState::Canceled => return Err(AppError::Cancelled),
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    async fn require_job(&self, job_id: &str) -> Result<Job> {
+        self.get_job(job_id)
+            .await?
+            .ok_or_else(|| AppError::ValidationError(format!("任务不存在: {}", job_id)))
     }
 
     /// 获取任务
@@ -85,6 +1134,52 @@ impl Pipeline {
         let jobs = self.jobs.read().await;
         Ok(jobs.clone())
     }
+
+    /// 读取任务日志，`cursor` 为已读取的条数
+    pub async fn get_logs(&self, job_id: &str, cursor: Option<i64>) -> Result<Vec<LogEntry>> {
+        let store = self.store.read().unwrap().clone();
+        match store {
+            Some(store) => store.list_logs(job_id, cursor),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// 获取任务产物
+    pub async fn get_artifacts(&self, job_id: &str) -> Result<Vec<Artifact>> {
+        let artifacts = self.artifacts.read().await;
+        Ok(artifacts.iter().filter(|a| a.job_id == job_id).cloned().collect())
+    }
+}
+
+/// 路线执行失败的原因
+struct RouteFailure {
+    error: AppError,
+    /// 写入任务错误与尝试记录的说明
+    message: String,
+    outcome: AttemptOutcome,
+}
+
+impl From<AppError> for RouteFailure {
+    fn from(error: AppError) -> Self {
+        let outcome = match error {
+            AppError::Cancelled => AttemptOutcome::Canceled,
+            _ => AttemptOutcome::Failed,
+        };
+        Self {
+            message: error.to_string(),
+            outcome,
+            error,
+        }
+    }
+}
+
+/// 解析任务的转换选项，未提供时使用默认值
+fn job_options(job: &Job) -> Result<ConversionOptions> {
+    match &job.options {
+        Value::Null => Ok(ConversionOptions::default()),
+        options => serde_json::from_value(options.clone())
+            .map_err(|e| AppError::ValidationError(format!("无效的转换选项: {}", e))),
+    }
 }
 
 impl Default for Pipeline {
@@ -92,3 +1187,970 @@ impl Default for Pipeline {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::converter::ConverterPlugin;
+    use crate::types::test_support::*;
+    use crate::types::*;
+
+    /// 把输入内容加上自身 ID 后写入输出的测试转换器
+    struct AppendPlugin(CapabilityRecord);
+
+    impl ConverterPlugin for AppendPlugin {
+        fn capability(&self) -> CapabilityRecord {
+            self.0.clone()
+        }
+
+        fn convert(&self, input: &Path, output: &Path, _params: &Value) -> Result<()> {
+            let content = std::fs::read_to_string(input)?;
+            std::fs::write(output, format!("{}|{}", content, self.0.id))?;
+            Ok(())
+        }
+    }
+
+    fn append(id: &str, from: &str, to: &str) -> Arc<dyn ConverterPlugin> {
+        Arc::new(AppendPlugin(capability(id, from, to)))
+    }
+
+    /// 测试环境：临时目录中内容为 "start" 的输入文件 doc.a 与注册了给定转换器的注册表
+    struct Fixture {
+        dir: tempfile::TempDir,
+        registry: Arc<Registry>,
+    }
+
+    impl Fixture {
+        fn new(plugins: Vec<Arc<dyn ConverterPlugin>>) -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            std::fs::write(dir.path().join("doc.a"), "start").unwrap();
+            let registry = Arc::new(Registry::new());
+            for plugin in plugins {
+                registry.register_plugin(plugin).unwrap();
+            }
+            Self { dir, registry }
+        }
+
+        /// 使用临时目录中独立工作区的流水线
+        fn pipeline(&self) -> Arc<Pipeline> {
+            let pipeline = Arc::new(Pipeline::with_registry(self.registry.clone()));
+            pipeline.set_workspace_dir(self.path("workspace"));
+            pipeline
+        }
+
+        fn path(&self, name: &str) -> PathBuf {
+            self.dir.path().join(name)
+        }
+
+        /// 把 doc.a 转换为 `target` 格式的任务
+        fn job(&self, target: &str) -> Job {
+            let input = self.path("doc.a").to_string_lossy().to_string();
+            Job::new(vec![input], target.to_string(), None, Value::Null)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_dispatches_steps_to_plugins() {
+        let fixture = Fixture::new(vec![append("a-b", "text/a", "text/b"), append("b-c", "text/b", "text/c")]);
+        let pipeline = fixture.pipeline();
+        let path = conversion_path(vec![step("a-b", "text/a", "text/b"), step("b-c", "text/b", "text/c")]);
+
+        let job_id = pipeline.submit(fixture.job("c")).await.unwrap();
+        pipeline.execute(&job_id, path.clone()).await.unwrap();
+
+        let output = std::fs::read_to_string(fixture.path("doc.c")).unwrap();
+        assert_eq!(output, "start|a-b|b-c");
+        let job = pipeline.get_job(&job_id).await.unwrap().unwrap();
+        assert_eq!(job.state, JobState::Succeeded);
+
+        // 中间文件只写入工作区，成功后清理
+        assert!(!fixture.path("doc.b").exists());
+        assert!(!pipeline.job_workspace(&job_id).unwrap().join("step-1.b").exists());
+        let artifacts = pipeline.get_artifacts(&job_id).await.unwrap();
+        assert_eq!(artifacts.len(), 1);
+        assert_eq!(artifacts[0].filename, "doc.c");
+        assert_eq!(artifacts[0].size_bytes, output.len() as u64);
+
+        // 已有同名产物时不覆盖
+        let job_id = pipeline.submit(fixture.job("c")).await.unwrap();
+        pipeline.execute(&job_id, path.clone()).await.unwrap();
+        let artifacts = pipeline.get_artifacts(&job_id).await.unwrap();
+        assert_eq!(artifacts[0].filename, "doc (1).c");
+
+        let mut broken = path;
+        broken.steps[1].converter_id = "missing".to_string();
+        let job_id = pipeline.submit(fixture.job("c")).await.unwrap();
+        assert!(matches!(
+            pipeline.execute(&job_id, broken).await,
+            Err(AppError::ToolError(_))
+        ));
+        let job = pipeline.get_job(&job_id).await.unwrap().unwrap();
+        assert_eq!(job.state, JobState::Failed);
+        let error = job.error.unwrap();
+        assert!(error.contains("步骤 2/2 (missing)"), "{}", error);
+        assert!(pipeline.job_workspace(&job_id).unwrap().join("step-1.b").is_file());
+    }
+
+    /// 收集所有事件的测试接收端
+    #[derive(Default)]
+    struct CollectingSink(std::sync::Mutex<Vec<PipelineEvent>>);
+
+    impl EventSink for CollectingSink {
+        fn emit(&self, event: PipelineEvent) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_emits_events() {
+        let fixture = Fixture::new(vec![append("a-b", "text/a", "text/b"), append("b-c", "text/b", "text/c")]);
+        let sink = Arc::new(CollectingSink::default());
+        let pipeline = fixture.pipeline();
+        pipeline.set_event_sink(sink.clone());
+
+        let job_id = pipeline.submit(fixture.job("c")).await.unwrap();
+        let path = ConversionPath {
+            estimated_time_ms: 2000,
+            ..conversion_path(vec![step("a-b", "text/a", "text/b"), step("b-c", "text/b", "text/c")])
+        };
+        pipeline.execute(&job_id, path).await.unwrap();
+
+        let events = sink.0.lock().unwrap().clone();
+        let summary: Vec<String> = events
+            .iter()
+            .map(|event| match event {
+                PipelineEvent::JobProgress(p) => format!("progress {} {} {:?}", p.progress, p.stage, p.eta_ms),
+                PipelineEvent::JobStateChanged(c) => format!("state {:?} -> {:?}", c.old_state, c.new_state),
+                PipelineEvent::ArtifactReady(a) => format!("artifact {}", a.format),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                "state Queued -> Running",
+                "progress 0 a-b Some(2000)",
+                "progress 0.5 b-c Some(1000)",
+                "state Running -> Succeeded",
+                "progress 1 完成 Some(0)",
+                "artifact c",
+            ]
+        );
+        assert!(events.iter().all(|event| match event {
+            PipelineEvent::JobProgress(p) => p.job_id == job_id,
+            PipelineEvent::JobStateChanged(c) => c.job_id == job_id,
+            PipelineEvent::ArtifactReady(a) => a.job_id == job_id,
+        }));
+    }
+
+    /// 等待任务进入终态
+    async fn wait_finished(pipeline: &Pipeline, job_id: &str) -> Job {
+        for _ in 0..500 {
+            let job = pipeline.get_job(job_id).await.unwrap().unwrap();
+            if matches!(job.state, JobState::Succeeded | JobState::Failed | JobState::Canceled) {
+                return job;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("job {} did not finish", job_id);
+    }
+
+    #[tokio::test]
+    async fn test_worker_runs_submitted_jobs() {
+        let fixture = Fixture::new(vec![append("md-html", "text/markdown", "text/html")]);
+        let pipeline = fixture.pipeline();
+        tokio::spawn(pipeline.clone().run_worker());
+
+        let input = fixture.path("doc.md");
+        std::fs::write(&input, "# title").unwrap();
+        let input = input.to_string_lossy().to_string();
+
+        let options = serde_json::json!({ "quality_priority": "balanced" });
+        let job_id = pipeline
+            .submit(Job::new(vec![input.clone()], "html".to_string(), None, options))
+            .await
+            .unwrap();
+        let job = wait_finished(&pipeline, &job_id).await;
+        assert_eq!(job.state, JobState::Succeeded);
+        assert!(job.started_at.is_some() && job.finished_at.is_some());
+        let output = std::fs::read_to_string(fixture.path("doc.html")).unwrap();
+        assert_eq!(output, "# title|md-html");
+
+        // 无可达路径的任务标记为失败并记录原因
+        let job_id = pipeline
+            .submit(Job::new(vec![input], "pdf".to_string(), None, Value::Null))
+            .await
+            .unwrap();
+        let job = wait_finished(&pipeline, &job_id).await;
+        assert_eq!(job.state, JobState::Failed);
+        assert!(job.error.unwrap().contains("pdf"));
+    }
+
+    #[tokio::test]
+    async fn test_jobs_survive_restart() {
+        let fixture = Fixture::new(vec![append("a-b", "text/a", "text/b")]);
+        let db = fixture.path("jobs.db");
+
+        let pipeline = fixture.pipeline();
+        pipeline
+            .attach_store(Arc::new(JobStore::open(&db).unwrap()), RunningJobRecovery::Fail)
+            .await
+            .unwrap();
+        let finished = pipeline.submit(fixture.job("b")).await.unwrap();
+        let path = conversion_path(vec![step("a-b", "text/a", "text/b")]);
+        pipeline.execute(&finished, path).await.unwrap();
+
+        // 模拟执行中退出：任务停留在 Running
+        let interrupted = pipeline.submit(fixture.job("b")).await.unwrap();
+        pipeline.update_job_state(&interrupted, JobState::Running).await.unwrap();
+        drop(pipeline);
+
+        let restarted = fixture.pipeline();
+        restarted
+            .attach_store(Arc::new(JobStore::open(&db).unwrap()), RunningJobRecovery::Fail)
+            .await
+            .unwrap();
+        let job = restarted.get_job(&finished).await.unwrap().unwrap();
+        assert_eq!(job.state, JobState::Succeeded);
+        assert_eq!(job.progress, 1.0);
+        assert_eq!(restarted.get_artifacts(&finished).await.unwrap()[0].filename, "doc.b");
+        assert!(!restarted.get_logs(&finished, None).await.unwrap().is_empty());
+        let job = restarted.get_job(&interrupted).await.unwrap().unwrap();
+        assert_eq!(job.state, JobState::Failed);
+        assert!(job.error.is_some());
+
+        // 设为重新排队时，工作循环会重新执行中断的任务
+        let interrupted = restarted.submit(fixture.job("b")).await.unwrap();
+        restarted.update_job_state(&interrupted, JobState::Running).await.unwrap();
+        drop(restarted);
+
+        let restarted = fixture.pipeline();
+        restarted
+            .attach_store(Arc::new(JobStore::open(&db).unwrap()), RunningJobRecovery::Requeue)
+            .await
+            .unwrap();
+        assert_eq!(restarted.get_job(&interrupted).await.unwrap().unwrap().state, JobState::Queued);
+        tokio::spawn(restarted.clone().run_worker());
+        let job = wait_finished(&restarted, &interrupted).await;
+        assert_eq!(job.state, JobState::Succeeded);
+        assert_eq!(restarted.list_jobs().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_back_to_back_submissions_keep_fifo_order() {
+        let fixture = Fixture::new(vec![]);
+        let db = fixture.path("jobs.db");
+
+        // 同一毫秒内提交的任务也按提交顺序排队
+        let pipeline = fixture.pipeline();
+        pipeline
+            .attach_store(Arc::new(JobStore::open(&db).unwrap()), RunningJobRecovery::Fail)
+            .await
+            .unwrap();
+        let mut ids = vec![];
+        for _ in 0..50 {
+            ids.push(pipeline.submit(fixture.job("b")).await.unwrap());
+        }
+        assert_eq!(pipeline.queue.lock().unwrap().job_ids(), ids);
+        drop(pipeline);
+
+        // 重启后新任务排在恢复的任务之后，连接前提交的任务也一样
+        let restarted = fixture.pipeline();
+        let early = restarted.submit(fixture.job("b")).await.unwrap();
+        restarted
+            .attach_store(Arc::new(JobStore::open(&db).unwrap()), RunningJobRecovery::Fail)
+            .await
+            .unwrap();
+        ids.push(early);
+        for _ in 0..5 {
+            ids.push(restarted.submit(fixture.job("b")).await.unwrap());
+        }
+        assert_eq!(restarted.queue.lock().unwrap().job_ids(), ids);
+    }
+
+    /// 前 `failures` 次调用返回 `error` 的测试转换器
+    struct FlakyPlugin {
+        record: CapabilityRecord,
+        failures: u32,
+        error: fn() -> AppError,
+        calls: std::sync::atomic::AtomicU32,
+    }
+
+    impl FlakyPlugin {
+        fn new(record: CapabilityRecord, failures: u32, error: fn() -> AppError) -> Arc<Self> {
+            Arc::new(Self { record, failures, error, calls: Default::default() })
+        }
+
+        fn calls(&self) -> u32 {
+            self.calls.load(std::sync::atomic::Ordering::SeqCst)
+        }
+    }
+
+    impl ConverterPlugin for FlakyPlugin {
+        fn capability(&self) -> CapabilityRecord {
+            self.record.clone()
+        }
+
+        fn convert(&self, input: &Path, output: &Path, params: &Value) -> Result<()> {
+            let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if call < self.failures {
+                std::fs::write(output, "partial")?;
+                return Err((self.error)());
+            }
+            AppendPlugin(self.record.clone()).convert(input, output, params)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_and_failed_steps() {
+        let fast = RetryPolicy {
+            max_retries: 2,
+            initial_backoff_ms: 5,
+            max_backoff_ms: 20,
+            multiplier: 2.0,
+        };
+        assert_eq!(fast.backoff(1).as_millis(), 5);
+        assert_eq!(fast.backoff(2).as_millis(), 10);
+        assert_eq!(fast.backoff(5).as_millis(), 20);
+
+        let mut record = capability("a-b", "text/a", "text/b");
+        record.retry = Some(fast);
+        let transient = FlakyPlugin::new(record, 2, || AppError::ToolCrashed("killed".to_string()));
+        let broken = FlakyPlugin::new(capability("b-c", "text/b", "text/c"), 1, || {
+            AppError::ToolError("bad input".to_string())
+        });
+        let fixture = Fixture::new(vec![transient.clone(), broken.clone()]);
+        let pipeline = fixture.pipeline();
+        tokio::spawn(pipeline.clone().run_worker());
+        let job_id = pipeline.submit(fixture.job("c")).await.unwrap();
+
+        // 崩溃自动重试两次后成功，普通工具错误不自动重试
+        let job = wait_finished(&pipeline, &job_id).await;
+        assert_eq!(job.state, JobState::Failed);
+        assert!(job.error.unwrap().contains("步骤 2/2 (b-c)"));
+        assert_eq!((transient.calls(), broken.calls()), (3, 1));
+
+        // 手动重试从失败的第二步继续，复用第一步的中间文件
+        assert!(pipeline.retry(&job_id).await.is_ok());
+        let job = wait_finished(&pipeline, &job_id).await;
+        assert_eq!(job.state, JobState::Succeeded);
+        assert!(job.error.is_none());
+        assert_eq!((transient.calls(), broken.calls()), (3, 2));
+        assert_eq!(std::fs::read_to_string(fixture.path("doc.c")).unwrap(), "start|a-b|b-c");
+        assert!(pipeline.retry(&job_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_at_max_retries() {
+        let immediate = RetryPolicy {
+            max_retries: 3,
+            initial_backoff_ms: 0,
+            max_backoff_ms: 0,
+            multiplier: 2.0,
+        };
+        assert!((1..=3).all(|retry| immediate.backoff(retry).is_zero()));
+
+        // (错误, 预期调用次数)：可重试的错误首次执行后再重试三次，其他错误只执行一次
+        let cases: [(fn() -> AppError, u32); 3] = [
+            (|| AppError::ToolCrashed("killed".to_string()), 4),
+            (|| AppError::ResourceLimit("timeout".to_string()), 4),
+            (|| AppError::ToolError("bad input".to_string()), 1),
+        ];
+        for (error, calls) in cases {
+            let mut record = capability("a-b", "text/a", "text/b");
+            record.retry = Some(immediate.clone());
+            let plugin = FlakyPlugin::new(record, u32::MAX, error);
+            let fixture = Fixture::new(vec![plugin.clone()]);
+            let pipeline = fixture.pipeline();
+            let job_id = pipeline.submit(fixture.job("b")).await.unwrap();
+
+            let path = conversion_path(vec![step("a-b", "text/a", "text/b")]);
+            assert!(pipeline.execute(&job_id, path).await.is_err());
+            assert_eq!(plugin.calls(), calls, "{}", error());
+            let job = pipeline.get_job(&job_id).await.unwrap().unwrap();
+            assert_eq!(job.state, JobState::Failed);
+            assert!(job.error.unwrap().contains(&error().to_string()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pause_between_dispatch_and_start_is_kept() {
+        let plugin = FlakyPlugin::new(capability("a-b", "text/a", "text/b"), 0, || {
+            AppError::ToolError("unused".to_string())
+        });
+        let fixture = Fixture::new(vec![plugin.clone()]);
+        let pipeline = fixture.pipeline();
+        let job_id = pipeline.submit(fixture.job("b")).await.unwrap();
+
+        // 任务已分派但尚未开始执行时被暂停
+        pipeline.pause(&job_id).await.unwrap();
+        let path = conversion_path(vec![step("a-b", "text/a", "text/b")]);
+        pipeline.execute(&job_id, path).await.unwrap();
+        let job = pipeline.get_job(&job_id).await.unwrap().unwrap();
+        assert_eq!(job.state, JobState::Paused);
+        assert!(job.started_at.is_none());
+        assert_eq!(plugin.calls(), 0);
+
+        // 恢复后重新排队并执行
+        tokio::spawn(pipeline.clone().run_worker());
+        pipeline.resume(&job_id).await.unwrap();
+        let job = wait_finished(&pipeline, &job_id).await;
+        assert_eq!(job.state, JobState::Succeeded, "{:?}", job.error);
+        assert_eq!(plugin.calls(), 1);
+    }
+
+    /// 拒绝由指定转换器产出的文件的质量检查
+    struct RejectConverter(&'static str);
+
+    impl QualityCheck for RejectConverter {
+        fn assess(&self, _source: &Path, result: &Path, _target_format: &str) -> Result<QualityMetrics> {
+            let content = std::fs::read_to_string(result)?;
+            let rejected = content.ends_with(&format!("|{}", self.0));
+            Ok(QualityMetrics {
+                ocr_cer: None,
+                structure_consistency: Some(if rejected { 0.2 } else { 0.99 }),
+                table_recall: None,
+                media_bitrate_deviation: None,
+                schema_validation_passed: true,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_rejection_reruns_steps() {
+        let plugin = FlakyPlugin::new(capability("a-b", "text/a", "text/b"), 0, || {
+            AppError::ToolError("unused".to_string())
+        });
+        let fixture = Fixture::new(vec![plugin.clone()]);
+        let pipeline = fixture.pipeline();
+        pipeline.set_quality_check(Arc::new(RejectConverter("a-b")));
+        tokio::spawn(pipeline.clone().run_worker());
+        let job_id = pipeline.submit(fixture.job("b")).await.unwrap();
+
+        let job = wait_finished(&pipeline, &job_id).await;
+        assert_eq!(job.state, JobState::Failed);
+        assert_eq!(job.attempts[0].outcome, AttemptOutcome::Rejected);
+        assert!(!pipeline.job_workspace(&job_id).unwrap().join("step-1.b").exists());
+
+        // 重试时重新执行步骤，而不是复用被拒绝的输出
+        pipeline.set_quality_check(Arc::new(DefaultQualityCheck));
+        pipeline.retry(&job_id).await.unwrap();
+        let job = wait_finished(&pipeline, &job_id).await;
+        assert_eq!(job.state, JobState::Succeeded, "{:?}", job.error);
+        assert_eq!(plugin.calls(), 2);
+        assert_eq!(std::fs::read_to_string(fixture.path("doc.b")).unwrap(), "start|a-b");
+    }
+
+    /// 评估前等待一段时间的质量检查，用于在发布产物前取消任务
+    struct SlowCheck(std::time::Duration);
+
+    impl QualityCheck for SlowCheck {
+        fn assess(&self, source: &Path, result: &Path, target_format: &str) -> Result<QualityMetrics> {
+            std::thread::sleep(self.0);
+            DefaultQualityCheck.assess(source, result, target_format)
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_cancel_before_publish_discards_result() {
+        let fixture = Fixture::new(vec![append("a-b", "text/a", "text/b")]);
+        let pipeline = fixture.pipeline();
+        pipeline.set_quality_check(Arc::new(SlowCheck(std::time::Duration::from_millis(300))));
+        let job_id = pipeline.submit(fixture.job("b")).await.unwrap();
+        let path = conversion_path(vec![step("a-b", "text/a", "text/b")]);
+        let task = tokio::spawn({
+            let pipeline = pipeline.clone();
+            let job_id = job_id.clone();
+            async move { pipeline.execute(&job_id, path).await }
+        });
+
+        // 步骤已完成、正在质量检查时取消
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        pipeline.cancel(&job_id).await.unwrap();
+        let result = task.await.unwrap();
+        assert!(matches!(result, Err(AppError::Cancelled)), "{:?}", result);
+
+        let job = pipeline.get_job(&job_id).await.unwrap().unwrap();
+        assert_eq!(job.state, JobState::Canceled);
+        assert!(!fixture.path("doc.b").exists());
+        assert!(pipeline.get_artifacts(&job_id).await.unwrap().is_empty());
+
+        // 终态只能通过重试离开
+        assert!(pipeline.update_job_state(&job_id, JobState::Succeeded).await.is_err());
+        assert!(pipeline.update_job_state(&job_id, JobState::Queued).await.is_err());
+        assert!(pipeline.retry(&job_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fallback_to_next_route() {
+        let mut direct = capability("a-c", "text/a", "text/c");
+        direct.quality = 0.99;
+        let broken = FlakyPlugin::new(direct.clone(), u32::MAX, || AppError::ToolError("bad input".to_string()));
+
+        // 第一条路线 (直接转换) 因工具错误失败，或输出被质量检查拒绝
+        let cases: Vec<(Arc<dyn ConverterPlugin>, Option<Arc<dyn QualityCheck>>, AttemptOutcome)> = vec![
+            (broken, None, AttemptOutcome::Failed),
+            (Arc::new(AppendPlugin(direct)), Some(Arc::new(RejectConverter("a-c"))), AttemptOutcome::Rejected),
+        ];
+        for (first_route, check, outcome) in cases {
+            let fixture = Fixture::new(vec![
+                first_route,
+                append("a-b", "text/a", "text/b"),
+                append("b-c", "text/b", "text/c"),
+            ]);
+            let pipeline = fixture.pipeline();
+            if let Some(check) = check {
+                pipeline.set_quality_check(check);
+            }
+            tokio::spawn(pipeline.clone().run_worker());
+            let job_id = pipeline.submit(fixture.job("c")).await.unwrap();
+
+            let job = wait_finished(&pipeline, &job_id).await;
+            assert_eq!(job.state, JobState::Succeeded, "{:?}", job.error);
+            assert_eq!(std::fs::read_to_string(fixture.path("doc.c")).unwrap(), "start|a-b|b-c");
+            let history: Vec<_> = job
+                .attempts
+                .iter()
+                .map(|a| (a.route, a.converters.join(","), a.outcome))
+                .collect();
+            assert_eq!(
+                history,
+                vec![
+                    (1, "a-c".to_string(), outcome),
+                    (2, "a-b,b-c".to_string(), AttemptOutcome::Succeeded),
+                ]
+            );
+            assert!(job.attempts[0].error.is_some());
+        }
+    }
+
+    /// 记录同时运行的调用数的测试转换器
+    struct GaugePlugin {
+        record: CapabilityRecord,
+        running: std::sync::atomic::AtomicU32,
+        peak: std::sync::atomic::AtomicU32,
+    }
+
+    impl ConverterPlugin for GaugePlugin {
+        fn capability(&self) -> CapabilityRecord {
+            self.record.clone()
+        }
+
+        fn convert(&self, input: &Path, output: &Path, params: &Value) -> Result<()> {
+            use std::sync::atomic::Ordering;
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+            std::thread::sleep(std::time::Duration::from_millis(30));
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            AppendPlugin(self.record.clone()).convert(input, output, params)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_worker_respects_concurrency_limits() {
+        // (max_concurrent_jobs, parallelism, 预期峰值)
+        for (max_jobs, parallelism, expected) in [(2, 8, 2), (8, 3, 3)] {
+            let mut record = capability("gauge", "text/markdown", "text/html");
+            record.parallelism = parallelism;
+            let plugin = Arc::new(GaugePlugin {
+                record,
+                running: Default::default(),
+                peak: Default::default(),
+            });
+            let fixture = Fixture::new(vec![plugin.clone()]);
+            let pipeline = fixture.pipeline();
+            pipeline.set_limits(SchedulerLimits {
+                max_concurrent_jobs: max_jobs,
+                cpu_slots: 8,
+                io_slots: 8,
+                gpu_slots: 1,
+                memory_budget_mb: 100_000,
+            });
+            tokio::spawn(pipeline.clone().run_worker());
+
+            let mut job_ids = vec![];
+            for i in 0..8 {
+                let input = fixture.path(&format!("doc{}.md", i));
+                std::fs::write(&input, "# title").unwrap();
+                let job = Job::new(vec![input.to_string_lossy().to_string()], "html".to_string(), None, Value::Null);
+                job_ids.push(pipeline.submit(job).await.unwrap());
+            }
+            for job_id in &job_ids {
+                assert_eq!(wait_finished(&pipeline, job_id).await.state, JobState::Succeeded);
+            }
+            assert_eq!(plugin.peak.load(std::sync::atomic::Ordering::SeqCst), expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_worker_dispatches_by_priority() {
+        let fixture = Fixture::new(vec![Arc::new(GaugePlugin {
+            record: capability("gauge", "text/markdown", "text/html"),
+            running: Default::default(),
+            peak: Default::default(),
+        })]);
+        let pipeline = fixture.pipeline();
+        pipeline.set_limits(SchedulerLimits {
+            max_concurrent_jobs: 1,
+            ..SchedulerLimits::default()
+        });
+
+        let mut ids = vec![];
+        for i in 0..4 {
+            let input = fixture.path(&format!("batch{}.md", i));
+            std::fs::write(&input, "# title").unwrap();
+            let job = Job::new(vec![input.to_string_lossy().to_string()], "html".to_string(), None, Value::Null);
+            ids.push(pipeline.submit(job).await.unwrap());
+        }
+
+        // 最后提交的任务移到队首，第一个任务推迟，第二个任务提高优先级
+        pipeline.move_to_top(&ids[3]).await.unwrap();
+        pipeline.defer(&ids[0]).await.unwrap();
+        pipeline.set_priority(&ids[1], 5).await.unwrap();
+        let expected = vec![ids[1].clone(), ids[3].clone(), ids[2].clone(), ids[0].clone()];
+        assert_eq!(pipeline.queue.lock().unwrap().job_ids(), expected);
+
+        tokio::spawn(pipeline.clone().run_worker());
+        let mut started = vec![];
+        for job_id in &ids {
+            let job = wait_finished(&pipeline, job_id).await;
+            assert_eq!(job.state, JobState::Succeeded);
+            started.push((job.started_at.unwrap(), job.id));
+        }
+        started.sort();
+        let order: Vec<String> = started.into_iter().map(|(_, id)| id).collect();
+        assert_eq!(order, expected);
+
+        // 已开始执行的任务不能再调整排队顺序
+        assert!(pipeline.move_to_top(&ids[0]).await.is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_cancel_kills_tool_and_cleans_workspace() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let fixture = Fixture::new(vec![]);
+        let tool = fixture.path("hang");
+        std::fs::write(&tool, "#!/bin/sh\necho partial > \"$2\"\nsleep 30\n").unwrap();
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut record = capability("hang-b-c", "text/b", "text/c");
+        record.binary_path = Some(tool.to_string_lossy().to_string());
+        record.command = Some(CommandTemplate {
+            tool: "hang".to_string(),
+            args: vec!["{input}".to_string(), "{output}".to_string()],
+            options: Default::default(),
+        });
+        fixture.registry.register(record).unwrap();
+        fixture.registry.register_plugin(append("a-b", "text/a", "text/b")).unwrap();
+
+        let pipeline = fixture.pipeline();
+        let job_id = pipeline.submit(fixture.job("c")).await.unwrap();
+        let path = conversion_path(vec![step("a-b", "text/a", "text/b"), step("hang-b-c", "text/b", "text/c")]);
+        let task = tokio::spawn({
+            let pipeline = pipeline.clone();
+            let job_id = job_id.clone();
+            async move { pipeline.execute(&job_id, path).await }
+        });
+
+        let mut pids = vec![];
+        for _ in 0..200 {
+            pids = ProcessTable::global().pids(&job_id);
+            if !pids.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        assert!(!pids.is_empty(), "tool process was not registered");
+        let workspace = pipeline.job_workspace(&job_id).unwrap();
+        assert!(workspace.join("step-1.b").is_file());
+
+        let started = std::time::Instant::now();
+        pipeline.cancel(&job_id).await.unwrap();
+        let result = tokio::time::timeout(std::time::Duration::from_secs(3), task)
+            .await
+            .expect("canceled job did not stop")
+            .unwrap();
+        assert!(matches!(result, Err(AppError::Cancelled)), "{:?}", result);
+        assert!(started.elapsed() < CANCEL_GRACE_PERIOD);
+
+        // 进程组已结束，中间文件已清理，也不会生成产物
+        assert!(!Path::new(&format!("/proc/{}", pids[0])).exists());
+        assert!(ProcessTable::global().pids(&job_id).is_empty());
+        assert!(!workspace.exists());
+        assert!(!fixture.path("doc.c").exists());
+        let job = pipeline.get_job(&job_id).await.unwrap().unwrap();
+        assert_eq!(job.state, JobState::Canceled);
+        assert_eq!(job.attempts.last().unwrap().outcome, AttemptOutcome::Canceled);
+        assert!(pipeline.cancel(&job_id).await.is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_pause_suspends_tool_and_resume_continues() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let fixture = Fixture::new(vec![]);
+        let tool = fixture.path("slow-copy");
+        std::fs::write(&tool, "#!/bin/sh\nsleep 0.3\ncat \"$1\" > \"$2\"\n").unwrap();
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut record = capability("slow-a-b", "text/a", "text/b");
+        record.binary_path = Some(tool.to_string_lossy().to_string());
+        record.command = Some(CommandTemplate {
+            tool: "slow-copy".to_string(),
+            args: vec!["{input}".to_string(), "{output}".to_string()],
+            options: Default::default(),
+        });
+        fixture.registry.register(record).unwrap();
+        fixture.registry.register_plugin(append("b-c", "text/b", "text/c")).unwrap();
+
+        let pipeline = fixture.pipeline();
+
+        // 排队中的任务暂停后恢复为排队
+        let queued = pipeline.submit(fixture.job("c")).await.unwrap();
+        pipeline.pause(&queued).await.unwrap();
+        assert_eq!(pipeline.get_job(&queued).await.unwrap().unwrap().state, JobState::Paused);
+        pipeline.resume(&queued).await.unwrap();
+        assert_eq!(pipeline.get_job(&queued).await.unwrap().unwrap().state, JobState::Queued);
+        assert!(pipeline.resume(&queued).await.is_err());
+
+        let job_id = pipeline.submit(fixture.job("c")).await.unwrap();
+        let path = conversion_path(vec![step("slow-a-b", "text/a", "text/b"), step("b-c", "text/b", "text/c")]);
+        let task = tokio::spawn({
+            let pipeline = pipeline.clone();
+            let job_id = job_id.clone();
+            async move { pipeline.execute(&job_id, path).await }
+        });
+
+        let mut pids = vec![];
+        for _ in 0..200 {
+            pids = ProcessTable::global().pids(&job_id);
+            if !pids.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        assert!(!pids.is_empty(), "tool process was not registered");
+
+        pipeline.pause(&job_id).await.unwrap();
+        // SIGSTOP 异步送达，稍等进程进入停止状态
+        let mut stat = String::new();
+        for _ in 0..500 {
+            stat = std::fs::read_to_string(format!("/proc/{}/stat", pids[0])).unwrap();
+            if stat.rsplit(')').next().unwrap().split_whitespace().next() == Some("T") {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+        }
+        let state = stat.rsplit(')').next().unwrap().split_whitespace().next().unwrap();
+        assert_eq!(state, "T", "{}", stat);
+
+        // 挂起期间工具不会完成，后续步骤也不会被调度
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+        let job = pipeline.get_job(&job_id).await.unwrap().unwrap();
+        assert_eq!(job.state, JobState::Paused);
+        let workspace = pipeline.job_workspace(&job_id).unwrap();
+        assert!(!workspace.join("step-1.b").exists());
+
+        pipeline.resume(&job_id).await.unwrap();
+        task.await.unwrap().unwrap();
+        let job = pipeline.get_job(&job_id).await.unwrap().unwrap();
+        assert_eq!(job.state, JobState::Succeeded);
+        assert!(ProcessTable::global().pids(&job_id).is_empty());
+        assert_eq!(std::fs::read_to_string(fixture.path("doc.c")).unwrap(), "start|b-c");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_paused_job_releases_concurrency_slot() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let fixture = Fixture::new(vec![]);
+        let tool = fixture.path("slow-copy");
+        std::fs::write(&tool, "#!/bin/sh\nsleep 0.3\ncat \"$1\" > \"$2\"\n").unwrap();
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut record = capability("slow-a-b", "text/a", "text/b");
+        record.binary_path = Some(tool.to_string_lossy().to_string());
+        record.command = Some(CommandTemplate {
+            tool: "slow-copy".to_string(),
+            args: vec!["{input}".to_string(), "{output}".to_string()],
+            options: Default::default(),
+        });
+        fixture.registry.register(record).unwrap();
+        fixture.registry.register_plugin(append("b-c", "text/b", "text/c")).unwrap();
+        let pipeline = fixture.pipeline();
+        pipeline.set_limits(SchedulerLimits {
+            max_concurrent_jobs: 1,
+            ..SchedulerLimits::default()
+        });
+        tokio::spawn(pipeline.clone().run_worker());
+
+        let paused = pipeline.submit(fixture.job("c")).await.unwrap();
+        for _ in 0..200 {
+            if !ProcessTable::global().pids(&paused).is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        pipeline.pause(&paused).await.unwrap();
+
+        // 唯一的并发名额被暂停的任务占用时，新任务仍能执行
+        let fast = fixture.path("other.b");
+        std::fs::write(&fast, "start").unwrap();
+        let job_id = pipeline
+            .submit(Job::new(vec![fast.to_string_lossy().to_string()], "c".to_string(), None, Value::Null))
+            .await
+            .unwrap();
+        assert_eq!(wait_finished(&pipeline, &job_id).await.state, JobState::Succeeded);
+        assert_eq!(pipeline.get_job(&paused).await.unwrap().unwrap().state, JobState::Paused);
+
+        pipeline.resume(&paused).await.unwrap();
+        assert_eq!(wait_finished(&pipeline, &paused).await.state, JobState::Succeeded);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_paused_time_not_counted_towards_timeout() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let fixture = Fixture::new(vec![]);
+        let tool = fixture.path("slow-copy");
+        std::fs::write(&tool, "#!/bin/sh\nsleep 0.5\ncat \"$1\" > \"$2\"\n").unwrap();
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut record = capability("slow-a-b", "text/a", "text/b");
+        record.binary_path = Some(tool.to_string_lossy().to_string());
+        record.timeout_s = Some(1);
+        // 不自动重试，超时会直接使任务失败
+        record.retry = Some(RetryPolicy {
+            max_retries: 0,
+            ..Default::default()
+        });
+        record.command = Some(CommandTemplate {
+            tool: "slow-copy".to_string(),
+            args: vec!["{input}".to_string(), "{output}".to_string()],
+            options: Default::default(),
+        });
+        fixture.registry.register(record).unwrap();
+
+        let pipeline = fixture.pipeline();
+        let job_id = pipeline.submit(fixture.job("b")).await.unwrap();
+        let path = conversion_path(vec![step("slow-a-b", "text/a", "text/b")]);
+        let task = tokio::spawn({
+            let pipeline = pipeline.clone();
+            let job_id = job_id.clone();
+            async move { pipeline.execute(&job_id, path).await }
+        });
+
+        for _ in 0..200 {
+            if !ProcessTable::global().pids(&job_id).is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        assert!(!ProcessTable::global().pids(&job_id).is_empty(), "tool process was not registered");
+
+        // 暂停时间远超 timeout_s，恢复后工具仍在剩余时间内完成
+        pipeline.pause(&job_id).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        pipeline.resume(&job_id).await.unwrap();
+        task.await.unwrap().unwrap();
+
+        let job = pipeline.get_job(&job_id).await.unwrap().unwrap();
+        assert_eq!(job.state, JobState::Succeeded, "{:?}", job.error);
+        assert_eq!(job.attempts.len(), 1);
+        assert_eq!(std::fs::read_to_string(fixture.path("doc.b")).unwrap(), "start");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_failed_tool_stderr_recorded_in_job_error() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let fixture = Fixture::new(vec![]);
+        let tool = fixture.path("broken-tool");
+        std::fs::write(&tool, "#!/bin/sh\necho 'unsupported input' >&2\nexit 1\n").unwrap();
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut record = capability("broken-a-b", "text/a", "text/b");
+        record.binary_path = Some(tool.to_string_lossy().to_string());
+        record.command = Some(CommandTemplate {
+            tool: "broken-tool".to_string(),
+            args: vec!["{input}".to_string(), "{output}".to_string()],
+            options: Default::default(),
+        });
+        fixture.registry.register(record).unwrap();
+
+        let pipeline = fixture.pipeline();
+        let job_id = pipeline.submit(fixture.job("b")).await.unwrap();
+        let path = conversion_path(vec![step("broken-a-b", "text/a", "text/b")]);
+
+        assert!(pipeline.execute(&job_id, path).await.is_err());
+        let job = pipeline.get_job(&job_id).await.unwrap().unwrap();
+        assert_eq!(job.state, JobState::Failed);
+        let error = job.error.unwrap();
+        assert!(error.contains("broken-a-b") && error.contains("unsupported input"), "{}", error);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_tool_progress_reported_during_step() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // 以 magick 命名的脚本按 -monitor 的格式输出进度
+        let fixture = Fixture::new(vec![]);
+        let tool = fixture.path("magick");
+        std::fs::write(
+            &tool,
+            "#!/bin/sh\n\
+             printf 'load image[%s]: 1 of 4, 025%% complete\\r' \"$1\" >&2\n\
+             sleep 0.1\n\
+             printf 'resize image[%s]: 2 of 4, 050%% complete\\n' \"$1\" >&2\n\
+             sleep 0.1\n\
+             cp \"$1\" \"$2\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut record = capability("magick-a-b", "text/a", "text/b");
+        record.binary_path = Some(tool.to_string_lossy().to_string());
+        record.command = Some(CommandTemplate {
+            tool: "magick".to_string(),
+            args: vec!["{input}".to_string(), "{output}".to_string()],
+            options: Default::default(),
+        });
+        fixture.registry.register(record).unwrap();
+        let sink = Arc::new(CollectingSink::default());
+        let pipeline = fixture.pipeline();
+        pipeline.set_event_sink(sink.clone());
+
+        let job_id = pipeline.submit(fixture.job("b")).await.unwrap();
+        let path = ConversionPath {
+            estimated_time_ms: 1000,
+            ..conversion_path(vec![step("magick-a-b", "text/a", "text/b")])
+        };
+        pipeline.execute(&job_id, path).await.unwrap();
+
+        let progress: Vec<String> = sink
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|event| match event {
+                PipelineEvent::JobProgress(p) => Some(format!("{} {} {:?}", p.progress, p.stage, p.eta_ms)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            progress,
+            vec![
+                "0 magick-a-b Some(1000)",
+                "0.25 magick-a-b: load image Some(750)",
+                "0.5 magick-a-b: resize image Some(500)",
+                "1 完成 Some(0)",
+            ]
+        );
+    }
+}
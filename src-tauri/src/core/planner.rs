@@ -1,74 +1,421 @@
 use crate::error::Result;
-use crate::types::{ConversionPath, ConversionStep, ConversionTarget};
+use crate::types::{
+    CapabilityRecord, ConversionOptions, ConversionPath, ConversionStep, ConversionTarget,
+    FeatureLoss, FileDetection, FormatSpec, MissingRequirement, PreserveConstraints, PreserveFeatures,
+    PreserveLevel, ScoringPresets, ScoringWeights,
+};
 use crate::core::registry::Registry;
+use crate::core::settings::SettingsStore;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+use std::sync::Arc;
+
+/// 单条路径允许的最大步数
+const MAX_HOPS: usize = 4;
+
+/// 每个目标最多返回的备选路径数
+const MAX_ALTERNATIVE_PATHS: usize = 3;
+
+/// Yen 算法最多枚举 `MAX_ALTERNATIVE_PATHS * CANDIDATE_FACTOR` 条路径以挑选足够不同的备选
+const CANDIDATE_FACTOR: usize = 4;
+
+/// 两条路径共享的转换器占较短路径的比例达到该值时视为重复
+const MAX_PATH_SIMILARITY: f32 = 0.5;
+
+/// 速度评分为 1.0 时单步的基准耗时
+const BASE_STEP_TIME_MS: f32 = 1000.0;
 
 /// 转换规划器 - 在转换图上寻径与评分
 pub struct Planner {
-    registry: std::sync::Arc<Registry>,
+    registry: Arc<Registry>,
+    scoring: ScoringPresets,
 }
 
 impl Planner {
     pub fn new() -> Self {
+        Self::with_registry(Registry::global())
+    }
+
+    /// 使用指定的注册表创建规划器，评分权重取自当前设置
+    pub fn with_registry(registry: Arc<Registry>) -> Self {
         Self {
-            registry: Registry::global(),
+            registry,
+            scoring: SettingsStore::global().get().scoring_weights,
+        }
+    }
+
+    /// 以探测到的文件格式为源格式查找，返回第一个非空的结果
+    ///
+    /// 格式图同时以 MIME 和扩展名为键，按 MIME 找不到结果时再按扩展名查找。
+    pub fn by_detected_source<T>(
+        detection: &FileDetection,
+        mut find: impl FnMut(&str) -> Result<Vec<T>>,
+    ) -> Result<Vec<T>> {
+        let mut found = Vec::new();
+        for source in [&detection.mime_type, &detection.extension] {
+            found = find(source)?;
+            if !found.is_empty() {
+                break;
+            }
         }
+        Ok(found)
     }
 
     /// 规划从源格式到目标格式的转换路径
+    ///
+    /// 在格式图上以 `-ln(quality)` 为边权运行 Dijkstra，使路径总质量
+    /// （各步质量之积）最大；总质量低于门槛、超过 `MAX_HOPS` 或重复
+    /// 经过同一格式的路径会被剪除。在最优路径之上用 Yen 算法继续
+    /// 枚举次优路径，并跳过与已选路径过于相似的候选，最多返回
+    /// `MAX_ALTERNATIVE_PATHS` 条，按 `options.quality_priority` 对应的
+    /// 权重评分排序。任一步不满足 `options.preserve` 约束的路径不会返回；
+    /// 依赖缺失的路径仍会返回，但排在可执行路径之后。
     pub fn plan(
         &self,
         source_mime: &str,
         target_mime: &str,
         quality_threshold: f32,
+        options: &ConversionOptions,
     ) -> Result<Vec<ConversionPath>> {
-        // TODO: 实现图搜索算法（BFS/Dijkstra）
-        // 1. 直达路径优先
-        // 2. 考虑质量门槛
-        // 3. 多候选路径
-        // 4. 依赖检查
-
-        let mut paths = Vec::new();
-
-        // 简化实现：只尝试直接转换
-        let converters = self.registry.find_converters(source_mime, target_mime);
-
-        for converter_id in converters {
-            if let Some(capability) = self.registry.get(&converter_id) {
-                if capability.quality >= quality_threshold {
-                    paths.push(ConversionPath {
-                        steps: vec![ConversionStep {
-                            converter_id: converter_id.clone(),
-                            from_format: source_mime.to_string(),
-                            to_format: target_mime.to_string(),
-                            quality: capability.quality,
-                        }],
-                        total_quality: capability.quality,
-                        estimated_time_ms: 1000, // TODO: 实际估算
-                        risks: capability.risks.clone(),
-                    });
-                }
-            }
-        }
+        let weights = self.scoring.weights_for(options.quality_priority);
+        let found = self.k_shortest_paths(
+            source_mime,
+            target_mime,
+            quality_threshold,
+            &options.preserve,
+        );
 
-        // 按质量排序
+        let mut paths: Vec<ConversionPath> = found
+            .iter()
+            .map(|steps| self.build_path(steps, &weights))
+            .collect();
         paths.sort_by(|a, b| {
-            b.total_quality
-                .partial_cmp(&a.total_quality)
-                .unwrap_or(std::cmp::Ordering::Equal)
+            a.missing_requirements
+                .is_empty()
+                .cmp(&b.missing_requirements.is_empty())
+                .reverse()
+                .then_with(|| b.score.total_cmp(&a.score))
         });
 
         Ok(paths)
     }
 
     /// 获取给定文件的所有可能目标格式
-    pub fn get_available_targets(&self, source_mime: &str) -> Vec<ConversionTarget> {
-        self.registry.get_targets(source_mime)
+    ///
+    /// 每个可达格式附带若干备选路径。没有任何路径可执行的目标仍会列出，
+    /// 但标记为不可用并给出最优路径缺失的依赖；可用目标中最优路径得分
+    /// 最高者被标记为推荐。
+    pub fn get_available_targets(
+        &self,
+        source_mime: &str,
+        options: &ConversionOptions,
+    ) -> Vec<ConversionTarget> {
+        let mut targets = Vec::new();
+
+        for spec in self.reachable_formats(source_mime) {
+            let paths = match self.plan(source_mime, &spec.mime, 0.0, options) {
+                Ok(paths) if !paths.is_empty() => paths,
+                _ => continue,
+            };
+
+            // 路径已按可执行优先排序，首条路径决定目标是否可用
+            let missing_requirements = paths[0].missing_requirements.clone();
+
+            targets.push(ConversionTarget {
+                format: spec.extensions.first().cloned().unwrap_or_else(|| spec.mime.clone()),
+                mime_type: spec.mime,
+                paths,
+                recommended: false,
+                available: missing_requirements.is_empty(),
+                missing_requirements,
+            });
+        }
+
+        targets.sort_by(|a, b| {
+            a.available
+                .cmp(&b.available)
+                .reverse()
+                .then_with(|| b.paths[0].score.total_cmp(&a.paths[0].score))
+        });
+        if let Some(best) = targets.first_mut().filter(|t| t.available) {
+            best.recommended = true;
+        }
+
+        targets
+    }
+
+    /// 收集 `MAX_HOPS` 步内可达的所有输出格式（按 MIME 去重）
+    fn reachable_formats(&self, source: &str) -> Vec<FormatSpec> {
+        let mut formats: Vec<FormatSpec> = Vec::new();
+        let mut frontier = vec![source.to_string()];
+
+        for _ in 0..MAX_HOPS {
+            let mut next = Vec::new();
+            for format in &frontier {
+                for (_, output) in self.registry.edges_from(format) {
+                    if output.mime == source || formats.iter().any(|f| f.mime == output.mime) {
+                        continue;
+                    }
+                    next.push(output.mime.clone());
+                    formats.push(output);
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+
+        formats
+    }
+
+    /// Yen 算法枚举 K 条最优且彼此足够不同的路径
+    fn k_shortest_paths(
+        &self,
+        source: &str,
+        target: &str,
+        quality_threshold: f32,
+        constraints: &PreserveConstraints,
+    ) -> Vec<Vec<PlannedStep>> {
+        let root = SearchState {
+            cost: 0.0,
+            format: source.to_string(),
+            visited: vec![source.to_string()],
+            steps: Vec::new(),
+        };
+        let no_banned = HashSet::new();
+        let Some(first) =
+            self.shortest_path(root, target, quality_threshold, constraints, &no_banned)
+        else {
+            return Vec::new();
+        };
+
+        let mut found: Vec<SearchState> = vec![first];
+        let mut accepted: Vec<Vec<PlannedStep>> = vec![found[0].steps.clone()];
+        let mut candidates: Vec<SearchState> = Vec::new();
+
+        while accepted.len() < MAX_ALTERNATIVE_PATHS
+            && found.len() < MAX_ALTERNATIVE_PATHS * CANDIDATE_FACTOR
+        {
+            let last = &found[found.len() - 1];
+
+            for i in 0..last.steps.len() {
+                let root_steps = &last.steps[..i];
+
+                // 剪掉所有共享同一前缀的已知路径在第 i 步使用的边
+                let mut banned = HashSet::new();
+                for path in &found {
+                    if path.steps.len() > i && same_route(&path.steps[..i], root_steps) {
+                        let step = &path.steps[i];
+                        banned.insert((step.capability.id.clone(), step.output.mime.clone()));
+                    }
+                }
+
+                let mut visited = vec![source.to_string()];
+                visited.extend(root_steps.iter().map(|s| s.output.mime.clone()));
+                let spur_root = SearchState {
+                    cost: root_steps.iter().map(|s| -s.capability.quality.ln()).sum(),
+                    format: visited[visited.len() - 1].clone(),
+                    visited,
+                    steps: root_steps.to_vec(),
+                };
+
+                if let Some(candidate) =
+                    self.shortest_path(spur_root, target, quality_threshold, constraints, &banned)
+                {
+                    if !candidates.iter().any(|c| same_route(&c.steps, &candidate.steps)) {
+                        candidates.push(candidate);
+                    }
+                }
+            }
+
+            let Some(best) = candidates
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.cost.total_cmp(&b.cost))
+                .map(|(index, _)| index)
+            else {
+                break;
+            };
+            let next = candidates.swap_remove(best);
+
+            let distinct = accepted
+                .iter()
+                .all(|path| route_similarity(path, &next.steps) < MAX_PATH_SIMILARITY);
+            if distinct {
+                accepted.push(next.steps.clone());
+            }
+            found.push(next);
+        }
+
+        accepted
     }
 
-    /// 评估路径质量得分
-    fn score_path(&self, path: &ConversionPath) -> f32 {
-        // TODO: 综合考虑质量、速度、成本
-        path.total_quality
+    /// 从给定状态出发搜索总质量最高的路径，`banned` 中的边在分叉点处不可用
+    ///
+    /// 路径的保留能力取各步最弱者，因此逐步检查约束即可剪除整条违规路径。
+    fn shortest_path(
+        &self,
+        start: SearchState,
+        target: &str,
+        quality_threshold: f32,
+        constraints: &PreserveConstraints,
+        banned: &HashSet<(String, String)>,
+    ) -> Option<SearchState> {
+        let spur_depth = start.steps.len();
+        let mut heap = BinaryHeap::new();
+        let mut settled: HashSet<(String, usize)> = HashSet::new();
+
+        heap.push(start);
+
+        while let Some(state) = heap.pop() {
+            let hops = state.steps.len();
+            if !settled.insert((state.format.clone(), hops)) {
+                continue;
+            }
+
+            if hops > spur_depth {
+                if let Some(last) = state.steps.last() {
+                    if format_matches(&last.output, target) {
+                        return Some(state);
+                    }
+                }
+            }
+
+            if hops >= MAX_HOPS {
+                continue;
+            }
+
+            for (converter_id, output) in self.registry.edges_from(&state.format) {
+                let edge = (converter_id.clone(), output.mime.clone());
+                if hops == spur_depth && banned.contains(&edge) {
+                    continue;
+                }
+                let Some(capability) = self.registry.lookup(&converter_id) else {
+                    continue;
+                };
+                if capability.quality <= 0.0 || state.visited.contains(&output.mime) {
+                    continue;
+                }
+                if !constraints.allows(&capability.preserve) {
+                    continue;
+                }
+
+                let cost = state.cost - capability.quality.ln();
+                if (-cost).exp() < quality_threshold {
+                    continue;
+                }
+
+                let mut visited = state.visited.clone();
+                visited.push(output.mime.clone());
+                let mut steps = state.steps.clone();
+                steps.push(PlannedStep {
+                    capability,
+                    from: state.format.clone(),
+                    output: output.clone(),
+                });
+
+                heap.push(SearchState {
+                    cost,
+                    format: output.mime,
+                    visited,
+                    steps,
+                });
+            }
+        }
+
+        None
+    }
+
+    /// 由搜索结果组装转换路径
+    fn build_path(&self, planned: &[PlannedStep], weights: &ScoringWeights) -> ConversionPath {
+        let mut steps = Vec::with_capacity(planned.len());
+        let mut total_quality = 1.0;
+        let mut estimated_time_ms = 0;
+        let mut risks: Vec<String> = Vec::new();
+        let mut preserve = PreserveFeatures::default();
+        let mut losses: Vec<FeatureLoss> = Vec::new();
+        let mut missing_requirements: Vec<MissingRequirement> = Vec::new();
+
+        for step in planned {
+            let capability = &step.capability;
+            total_quality *= capability.quality;
+
+            for missing in self.registry.missing_requirements(&capability.requires) {
+                if !missing_requirements.contains(&missing) {
+                    missing_requirements.push(missing);
+                }
+            }
+
+            preserve = preserve.weakest(&capability.preserve);
+            for (feature, level) in capability.preserve.levels() {
+                if level == PreserveLevel::True {
+                    continue;
+                }
+                match losses.iter_mut().find(|loss| loss.feature == feature) {
+                    Some(loss) if level.satisfies(loss.level) => {}
+                    Some(loss) => {
+                        loss.level = level;
+                        loss.converter_id = capability.id.clone();
+                    }
+                    None => losses.push(FeatureLoss {
+                        feature: feature.to_string(),
+                        level,
+                        converter_id: capability.id.clone(),
+                    }),
+                }
+            }
+
+            estimated_time_ms += estimate_step_time_ms(capability);
+            for risk in &capability.risks {
+                if !risks.contains(risk) {
+                    risks.push(risk.clone());
+                }
+            }
+
+            steps.push(ConversionStep {
+                converter_id: capability.id.clone(),
+                from_format: step.from.clone(),
+                to_format: step.output.mime.clone(),
+                quality: capability.quality,
+            });
+        }
+
+        let score = self.score_path(planned, total_quality, estimated_time_ms, weights);
+
+        ConversionPath {
+            steps,
+            total_quality,
+            estimated_time_ms,
+            risks,
+            score,
+            preserve,
+            losses,
+            missing_requirements,
+        }
+    }
+
+    /// 评估路径综合得分
+    ///
+    /// 路径速度为各步耗时合成后的等效速度评分，成本为各步资源成本
+    /// (CPU/内存/GPU 的均值) 之和。
+    fn score_path(
+        &self,
+        planned: &[PlannedStep],
+        total_quality: f32,
+        estimated_time_ms: u64,
+        weights: &ScoringWeights,
+    ) -> f32 {
+        let speed = BASE_STEP_TIME_MS / estimated_time_ms.max(1) as f32;
+        let cost: f32 = planned
+            .iter()
+            .map(|step| {
+                let cost = &step.capability.cost;
+                (cost.cpu + cost.memory + cost.gpu) / 3.0
+            })
+            .sum();
+
+        weights.quality * total_quality + weights.speed * speed - weights.cost * cost
     }
 }
 
@@ -77,3 +424,340 @@ impl Default for Planner {
         Self::new()
     }
 }
+
+/// 搜索中已确定的一步
+#[derive(Clone)]
+struct PlannedStep {
+    capability: CapabilityRecord,
+    from: String,
+    output: FormatSpec,
+}
+
+/// 优先队列中的搜索状态，按累计代价升序出队
+struct SearchState {
+    cost: f32,
+    format: String,
+    visited: Vec<String>,
+    steps: Vec<PlannedStep>,
+}
+
+impl PartialEq for SearchState {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SearchState {}
+
+impl PartialOrd for SearchState {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SearchState {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap 是大顶堆，反转比较使代价小、步数少者先出队
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.steps.len().cmp(&self.steps.len()))
+    }
+}
+
+/// 判断两段路径是否经过相同的转换器与格式
+fn same_route(a: &[PlannedStep], b: &[PlannedStep]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).all(|(x, y)| {
+            x.capability.id == y.capability.id && x.output.mime == y.output.mime
+        })
+}
+
+/// 两条路径的重叠系数：共享转换器数 / 较短路径的转换器数
+fn route_similarity(a: &[PlannedStep], b: &[PlannedStep]) -> f32 {
+    let a: HashSet<&str> = a.iter().map(|s| s.capability.id.as_str()).collect();
+    let b: HashSet<&str> = b.iter().map(|s| s.capability.id.as_str()).collect();
+    let smaller = a.len().min(b.len());
+    if smaller == 0 {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f32 / smaller as f32
+}
+
+/// 判断输出格式是否命中目标（MIME 或扩展名）
+fn format_matches(spec: &FormatSpec, target: &str) -> bool {
+    spec.mime == target || spec.extensions.iter().any(|ext| ext == target)
+}
+
+/// 根据速度评分估算单步耗时
+fn estimate_step_time_ms(capability: &CapabilityRecord) -> u64 {
+    (BASE_STEP_TIME_MS / capability.speed.max(0.1)) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::*;
+    use std::collections::HashMap;
+
+    const DOCX: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+    fn capability(id: &str, from: &str, to: &str, quality: f32) -> CapabilityRecord {
+        CapabilityRecord {
+            quality,
+            speed: 0.5,
+            cost: ResourceCost {
+                cpu: 0.5,
+                memory: 0.5,
+                gpu: 0.0,
+            },
+            risks: vec![format!("{} risk", id)],
+            ..test_support::capability(id, from, to)
+        }
+    }
+
+    fn options(priority: QualityPriority) -> ConversionOptions {
+        ConversionOptions {
+            quality_priority: priority,
+            ..Default::default()
+        }
+    }
+
+    /// 以质量优先规划，使结果顺序与总质量一致
+    fn plan(planner: &Planner, from: &str, to: &str, threshold: f32) -> Vec<ConversionPath> {
+        planner
+            .plan(from, to, threshold, &options(QualityPriority::Quality))
+            .unwrap()
+    }
+
+    fn planner(capabilities: Vec<CapabilityRecord>) -> Planner {
+        let registry = Arc::new(Registry::new());
+        for capability in capabilities {
+            registry.register(capability).unwrap();
+        }
+        Planner::with_registry(registry)
+    }
+
+    #[test]
+    fn test_plan_multi_hop() {
+        let planner = planner(vec![
+            capability("docx-md", DOCX, "text/markdown", 0.9),
+            capability("md-html", "text/markdown", "text/html", 0.95),
+        ]);
+
+        let paths = plan(&planner, DOCX, "text/html", 0.0);
+        assert_eq!(paths.len(), 1);
+
+        let path = &paths[0];
+        let ids: Vec<_> = path.steps.iter().map(|s| s.converter_id.as_str()).collect();
+        assert_eq!(ids, vec!["docx-md", "md-html"]);
+        assert!((path.total_quality - 0.855).abs() < 1e-4);
+        assert_eq!(path.risks, vec!["docx-md risk", "md-html risk"]);
+
+        // 扩展名同样可以作为目标
+        assert_eq!(plan(&planner, DOCX, "html", 0.0).len(), 1);
+    }
+
+    #[test]
+    fn test_plan_prefers_higher_product_quality() {
+        let planner = planner(vec![
+            capability("a-c", "x/a", "x/c", 0.7),
+            capability("a-b", "x/a", "x/b", 0.95),
+            capability("b-c", "x/b", "x/c", 0.95),
+        ]);
+
+        let paths = plan(&planner, "x/a", "x/c", 0.0);
+        let ids: Vec<_> = paths[0].steps.iter().map(|s| s.converter_id.as_str()).collect();
+        assert_eq!(ids, vec!["a-b", "b-c"]);
+    }
+
+    #[test]
+    fn test_plan_respects_threshold_and_hop_limit() {
+        let planner = planner(vec![
+            capability("a-b", "x/a", "x/b", 0.8),
+            capability("b-c", "x/b", "x/c", 0.8),
+            capability("c-d", "x/c", "x/d", 1.0),
+            capability("d-e", "x/d", "x/e", 1.0),
+            capability("e-f", "x/e", "x/f", 1.0),
+        ]);
+
+        assert_eq!(plan(&planner, "x/a", "x/c", 0.6).len(), 1);
+        assert!(plan(&planner, "x/a", "x/c", 0.7).is_empty());
+        assert_eq!(plan(&planner, "x/a", "x/e", 0.0).len(), 1);
+        assert!(plan(&planner, "x/a", "x/f", 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_plan_avoids_cycles() {
+        let planner = planner(vec![
+            capability("a-b", "x/a", "x/b", 1.0),
+            capability("b-a", "x/b", "x/a", 1.0),
+        ]);
+
+        assert!(plan(&planner, "x/a", "x/c", 0.0).is_empty());
+        assert!(plan(&planner, "x/a", "x/a", 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_plan_returns_diverse_alternatives() {
+        let planner = planner(vec![
+            capability("a-d", "x/a", "x/d", 0.6),
+            capability("a-b", "x/a", "x/b", 0.95),
+            capability("b-d", "x/b", "x/d", 0.95),
+            capability("a-c", "x/a", "x/c", 0.9),
+            capability("c-d", "x/c", "x/d", 0.9),
+            capability("b-c", "x/b", "x/c", 0.99),
+        ]);
+
+        let paths = plan(&planner, "x/a", "x/d", 0.0);
+        let routes: Vec<Vec<&str>> = paths
+            .iter()
+            .map(|p| p.steps.iter().map(|s| s.converter_id.as_str()).collect())
+            .collect();
+
+        // a-b-c-d 与最优路径共享 a-b，相似度过高被跳过
+        assert_eq!(
+            routes,
+            vec![vec!["a-b", "b-d"], vec!["a-c", "c-d"], vec!["a-d"]]
+        );
+        assert!(paths
+            .windows(2)
+            .all(|w| w[0].total_quality >= w[1].total_quality));
+    }
+
+    #[test]
+    fn test_available_targets_include_paths() {
+        let planner = planner(vec![
+            capability("docx-md", DOCX, "text/markdown", 0.9),
+            capability("md-html", "text/markdown", "text/html", 0.95),
+            capability("docx-pdf", DOCX, "application/pdf", 0.8),
+        ]);
+
+        let targets = planner.get_available_targets(DOCX, &options(QualityPriority::Balanced));
+        let formats: Vec<_> = targets.iter().map(|t| t.format.as_str()).collect();
+        assert_eq!(formats, vec!["markdown", "pdf", "html"]);
+        assert!(targets[0].recommended);
+        assert!(targets[1..].iter().all(|t| !t.recommended));
+        assert_eq!(targets[2].paths[0].steps.len(), 2);
+    }
+
+    #[test]
+    fn test_plan_order_follows_quality_priority() {
+        let mut faithful = capability("faithful", "x/a", "x/b", 0.95);
+        faithful.speed = 0.2;
+        let mut fast = capability("fast", "x/a", "x/b", 0.7);
+        fast.speed = 0.95;
+        let planner = planner(vec![faithful, fast]);
+
+        let by_quality = plan(&planner, "x/a", "x/b", 0.0);
+        assert_eq!(by_quality[0].steps[0].converter_id, "faithful");
+
+        let by_speed = planner
+            .plan("x/a", "x/b", 0.0, &options(QualityPriority::Speed))
+            .unwrap();
+        assert_eq!(by_speed[0].steps[0].converter_id, "fast");
+        assert!(by_speed[0].score > by_speed[1].score);
+    }
+
+    #[test]
+    fn test_plan_enforces_preserve_constraints() {
+        let mut lossy = capability("lossy", "x/a", "x/c", 0.95);
+        lossy.preserve.footnotes = PreserveLevel::False;
+        let mut partial = capability("a-b", "x/a", "x/b", 0.9);
+        partial.preserve.formulas = PreserveLevel::Partial;
+        let planner = planner(vec![lossy, partial, capability("b-c", "x/b", "x/c", 0.9)]);
+
+        let mut options = options(QualityPriority::Quality);
+        options.preserve.footnotes = Some(PreserveLevel::True);
+        options.preserve.formulas = Some(PreserveLevel::Partial);
+
+        let paths = planner.plan("x/a", "x/c", 0.0, &options).unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].steps[0].converter_id, "a-b");
+        assert_eq!(paths[0].preserve.formulas, PreserveLevel::Partial);
+        assert_eq!(paths[0].losses.len(), 1);
+        assert_eq!(paths[0].losses[0].feature, "formulas");
+        assert_eq!(paths[0].losses[0].converter_id, "a-b");
+
+        options.preserve.formulas = Some(PreserveLevel::True);
+        assert!(planner.plan("x/a", "x/c", 0.0, &options).unwrap().is_empty());
+
+        // 无约束时有损直达路径照常返回，并报告脚注损失
+        let paths = plan(&planner, "x/a", "x/c", 0.0);
+        assert_eq!(paths[0].steps[0].converter_id, "lossy");
+        assert_eq!(paths[0].losses[0].feature, "footnotes");
+        assert_eq!(paths[0].losses[0].level, PreserveLevel::False);
+    }
+
+    #[test]
+    fn test_targets_fall_back_to_extension() {
+        let mut record = capability("md-html", "text/markdown", "text/html", 0.9);
+        record.inputs[0].extensions = vec!["md".to_string()];
+        let planner = planner(vec![record]);
+        let mut detection = FileDetection {
+            path: "notes.md".to_string(),
+            mime_type: "text/plain".to_string(),
+            extension: "md".to_string(),
+            size_bytes: 0,
+            is_scanned_pdf: false,
+            metadata: serde_json::Value::Null,
+        };
+        let targets = |detection: &FileDetection| {
+            Planner::by_detected_source(detection, |source| {
+                Ok(planner.get_available_targets(source, &ConversionOptions::default()))
+            })
+            .unwrap()
+        };
+
+        // 探测到的 MIME 不在格式图中时按扩展名列出目标
+        let found = targets(&detection);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].mime_type, "text/html");
+
+        detection.extension = "txt".to_string();
+        assert!(targets(&detection).is_empty());
+    }
+
+    #[test]
+    fn test_targets_report_missing_requirements() {
+        let mut needs_tool = capability("needs-tool", "x/a", "x/b", 0.95);
+        needs_tool.requires.tools = vec!["f2f-test-missing-tool".to_string()];
+        let mut needs_lang = capability("needs-lang", "x/a", "x/c", 0.9);
+        needs_lang.requires.lang_packs = vec!["f2f_test_lang".to_string()];
+        let fallback = capability("fallback", "x/a", "x/b", 0.6);
+        let planner = planner(vec![needs_tool, needs_lang, fallback]);
+        let missing_tool = ToolHealth {
+            name: "f2f-test-missing-tool".to_string(),
+            available: false,
+            version: None,
+            path: None,
+            error: Some("not found".to_string()),
+        };
+        planner.registry.set_health(HealthReport {
+            tools: HashMap::from([(missing_tool.name.clone(), missing_tool)]),
+            lang_packs: vec!["eng".to_string()],
+            fonts: None,
+            isolation: IsolationLevel::Restricted,
+            timestamp: 0,
+        });
+
+        let paths = plan(&planner, "x/a", "x/b", 0.0);
+        assert_eq!(paths[0].steps[0].converter_id, "fallback");
+        assert!(paths[0].missing_requirements.is_empty());
+        assert_eq!(
+            paths[1].missing_requirements,
+            vec![MissingRequirement::not_installed(RequirementKind::Tool, "f2f-test-missing-tool")]
+        );
+
+        let targets = planner.get_available_targets("x/a", &options(QualityPriority::Quality));
+        assert_eq!(targets.len(), 2);
+        assert!(targets[0].available && targets[0].recommended);
+        assert_eq!(targets[0].mime_type, "x/b");
+
+        assert!(!targets[1].available && !targets[1].recommended);
+        assert_eq!(
+            targets[1].missing_requirements,
+            vec![MissingRequirement::not_installed(RequirementKind::LangPack, "f2f_test_lang")]
+        );
+    }
+}
@@ -0,0 +1,532 @@
+use crate::core::converter::ConverterPlugin;
+use crate::core::manifest::ManifestLoader;
+use crate::core::registry::Registry;
+use crate::core::wasm::{WasmConverter, MANIFEST_FILE, MODULE_FILE};
+use crate::error::{AppError, Result};
+use crate::types::{AppSettings, PluginInfo};
+use ed25519_dalek::{Signature, VerifyingKey};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, Manager};
+
+/// 插件包中的校验和文件 (十六进制 SHA-256)
+pub const CHECKSUM_FILE: &str = "checksum";
+/// 插件包中的签名文件 (十六进制 ed25519 签名，可选)
+pub const SIGNATURE_FILE: &str = "signature";
+/// 插件目录中的安装状态文件
+const STATE_FILE: &str = "install.json";
+
+/// 全局插件管理器实例
+static PLUGINS: Lazy<Arc<PluginManager>> =
+    Lazy::new(|| Arc::new(PluginManager::new(Registry::global())));
+
+/// 插件包 - zip 归档，包含清单、WASM 模块、校验和与可选签名
+pub struct PluginPackage {
+    pub manifest: Vec<u8>,
+    pub module: Vec<u8>,
+    pub checksum: String,
+    pub signature: Option<String>,
+}
+
+impl PluginPackage {
+    /// 读取插件包
+    pub fn read(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .map_err(|e| AppError::IoError(format!("{}: {}", path.display(), e)))?;
+        let mut archive = zip::ZipArchive::new(file)
+            .map_err(|e| AppError::ValidationError(format!("{}: 无效的插件包: {}", path.display(), e)))?;
+
+        let mut entry = |name: &str| -> Result<Option<Vec<u8>>> {
+            let mut file = match archive.by_name(name) {
+                Ok(file) => file,
+                Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+                Err(e) => return Err(AppError::ValidationError(format!("{}: {}", name, e))),
+            };
+            let mut content = Vec::new();
+            file.read_to_end(&mut content)?;
+            Ok(Some(content))
+        };
+        let required = |name: &str, content: Option<Vec<u8>>| {
+            content.ok_or_else(|| AppError::ValidationError(format!("插件包缺少 {}", name)))
+        };
+        let text = |content: Vec<u8>| String::from_utf8_lossy(&content).trim().to_string();
+
+        Ok(Self {
+            manifest: required(MANIFEST_FILE, entry(MANIFEST_FILE)?)?,
+            module: required(MODULE_FILE, entry(MODULE_FILE)?)?,
+            checksum: text(required(CHECKSUM_FILE, entry(CHECKSUM_FILE)?)?),
+            signature: entry(SIGNATURE_FILE)?.map(text),
+        })
+    }
+
+    /// 包内容摘要：依次对每个文件的名称、长度与内容做 SHA-256
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for (name, content) in [(MANIFEST_FILE, &self.manifest), (MODULE_FILE, &self.module)] {
+            hasher.update(name.as_bytes());
+            hasher.update((content.len() as u64).to_le_bytes());
+            hasher.update(content);
+        }
+        hasher.finalize().into()
+    }
+
+    /// 校验和与签名验证，返回插件是否已签名
+    ///
+    /// 签名必须能由设置中的某个受信任公钥验证；未签名插件只有在设置允许时才能安装。
+    pub fn verify(&self, settings: &AppSettings) -> Result<bool> {
+        let digest = self.digest();
+        if !self.checksum.eq_ignore_ascii_case(&hex::encode(digest)) {
+            return Err(AppError::ValidationError("插件包校验和不匹配".to_string()));
+        }
+
+        let Some(signature) = &self.signature else {
+            if settings.allow_unsigned_plugins {
+                return Ok(false);
+            }
+            return Err(AppError::ValidationError(
+                "插件未签名，如需安装请在设置中允许未签名插件".to_string(),
+            ));
+        };
+
+        let signature = hex::decode(signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or_else(|| AppError::ValidationError("插件签名格式无效".to_string()))?;
+
+        for key in &settings.trusted_plugin_keys {
+            let Some(key) = hex::decode(key)
+                .ok()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+            else {
+                tracing::warn!("Ignoring invalid trusted plugin key: {}", key);
+                continue;
+            };
+            if key.verify_strict(&digest, &signature).is_ok() {
+                return Ok(true);
+            }
+        }
+
+        Err(AppError::ValidationError(
+            "插件签名无法由任何受信任的密钥验证".to_string(),
+        ))
+    }
+}
+
+/// 插件安装状态，保存在插件目录的 install.json 中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PluginState {
+    signed: bool,
+    enabled: bool,
+    installed_at: i64,
+}
+
+/// 插件管理器 - 安装、启用/禁用、卸载插件并同步更新注册表
+pub struct PluginManager {
+    registry: Arc<Registry>,
+    dir: RwLock<Option<PathBuf>>,
+}
+
+impl PluginManager {
+    pub fn new(registry: Arc<Registry>) -> Self {
+        Self {
+            registry,
+            dir: RwLock::new(None),
+        }
+    }
+
+    /// 获取全局插件管理器实例
+    pub fn global() -> Arc<PluginManager> {
+        PLUGINS.clone()
+    }
+
+    /// 插件安装目录
+    pub fn plugins_dir(app_handle: &AppHandle) -> Result<PathBuf> {
+        let app_data_dir = app_handle
+            .path()
+            .app_data_dir()
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+
+        Ok(app_data_dir.join("plugins"))
+    }
+
+    /// 设置安装目录并注册其中已启用的插件，单个插件无效不影响其它插件
+    pub fn load(&self, dir: PathBuf) -> Result<()> {
+        std::fs::create_dir_all(&dir)?;
+        *self.dir.write().unwrap() = Some(dir);
+
+        for info in self.list()? {
+            if !info.enabled {
+                continue;
+            }
+            match WasmConverter::load_dir(&self.plugin_dir(&info.id)?) {
+                Ok(plugin) => self.registry.register_plugin(Arc::new(plugin))?,
+                Err(e) => tracing::warn!("Invalid WASM plugin {}: {}", info.id, e),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 从本地插件包安装，同 ID 插件视为升级
+    pub fn install(&self, package: &Path, settings: &AppSettings) -> Result<PluginInfo> {
+        let dir = self.dir()?;
+        let package = PluginPackage::read(package)?;
+        let signed = package.verify(settings)?;
+
+        // 先解包到临时目录完成校验，再替换到正式目录
+        let staging = dir.join(format!(".install-{}", uuid::Uuid::new_v4()));
+        let result = self.install_staged(&package, &staging, signed);
+        if staging.exists() {
+            let _ = std::fs::remove_dir_all(&staging);
+        }
+        result
+    }
+
+    fn install_staged(&self, package: &PluginPackage, staging: &Path, signed: bool) -> Result<PluginInfo> {
+        std::fs::create_dir_all(staging)?;
+        std::fs::write(staging.join(MANIFEST_FILE), &package.manifest)?;
+        std::fs::write(staging.join(MODULE_FILE), &package.module)?;
+        let plugin = WasmConverter::load_dir(staging)?;
+
+        let id = plugin.id();
+        if !is_valid_id(&id) {
+            return Err(AppError::ValidationError(format!(
+                "插件 ID \"{}\" 只能包含字母、数字、'.'、'_' 与 '-'",
+                id
+            )));
+        }
+        let target = self.plugin_dir(&id)?;
+        if !target.exists() && self.registry.get(&id).is_some() {
+            return Err(AppError::ValidationError(format!("插件 ID \"{}\" 与已有转换器冲突", id)));
+        }
+
+        let state = PluginState {
+            signed,
+            enabled: true,
+            installed_at: chrono::Utc::now().timestamp_millis(),
+        };
+        std::fs::write(staging.join(STATE_FILE), serde_json::to_string_pretty(&state)?)?;
+
+        // 先替换目录再更新注册表，任一步失败都恢复旧版本，注册表保持不变
+        let backup = target.with_file_name(format!(".replaced-{}", uuid::Uuid::new_v4()));
+        let upgrading = target.exists();
+        if upgrading {
+            std::fs::rename(&target, &backup)?;
+        }
+        let swapped = std::fs::rename(staging, &target)
+            .map_err(AppError::from)
+            .and_then(|_| self.registry.register_plugin(Arc::new(plugin)));
+        if let Err(e) = swapped {
+            if target.exists() {
+                let _ = std::fs::rename(&target, staging);
+            }
+            if upgrading {
+                std::fs::rename(&backup, &target)?;
+            }
+            return Err(e);
+        }
+        if upgrading {
+            if let Err(e) = std::fs::remove_dir_all(&backup) {
+                tracing::warn!("Failed to remove replaced plugin directory {:?}: {}", backup, e);
+            }
+        }
+
+        tracing::info!("Installed plugin {} (signed: {})", id, signed);
+        self.info(&id)
+    }
+
+    /// 列出已安装的插件
+    pub fn list(&self) -> Result<Vec<PluginInfo>> {
+        let dir = self.dir()?;
+        let mut plugins = Vec::new();
+
+        let mut entries: Vec<PathBuf> = std::fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_dir())
+            .filter(|path| path.file_name().and_then(|n| n.to_str()).is_some_and(is_valid_id))
+            .collect();
+        entries.sort();
+
+        for path in entries {
+            let id = path.file_name().unwrap().to_string_lossy().to_string();
+            match self.info(&id) {
+                Ok(info) => plugins.push(info),
+                Err(e) => tracing::warn!("Skipping plugin directory {:?}: {}", path, e),
+            }
+        }
+
+        Ok(plugins)
+    }
+
+    /// 启用或禁用插件，注册表即时更新
+    pub fn set_enabled(&self, id: &str, enabled: bool) -> Result<PluginInfo> {
+        let plugin_dir = self.installed_dir(id)?;
+        let mut state = read_state(&plugin_dir)?;
+
+        if enabled {
+            let plugin = WasmConverter::load_dir(&plugin_dir)?;
+            self.registry.register_plugin(Arc::new(plugin))?;
+        } else {
+            self.registry.unregister_plugin(id);
+        }
+
+        state.enabled = enabled;
+        std::fs::write(plugin_dir.join(STATE_FILE), serde_json::to_string_pretty(&state)?)?;
+
+        tracing::info!("Plugin {} {}", id, if enabled { "enabled" } else { "disabled" });
+        self.info(id)
+    }
+
+    /// 卸载插件
+    pub fn uninstall(&self, id: &str) -> Result<()> {
+        let plugin_dir = self.installed_dir(id)?;
+
+        self.registry.unregister_plugin(id);
+        std::fs::remove_dir_all(&plugin_dir)?;
+
+        tracing::info!("Uninstalled plugin {}", id);
+        Ok(())
+    }
+
+    fn info(&self, id: &str) -> Result<PluginInfo> {
+        let plugin_dir = self.plugin_dir(id)?;
+        let capability = ManifestLoader::load_file(&plugin_dir.join(MANIFEST_FILE))?;
+        let state = read_state(&plugin_dir)?;
+
+        Ok(PluginInfo {
+            id: capability.id,
+            name: capability.name,
+            version: capability.version,
+            vendor: capability.vendor,
+            license: capability.license,
+            signed: state.signed,
+            enabled: state.enabled,
+            installed_at: state.installed_at,
+        })
+    }
+
+    fn dir(&self) -> Result<PathBuf> {
+        self.dir
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| AppError::Internal("插件目录尚未初始化".to_string()))
+    }
+
+    fn plugin_dir(&self, id: &str) -> Result<PathBuf> {
+        Ok(self.dir()?.join(id))
+    }
+
+    fn installed_dir(&self, id: &str) -> Result<PathBuf> {
+        let plugin_dir = self.plugin_dir(id)?;
+        if !is_valid_id(id) || !plugin_dir.join(STATE_FILE).is_file() {
+            return Err(AppError::ValidationError(format!("插件未安装: {}", id)));
+        }
+        Ok(plugin_dir)
+    }
+}
+
+fn read_state(plugin_dir: &Path) -> Result<PluginState> {
+    let content = std::fs::read_to_string(plugin_dir.join(STATE_FILE))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// 插件 ID 同时用作目录名，只允许安全字符
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty()
+        && !id.starts_with('.')
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+/// 初始化插件管理器
+pub fn initialize(app_handle: &AppHandle) -> Result<()> {
+    let manager = PluginManager::global();
+    manager.load(PluginManager::plugins_dir(app_handle)?)?;
+
+    tracing::info!("Plugin manager initialized");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use std::io::Write;
+
+    const MANIFEST: &str = r#"
+id = "wasm-a-b"
+name = "WASM A to B"
+version = "1.0.0"
+license = "MIT"
+vendor = "test"
+quality = 0.9
+speed = 0.9
+risks = []
+parallelism = 1
+streaming = false
+
+[[inputs]]
+mime = "text/a"
+extensions = ["a"]
+
+[[outputs]]
+mime = "text/b"
+extensions = ["b"]
+
+[cost]
+cpu = 0.1
+memory = 0.1
+gpu = 0.0
+
+[requires]
+tools = []
+lang_packs = []
+fonts = []
+
+[preserve]
+styles = "true"
+links = "true"
+footnotes = "true"
+headers = "true"
+lists = "true"
+tables = "true"
+images = "true"
+formulas = "true"
+"#;
+
+    const MODULE: &str = r#"(module (func (export "_start")))"#;
+
+    /// 写入插件包，`key` 为空时不签名
+    fn write_package(dir: &Path, name: &str, key: Option<&SigningKey>) -> PathBuf {
+        let mut package = PluginPackage {
+            manifest: MANIFEST.as_bytes().to_vec(),
+            module: MODULE.as_bytes().to_vec(),
+            checksum: String::new(),
+            signature: None,
+        };
+        let digest = package.digest();
+        package.checksum = hex::encode(digest);
+        package.signature = key.map(|key| hex::encode(key.sign(&digest).to_bytes()));
+
+        let path = dir.join(name);
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        let mut files = vec![
+            (MANIFEST_FILE, package.manifest.clone()),
+            (MODULE_FILE, package.module.clone()),
+            (CHECKSUM_FILE, package.checksum.clone().into_bytes()),
+        ];
+        if let Some(signature) = &package.signature {
+            files.push((SIGNATURE_FILE, signature.clone().into_bytes()));
+        }
+        for (name, content) in files {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(&content).unwrap();
+        }
+        zip.finish().unwrap();
+        path
+    }
+
+    fn setup() -> (tempfile::TempDir, Arc<Registry>, PluginManager) {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = Arc::new(Registry::new());
+        let manager = PluginManager::new(registry.clone());
+        manager.load(dir.path().join("plugins")).unwrap();
+        (dir, registry, manager)
+    }
+
+    fn trusting(key: &SigningKey) -> AppSettings {
+        AppSettings {
+            trusted_plugin_keys: vec![hex::encode(key.verifying_key().to_bytes())],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_install_enable_disable_uninstall() {
+        let (dir, registry, manager) = setup();
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let package = write_package(dir.path(), "signed.f2fplugin", Some(&key));
+
+        let info = manager.install(&package, &trusting(&key)).unwrap();
+        assert!(info.signed && info.enabled);
+        assert!(registry.converter("wasm-a-b").is_some());
+        assert_eq!(registry.find_converters("a", "b"), vec!["wasm-a-b"]);
+
+        let info = manager.set_enabled("wasm-a-b", false).unwrap();
+        assert!(!info.enabled);
+        assert!(registry.get("wasm-a-b").is_none());
+        assert!(registry.find_converters("a", "b").is_empty());
+
+        // 重新加载时跳过已禁用的插件
+        let reloaded = PluginManager::new(Arc::new(Registry::new()));
+        reloaded.load(dir.path().join("plugins")).unwrap();
+        assert_eq!(reloaded.list().unwrap().len(), 1);
+        assert!(reloaded.registry.get("wasm-a-b").is_none());
+
+        manager.set_enabled("wasm-a-b", true).unwrap();
+        assert!(registry.converter("wasm-a-b").is_some());
+
+        manager.uninstall("wasm-a-b").unwrap();
+        assert!(registry.get("wasm-a-b").is_none());
+        assert!(manager.list().unwrap().is_empty());
+        assert!(manager.uninstall("wasm-a-b").is_err());
+    }
+
+    #[test]
+    fn test_failed_upgrade_keeps_previous_version() {
+        let (dir, registry, manager) = setup();
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let package = write_package(dir.path(), "signed.f2fplugin", Some(&key));
+        let installed_at = manager.install(&package, &trusting(&key)).unwrap().installed_at;
+        let capability = registry.get("wasm-a-b").unwrap();
+
+        // 禁用后同 ID 被清单占用，升级时注册失败
+        manager.set_enabled("wasm-a-b", false).unwrap();
+        registry.declare(capability).unwrap();
+        assert!(manager.install(&package, &trusting(&key)).is_err());
+
+        let plugins = std::fs::read_dir(dir.path().join("plugins")).unwrap().count();
+        assert_eq!(plugins, 1);
+        let info = manager.info("wasm-a-b").unwrap();
+        assert_eq!(info.installed_at, installed_at);
+        assert!(!info.enabled);
+    }
+
+    #[test]
+    fn test_install_rejects_untrusted_packages() {
+        let (dir, registry, manager) = setup();
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let other = SigningKey::from_bytes(&[9; 32]);
+
+        let unsigned = write_package(dir.path(), "unsigned.f2fplugin", None);
+        let error = manager.install(&unsigned, &trusting(&key)).unwrap_err();
+        assert!(error.to_string().contains("未签名"), "{}", error);
+
+        let signed_by_other = write_package(dir.path(), "other.f2fplugin", Some(&other));
+        let error = manager.install(&signed_by_other, &trusting(&key)).unwrap_err();
+        assert!(error.to_string().contains("受信任"), "{}", error);
+
+        // 篡改内容后校验和不再匹配
+        let mut package = PluginPackage::read(&write_package(dir.path(), "signed.f2fplugin", Some(&key))).unwrap();
+        package.module = br#"(module (func (export "_start") unreachable))"#.to_vec();
+        assert!(package.verify(&trusting(&key)).unwrap_err().to_string().contains("校验和"));
+
+        assert!(registry.get("wasm-a-b").is_none());
+        assert!(manager.list().unwrap().is_empty());
+
+        let settings = AppSettings {
+            allow_unsigned_plugins: true,
+            ..Default::default()
+        };
+        let info = manager.install(&unsigned, &settings).unwrap();
+        assert!(!info.signed);
+        assert!(registry.get("wasm-a-b").is_some());
+    }
+}
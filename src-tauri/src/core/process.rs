@@ -0,0 +1,288 @@
+use dashmap::{DashMap, DashSet};
+use once_cell::sync::Lazy;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// 全局进程表
+static PROCESS_TABLE: Lazy<Arc<ProcessTable>> = Lazy::new(|| Arc::new(ProcessTable::new()));
+
+thread_local! {
+    /// 当前线程正在执行的任务及其取消令牌，由流水线在调用转换器前设置
+    static CURRENT_JOB: RefCell<Option<(String, CancellationToken)>> = const { RefCell::new(None) };
+}
+
+/// 取消令牌 - 流水线取消任务时触发，执行中的步骤与外部进程据此提前结束
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<TokenInner>,
+}
+
+#[derive(Default)]
+struct TokenInner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 等待令牌被取消
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.inner.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// 进程表 - 按任务登记正在运行的外部工具进程，用于暂停、恢复与终止
+///
+/// 外部工具在独立的进程组中启动，信号发送给整个进程组，工具派生的子进程一并生效。
+pub struct ProcessTable {
+    processes: DashMap<String, Vec<u32>>,
+    paused: DashSet<String>,
+}
+
+impl ProcessTable {
+    pub fn new() -> Self {
+        Self {
+            processes: DashMap::new(),
+            paused: DashSet::new(),
+        }
+    }
+
+    /// 获取全局进程表
+    pub fn global() -> Arc<ProcessTable> {
+        PROCESS_TABLE.clone()
+    }
+
+    /// 在当前线程上以指定任务的身份执行 `f`，期间启动的外部进程登记到该任务
+    pub fn scope<R>(job_id: &str, token: &CancellationToken, f: impl FnOnce() -> R) -> R {
+        let current = (job_id.to_string(), token.clone());
+        let previous = CURRENT_JOB.with(|job| job.replace(Some(current)));
+        let result = f();
+        CURRENT_JOB.with(|job| *job.borrow_mut() = previous);
+        result
+    }
+
+    /// 当前线程所属的任务
+    pub fn current_job() -> Option<String> {
+        CURRENT_JOB.with(|job| job.borrow().as_ref().map(|(job_id, _)| job_id.clone()))
+    }
+
+    /// 当前线程所属任务的取消令牌
+    pub fn current_token() -> Option<CancellationToken> {
+        CURRENT_JOB.with(|job| job.borrow().as_ref().map(|(_, token)| token.clone()))
+    }
+
+    /// 登记当前任务新启动的进程，返回的守卫在进程结束后注销登记
+    ///
+    /// 任务已取消时立即终止该进程，已暂停时立即挂起。当前线程不属于任何任务时不登记。
+    pub fn register(self: &Arc<Self>, pid: u32) -> Option<ProcessGuard> {
+        let (job_id, token) = CURRENT_JOB.with(|job| job.borrow().clone())?;
+
+        self.processes.entry(job_id.clone()).or_default().push(pid);
+        if token.is_cancelled() {
+            send_signal(pid, Signal::Kill);
+        } else if self.paused.contains(&job_id) {
+            send_signal(pid, Signal::Stop);
+        }
+
+        Some(ProcessGuard {
+            table: self.clone(),
+            job_id,
+            pid,
+        })
+    }
+
+    fn unregister(&self, job_id: &str, pid: u32) {
+        if let Some(mut pids) = self.processes.get_mut(job_id) {
+            pids.retain(|p| *p != pid);
+        }
+        self.processes.remove_if(job_id, |_, pids| pids.is_empty());
+    }
+
+    /// 任务正在运行的进程
+    pub fn pids(&self, job_id: &str) -> Vec<u32> {
+        self.processes
+            .get(job_id)
+            .map(|pids| pids.clone())
+            .unwrap_or_default()
+    }
+
+    /// 挂起任务的所有进程 (SIGSTOP)，之后启动的进程也会被挂起
+    ///
+    /// 返回收到信号的进程数，不支持的平台上为 0。
+    pub fn suspend(&self, job_id: &str) -> usize {
+        self.paused.insert(job_id.to_string());
+        self.signal_all(job_id, Signal::Stop)
+    }
+
+    /// 任务是否处于暂停状态
+    pub fn is_paused(&self, job_id: &str) -> bool {
+        self.paused.contains(job_id)
+    }
+
+    /// 恢复任务被挂起的进程 (SIGCONT)
+    pub fn resume(&self, job_id: &str) -> usize {
+        self.paused.remove(job_id);
+        self.signal_all(job_id, Signal::Continue)
+    }
+
+    /// 终止任务的所有进程组：先发送 SIGTERM，`grace` 后仍未退出的进程组发送 SIGKILL
+    ///
+    /// 已挂起的进程同时收到 SIGCONT 以便处理 SIGTERM。返回收到 SIGTERM 的进程数。
+    pub fn terminate(self: &Arc<Self>, job_id: &str, grace: Duration) -> usize {
+        self.paused.remove(job_id);
+        let terminated = self.signal_all(job_id, Signal::Terminate);
+        self.signal_all(job_id, Signal::Continue);
+
+        if terminated > 0 {
+            let table = self.clone();
+            let job_id = job_id.to_string();
+            std::thread::spawn(move || {
+                std::thread::sleep(grace);
+                let killed = table.signal_all(&job_id, Signal::Kill);
+                if killed > 0 {
+                    tracing::warn!("Killed {} process(es) of job {} after grace period", killed, job_id);
+                }
+            });
+        }
+
+        terminated
+    }
+
+    fn signal_all(&self, job_id: &str, signal: Signal) -> usize {
+        self.pids(job_id)
+            .into_iter()
+            .filter(|pid| send_signal(*pid, signal))
+            .count()
+    }
+}
+
+impl Default for ProcessTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 进程登记守卫，离开作用域时从进程表中移除
+pub struct ProcessGuard {
+    table: Arc<ProcessTable>,
+    job_id: String,
+    pid: u32,
+}
+
+impl Drop for ProcessGuard {
+    fn drop(&mut self) {
+        self.table.unregister(&self.job_id, self.pid);
+    }
+}
+
+/// 立即结束进程组 (SIGKILL)
+pub fn kill_process_group(pid: u32) -> bool {
+    send_signal(pid, Signal::Kill)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Signal {
+    Stop,
+    Continue,
+    Terminate,
+    Kill,
+}
+
+/// 向进程组发送信号，进程组 ID 与组长进程 ID 相同
+#[cfg(unix)]
+fn send_signal(pid: u32, signal: Signal) -> bool {
+    let signal = match signal {
+        Signal::Stop => libc::SIGSTOP,
+        Signal::Continue => libc::SIGCONT,
+        Signal::Terminate => libc::SIGTERM,
+        Signal::Kill => libc::SIGKILL,
+    };
+    unsafe { libc::kill(-(pid as libc::pid_t), signal) == 0 }
+}
+
+#[cfg(not(unix))]
+fn send_signal(_pid: u32, _signal: Signal) -> bool {
+    false
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::core::adapter::Adapter;
+    use crate::core::invocation::ToolInvocation;
+    use crate::error::AppError;
+    use std::path::Path;
+
+    #[test]
+    fn test_terminate_escalates_to_kill() {
+        let token = CancellationToken::new();
+        let worker = std::thread::spawn({
+            let token = token.clone();
+            move || {
+                // 忽略 SIGTERM 的工具只能在宽限期后被 SIGKILL 结束
+                ProcessTable::scope("terminate-test", &token, || {
+                    Adapter::execute_sandboxed(
+                        &ToolInvocation::new("sh", ["-c", "trap '' TERM; sleep 30"]),
+                        Path::new("/dev/null"),
+                        Path::new("/dev/null"),
+                        None,
+                        None,
+                    )
+                })
+            }
+        });
+
+        let table = ProcessTable::global();
+        let mut pids = vec![];
+        for _ in 0..200 {
+            pids = table.pids("terminate-test");
+            if !pids.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(!pids.is_empty());
+        // 等待 shell 设置好信号处理
+        std::thread::sleep(Duration::from_millis(100));
+
+        let started = std::time::Instant::now();
+        token.cancel();
+        assert_eq!(table.terminate("terminate-test", Duration::from_millis(300)), 1);
+        let result = worker.join().unwrap();
+        assert!(matches!(result, Err(AppError::Cancelled)), "{:?}", result);
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(table.pids("terminate-test").is_empty());
+
+        // 已取消的令牌下不再启动新进程
+        let result = ProcessTable::scope("terminate-test", &token, || {
+            let invocation = ToolInvocation::new("true", Vec::<String>::new());
+            Adapter::execute_sandboxed(&invocation, Path::new("/dev/null"), Path::new("/dev/null"), None, None)
+        });
+        assert!(matches!(result, Err(AppError::Cancelled)));
+    }
+}
@@ -0,0 +1,206 @@
+use std::cell::RefCell;
+use std::path::Path;
+use std::sync::Arc;
+
+/// 工具报告的步骤内进度
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolProgress {
+    /// 步骤完成比例 (0-1)，工具只报告阶段时为 None
+    pub fraction: Option<f32>,
+    pub stage: Option<String>,
+}
+
+/// 进度接收端，由流水线在执行步骤时设置
+pub type ProgressSink = Arc<dyn Fn(ToolProgress) + Send + Sync>;
+
+thread_local! {
+    static CURRENT_SINK: RefCell<Option<ProgressSink>> = const { RefCell::new(None) };
+}
+
+/// 在当前线程上以 `sink` 作为进度接收端执行 `f`
+pub fn scope<R>(sink: ProgressSink, f: impl FnOnce() -> R) -> R {
+    let previous = CURRENT_SINK.with(|current| current.replace(Some(sink)));
+    let result = f();
+    CURRENT_SINK.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+/// 当前线程的进度接收端
+pub fn current_sink() -> Option<ProgressSink> {
+    CURRENT_SINK.with(|current| current.borrow().clone())
+}
+
+/// 工具输出流
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// 逐行解析工具输出中的进度信息
+pub trait ProgressParser: Send {
+    fn feed(&mut self, stream: OutputStream, line: &str) -> Option<ToolProgress>;
+}
+
+/// 按工具名选择进度解析器，未知工具返回 None
+pub fn parser_for(program: &str) -> Option<Box<dyn ProgressParser>> {
+    let name = Path::new(program).file_stem()?.to_str()?;
+    match name {
+        "ffmpeg" => Some(Box::new(FfmpegProgress::default())),
+        "pandoc" => Some(Box::new(PandocProgress)),
+        "magick" | "convert" => Some(Box::new(MagickProgress)),
+        _ => None,
+    }
+}
+
+/// 按输出文件增长估算进度，用于没有进度输出的工具
+///
+/// 以输入文件大小作为预期输出大小，最多报告 95%，由步骤结束时的进度补齐。
+pub fn output_growth(input_size: u64, output_size: u64) -> Option<ToolProgress> {
+    if input_size == 0 || output_size == 0 {
+        return None;
+    }
+    Some(ToolProgress {
+        fraction: Some((output_size as f32 / input_size as f32).min(0.95)),
+        stage: Some(format!("已写入 {} KB", output_size / 1024)),
+    })
+}
+
+/// ffmpeg `-progress pipe:1`：stdout 输出 `key=value` 块，总时长取自 stderr 的 `Duration:` 行
+#[derive(Default)]
+struct FfmpegProgress {
+    duration_us: Option<u64>,
+    out_time_us: Option<u64>,
+}
+
+impl ProgressParser for FfmpegProgress {
+    fn feed(&mut self, stream: OutputStream, line: &str) -> Option<ToolProgress> {
+        let line = line.trim();
+        if stream == OutputStream::Stderr {
+            if let Some(rest) = line.strip_prefix("Duration:") {
+                let timestamp = rest.split(',').next().unwrap_or_default().trim();
+                self.duration_us = self.duration_us.or(parse_timestamp_us(timestamp));
+            }
+            return None;
+        }
+
+        let (key, value) = line.split_once('=')?;
+        match key {
+            // out_time_ms 实际单位同样是微秒
+            "out_time_us" | "out_time_ms" => self.out_time_us = value.parse().ok(),
+            "progress" if value == "end" => {
+                return Some(ToolProgress {
+                    fraction: Some(1.0),
+                    stage: None,
+                });
+            }
+            "progress" => {
+                let (done, total) = (self.out_time_us?, self.duration_us.filter(|d| *d > 0)?);
+                return Some(ToolProgress {
+                    fraction: Some((done as f32 / total as f32).clamp(0.0, 1.0)),
+                    stage: None,
+                });
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+/// 解析 `HH:MM:SS.ff` 格式的时间
+fn parse_timestamp_us(timestamp: &str) -> Option<u64> {
+    let mut parts = timestamp.split(':');
+    let hours: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    Some(((hours * 3600.0 + minutes * 60.0 + seconds) * 1_000_000.0) as u64)
+}
+
+/// pandoc `--verbose`：stderr 中以 `[INFO]`、`[makePDF]` 等开头的行作为阶段
+struct PandocProgress;
+
+impl ProgressParser for PandocProgress {
+    fn feed(&mut self, stream: OutputStream, line: &str) -> Option<ToolProgress> {
+        let line = line.trim();
+        if stream != OutputStream::Stderr || !line.starts_with('[') {
+            return None;
+        }
+        Some(ToolProgress {
+            fraction: None,
+            stage: Some(line.chars().take(80).collect()),
+        })
+    }
+}
+
+/// ImageMagick `-monitor`：stderr 输出 `load image[a.png]: 99 of 100, 100% complete`
+struct MagickProgress;
+
+impl ProgressParser for MagickProgress {
+    fn feed(&mut self, stream: OutputStream, line: &str) -> Option<ToolProgress> {
+        if stream != OutputStream::Stderr {
+            return None;
+        }
+        let (head, _) = line.trim().split_once("% complete")?;
+        let percent: f32 = head.rsplit([' ', ',']).next()?.parse().ok()?;
+        let stage = head.split(['[', ':']).next().unwrap_or_default().trim();
+
+        Some(ToolProgress {
+            fraction: Some((percent / 100.0).clamp(0.0, 1.0)),
+            stage: (!stage.is_empty()).then(|| stage.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_all(parser: &mut dyn ProgressParser, lines: &[(OutputStream, &str)]) -> Vec<ToolProgress> {
+        lines
+            .iter()
+            .filter_map(|(stream, line)| parser.feed(*stream, line))
+            .collect()
+    }
+
+    #[test]
+    fn test_ffmpeg_progress() {
+        let mut parser = parser_for("/usr/bin/ffmpeg").unwrap();
+        let updates = feed_all(
+            parser.as_mut(),
+            &[
+                (OutputStream::Stderr, "  Duration: 00:00:10.00, start: 0.000000, bitrate: 1205 kb/s"),
+                (OutputStream::Stdout, "frame=120"),
+                (OutputStream::Stdout, "out_time_us=2500000"),
+                (OutputStream::Stdout, "progress=continue"),
+                (OutputStream::Stdout, "out_time_us=10000000"),
+                (OutputStream::Stdout, "progress=end"),
+            ],
+        );
+        let fractions: Vec<_> = updates.iter().map(|u| u.fraction.unwrap()).collect();
+        assert_eq!(fractions, vec![0.25, 1.0]);
+    }
+
+    #[test]
+    fn test_magick_and_pandoc_progress() {
+        let mut magick = parser_for("magick").unwrap();
+        let update = magick
+            .feed(OutputStream::Stderr, "resize image[photo.png]: 12 of 13, 092% complete")
+            .unwrap();
+        assert_eq!(update.fraction, Some(0.92));
+        assert_eq!(update.stage.as_deref(), Some("resize image"));
+        assert!(magick.feed(OutputStream::Stdout, "100% complete").is_none());
+
+        let mut pandoc = parser_for("pandoc").unwrap();
+        let update = pandoc
+            .feed(OutputStream::Stderr, "[makePDF] Running pdflatex on input.tex")
+            .unwrap();
+        assert_eq!(update.fraction, None);
+        assert_eq!(update.stage.as_deref(), Some("[makePDF] Running pdflatex on input.tex"));
+        assert!(pandoc.feed(OutputStream::Stderr, "warning without tag").is_none());
+
+        assert!(parser_for("soffice").is_none());
+        assert_eq!(output_growth(1000, 500).unwrap().fraction, Some(0.5));
+        assert_eq!(output_growth(1000, 5000).unwrap().fraction, Some(0.95));
+        assert!(output_growth(1000, 0).is_none());
+    }
+}
@@ -1,5 +1,28 @@
 use crate::error::Result;
 use crate::types::QualityMetrics;
+use std::path::Path;
+
+/// 质量检查 - 流水线在发布产物前评估最终输出，测试中可替换
+pub trait QualityCheck: Send + Sync {
+    fn assess(&self, source: &Path, result: &Path, target_format: &str) -> Result<QualityMetrics>;
+
+    fn thresholds(&self) -> QualityThresholds {
+        QualityThresholds::default()
+    }
+}
+
+/// 使用 `QualityAssessor` 的默认质量检查
+pub struct DefaultQualityCheck;
+
+impl QualityCheck for DefaultQualityCheck {
+    fn assess(&self, source: &Path, result: &Path, target_format: &str) -> Result<QualityMetrics> {
+        QualityAssessor::assess(
+            &source.to_string_lossy(),
+            &result.to_string_lossy(),
+            target_format,
+        )
+    }
+}
 
 /// 质量评估器
 pub struct QualityAssessor;
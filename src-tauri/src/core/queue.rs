@@ -0,0 +1,122 @@
+use crate::types::Job;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap};
+
+/// 排队顺序：优先级高的在前，同一优先级按 `queue_position` 从小到大
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct QueueKey {
+    priority: Reverse<i32>,
+    position: i64,
+    job_id: String,
+}
+
+impl QueueKey {
+    fn of(job: &Job) -> Self {
+        Self {
+            priority: Reverse(job.priority),
+            position: job.queue_position,
+            job_id: job.id.clone(),
+        }
+    }
+}
+
+/// 任务优先队列 - 只包含处于 `Queued` 状态的任务
+#[derive(Debug, Default)]
+pub struct JobQueue {
+    entries: BTreeSet<QueueKey>,
+    keys: HashMap<String, QueueKey>,
+}
+
+impl JobQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 加入或按任务当前的优先级与顺序重新排队
+    pub fn push(&mut self, job: &Job) {
+        self.remove(&job.id);
+        let key = QueueKey::of(job);
+        self.keys.insert(job.id.clone(), key.clone());
+        self.entries.insert(key);
+    }
+
+    pub fn remove(&mut self, job_id: &str) -> bool {
+        match self.keys.remove(job_id) {
+            Some(key) => self.entries.remove(&key),
+            None => false,
+        }
+    }
+
+    /// 取出队首任务
+    pub fn pop(&mut self) -> Option<String> {
+        let key = self.entries.pop_first()?;
+        self.keys.remove(&key.job_id);
+        Some(key.job_id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 按执行顺序排列的任务 ID
+    pub fn job_ids(&self) -> Vec<String> {
+        self.entries.iter().map(|key| key.job_id.clone()).collect()
+    }
+
+    /// 使任务排在队首所需的优先级与顺序
+    pub fn front_slot(&self, job: &Job) -> (i32, i64) {
+        match self.entries.iter().find(|key| key.job_id != job.id) {
+            Some(first) => (first.priority.0.max(job.priority), first.position - 1),
+            None => (job.priority, job.queue_position),
+        }
+    }
+
+    /// 使任务排在队尾所需的优先级与顺序
+    pub fn back_slot(&self, job: &Job) -> (i32, i64) {
+        match self.entries.iter().rev().find(|key| key.job_id != job.id) {
+            Some(last) => (last.priority.0.min(job.priority), last.position + 1),
+            None => (job.priority, job.queue_position),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(priority: i32, position: i64) -> Job {
+        let mut job = Job::new(vec![], "html".to_string(), None, serde_json::Value::Null);
+        job.priority = priority;
+        job.queue_position = position;
+        job
+    }
+
+    #[test]
+    fn test_queue_order_and_slots() {
+        let mut queue = JobQueue::new();
+        let batch: Vec<Job> = (0..3).map(|i| job(0, i)).collect();
+        for job in &batch {
+            queue.push(job);
+        }
+        let mut urgent = job(10, 100);
+        queue.push(&urgent);
+        assert_eq!(queue.job_ids()[0], urgent.id);
+        assert_eq!(queue.job_ids()[1], batch[0].id);
+
+        // 移到队首后排在所有高优先级任务之前
+        let mut last = batch[2].clone();
+        (last.priority, last.queue_position) = queue.front_slot(&last);
+        queue.push(&last);
+        assert_eq!(queue.job_ids().len(), 4);
+        assert_eq!(queue.job_ids()[0], last.id);
+
+        // 推迟后排在所有任务之后
+        (urgent.priority, urgent.queue_position) = queue.back_slot(&urgent);
+        queue.push(&urgent);
+        assert_eq!(queue.job_ids().last(), Some(&urgent.id));
+
+        assert_eq!(queue.pop(), Some(last.id.clone()));
+        assert!(queue.remove(&batch[0].id));
+        assert_eq!(queue.job_ids(), vec![batch[1].id.clone(), urgent.id.clone()]);
+    }
+}
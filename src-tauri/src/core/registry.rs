@@ -1,9 +1,18 @@
-use crate::error::Result;
-use crate::types::{CapabilityRecord, ConversionTarget, ToolHealth, HealthReport};
+use crate::core::adapter::{Adapter, KNOWN_TOOLS};
+use crate::core::converter::{CommandConverter, ConverterPlugin};
+use crate::core::manifest::ManifestLoader;
+use crate::core::sandbox;
+use crate::core::version::{ToolVersion, VersionRange};
+use crate::error::{AppError, Result};
+use crate::types::{
+    CapabilityRecord, FormatSpec, HealthReport, MissingReason, MissingRequirement, RequirementKind,
+    Requirements, ToolHealth,
+};
 use dashmap::DashMap;
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsStr;
+use std::sync::{Arc, RwLock};
 use tauri::AppHandle;
 
 /// 全局能力注册表实例
@@ -11,418 +20,801 @@ static REGISTRY: Lazy<Arc<Registry>> = Lazy::new(|| Arc::new(Registry::new()));
 
 /// 能力注册表 - 管理所有转换器的能力声明
 pub struct Registry {
+    /// 声明的能力，刷新时依赖工具可用者才会注册
+    declared: DashMap<String, CapabilityRecord>,
+    /// 进程内注册的转换器插件，刷新后依然保留
+    plugins: DashMap<String, Arc<dyn ConverterPlugin>>,
+    /// 当前生效的能力目录，刷新时整体替换
+    catalog: RwLock<Catalog>,
+    /// 最近一次健康检查结果
+    health: RwLock<Option<HealthReport>>,
+}
+
+/// 已注册的能力及其转换器与格式图
+///
+/// 三者必须保持一致，因此放在同一把锁下读写；刷新时在锁外构建新目录再替换，
+/// 规划与执行不会看到只注册了一部分的中间状态。
+#[derive(Default)]
+struct Catalog {
     /// 所有注册的能力
-    capabilities: DashMap<String, CapabilityRecord>,
+    capabilities: HashMap<String, CapabilityRecord>,
+    /// 已注册能力对应的可执行转换器
+    converters: HashMap<String, Arc<dyn ConverterPlugin>>,
     /// 格式到转换器的映射 (from_mime -> to_mime -> converter_ids)
-    format_graph: DashMap<String, DashMap<String, Vec<String>>>,
+    format_graph: HashMap<String, HashMap<String, Vec<String>>>,
+    /// 依赖工具缺失或版本不符而未注册的能力，只参与规划以报告缺失的依赖
+    unavailable: BTreeMap<String, CapabilityRecord>,
 }
 
-impl Registry {
-    pub fn new() -> Self {
-        Self {
-            capabilities: DashMap::new(),
-            format_graph: DashMap::new(),
-        }
-    }
-
-    /// 获取全局注册表实例
-    pub fn global() -> Arc<Registry> {
-        REGISTRY.clone()
-    }
-
-    /// 注册一个转换能力
-    pub fn register(&self, capability: CapabilityRecord) -> Result<()> {
+impl Catalog {
+    /// 加入一个能力，同 ID 的旧能力先被移除
+    ///
+    /// 带命令模板的能力会同时注册为外部工具转换器。
+    fn insert(&mut self, capability: CapabilityRecord) {
         let id = capability.id.clone();
+        self.unavailable.remove(&id);
+        if self.capabilities.contains_key(&id) {
+            self.remove_edges(&id);
+        }
 
         // 更新格式图
         for input in &capability.inputs {
             for input_mime in std::iter::once(&input.mime).chain(input.extensions.iter()) {
                 for output in &capability.outputs {
-                    for output_mime in std::iter::once(&output.mime).chain(output.extensions.iter()) {
+                    for output_mime in std::iter::once(&output.mime).chain(output.extensions.iter())
+                    {
                         self.format_graph
                             .entry(input_mime.clone())
-                            .or_insert_with(DashMap::new)
+                            .or_default()
                             .entry(output_mime.clone())
-                            .or_insert_with(Vec::new)
+                            .or_default()
                             .push(id.clone());
                     }
                 }
             }
         }
 
+        if let Some(converter) = CommandConverter::new(capability.clone()) {
+            self.converters.insert(id.clone(), Arc::new(converter));
+        }
+
         self.capabilities.insert(id, capability);
+    }
+
+    /// 加入进程内插件，插件本身即为转换器
+    fn insert_plugin(&mut self, plugin: Arc<dyn ConverterPlugin>) {
+        self.insert(plugin.capability());
+        self.converters.insert(plugin.id(), plugin);
+    }
+
+    fn remove(&mut self, id: &str) {
+        self.unavailable.remove(id);
+        self.converters.remove(id);
+        if self.capabilities.remove(id).is_some() {
+            self.remove_edges(id);
+        }
+    }
+
+    /// 从格式图中移除转换器的所有边
+    fn remove_edges(&mut self, id: &str) {
+        for targets in self.format_graph.values_mut() {
+            for ids in targets.values_mut() {
+                ids.retain(|converter_id| converter_id != id);
+            }
+            targets.retain(|_, ids| !ids.is_empty());
+        }
+        self.format_graph.retain(|_, targets| !targets.is_empty());
+    }
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self {
+            declared: DashMap::new(),
+            plugins: DashMap::new(),
+            catalog: RwLock::new(Catalog::default()),
+            health: RwLock::new(None),
+        }
+    }
+
+    /// 获取全局注册表实例
+    pub fn global() -> Arc<Registry> {
+        REGISTRY.clone()
+    }
+
+    /// 声明一个转换能力，在 `refresh` 时依赖工具齐全才会注册
+    ///
+    /// ID 与已注册的进程内插件相同时拒绝。
+    pub fn declare(&self, capability: CapabilityRecord) -> Result<()> {
+        if self.plugins.contains_key(&capability.id) {
+            return Err(AppError::ValidationError(format!(
+                "转换器 ID {} 已被插件占用",
+                capability.id
+            )));
+        }
+        self.declared.insert(capability.id.clone(), capability);
+        Ok(())
+    }
+
+    /// 注册一个进程内转换器插件，其能力立即可用于规划与执行
+    ///
+    /// 重复注册同一插件会替换之前的版本；ID 与转换器清单声明的能力相同时拒绝。
+    pub fn register_plugin(&self, plugin: Arc<dyn ConverterPlugin>) -> Result<()> {
+        let id = plugin.id();
+        if self.declared.contains_key(&id) {
+            return Err(AppError::ValidationError(format!(
+                "插件 ID {} 与转换器清单冲突",
+                id
+            )));
+        }
+        self.plugins.insert(id, plugin.clone());
+        self.catalog.write().unwrap().insert_plugin(plugin);
+        Ok(())
+    }
+
+    /// 移除进程内插件及其能力，返回插件是否存在
+    pub fn unregister_plugin(&self, id: &str) -> bool {
+        let existed = self.plugins.remove(id).is_some();
+        self.catalog.write().unwrap().remove(id);
+        existed
+    }
+
+    /// 注册一个转换能力
+    ///
+    /// 带命令模板的能力会同时注册为外部工具转换器。重复注册同一 ID 时替换之前的能力。
+    pub fn register(&self, capability: CapabilityRecord) -> Result<()> {
+        self.catalog.write().unwrap().insert(capability);
         Ok(())
     }
 
+    /// 获取能力对应的可执行转换器
+    pub fn converter(&self, id: &str) -> Option<Arc<dyn ConverterPlugin>> {
+        self.catalog.read().unwrap().converters.get(id).cloned()
+    }
+
     /// 获取能力记录
     pub fn get(&self, id: &str) -> Option<CapabilityRecord> {
-        self.capabilities.get(id).map(|r| r.clone())
+        self.catalog.read().unwrap().capabilities.get(id).cloned()
+    }
+
+    /// 获取能力记录，包括依赖不满足、尚不可执行的能力
+    pub fn lookup(&self, id: &str) -> Option<CapabilityRecord> {
+        let catalog = self.catalog.read().unwrap();
+        catalog
+            .capabilities
+            .get(id)
+            .or_else(|| catalog.unavailable.get(id))
+            .cloned()
     }
 
     /// 获取所有能力
     pub fn list_all(&self) -> Vec<CapabilityRecord> {
-        self.capabilities
-            .iter()
-            .map(|entry| entry.value().clone())
+        self.catalog
+            .read()
+            .unwrap()
+            .capabilities
+            .values()
+            .cloned()
             .collect()
     }
 
     /// 查找从源格式到目标格式的转换器
     pub fn find_converters(&self, from: &str, to: &str) -> Vec<String> {
-        self.format_graph
+        self.catalog
+            .read()
+            .unwrap()
+            .format_graph
             .get(from)
-            .and_then(|targets| targets.get(to).map(|ids| ids.clone()))
+            .and_then(|targets| targets.get(to).cloned())
             .unwrap_or_default()
     }
 
-    /// 获取给定源格式的所有可达目标格式
-    pub fn get_targets(&self, source_mime: &str) -> Vec<ConversionTarget> {
-        // 简化实现 - 实际应该进行图搜索
-        let mut targets = Vec::new();
-
-        if let Some(target_map) = self.format_graph.get(source_mime) {
-            for entry in target_map.iter() {
-                let to_mime = entry.key();
-                let converter_ids = entry.value();
-
-                if !converter_ids.is_empty() {
-                    targets.push(ConversionTarget {
-                        format: to_mime.clone(),
-                        mime_type: to_mime.clone(),
-                        paths: vec![], // TODO: 计算实际路径
-                        recommended: false,
-                        available: true,
-                        missing_requirements: vec![],
-                    });
+    /// 获取从某格式出发的所有转换边 (converter_id, 输出格式)
+    ///
+    /// 格式图同时以 MIME 和扩展名为键，这里按能力的输出规格去重，
+    /// 避免同一转换器因别名被重复展开。依赖不满足的能力也会给出，
+    /// 由规划器标记缺失的依赖。
+    pub fn edges_from(&self, from: &str) -> Vec<(String, FormatSpec)> {
+        let catalog = self.catalog.read().unwrap();
+        let mut converter_ids: Vec<String> = Vec::new();
+
+        if let Some(targets) = catalog.format_graph.get(from) {
+            for ids in targets.values() {
+                for id in ids {
+                    if !converter_ids.contains(id) {
+                        converter_ids.push(id.clone());
+                    }
                 }
             }
         }
 
-        targets
+        let mut edges = Vec::new();
+        for id in converter_ids {
+            if let Some(capability) = catalog.capabilities.get(&id) {
+                for output in &capability.outputs {
+                    edges.push((id.clone(), output.clone()));
+                }
+            }
+        }
+
+        for (id, capability) in &catalog.unavailable {
+            let accepts = capability
+                .inputs
+                .iter()
+                .any(|input| input.mime == from || input.extensions.iter().any(|ext| ext == from));
+            if accepts {
+                for output in &capability.outputs {
+                    edges.push((id.clone(), output.clone()));
+                }
+            }
+        }
+
+        edges
     }
 
     /// 刷新注册表 - 重新扫描所有工具
+    ///
+    /// 在 PATH 中探测工具后重建能力目录，只注册依赖工具全部可用的
+    /// 已声明能力与进程内插件，建好后一次性替换旧目录。依赖工具缺失或版本
+    /// 不在要求范围内的能力不注册，但保留在目录中供规划时报告原因。
     pub fn refresh(&self) -> Result<()> {
-        self.capabilities.clear();
-        self.format_graph.clear();
+        let search_path = std::env::var_os("PATH");
+        self.refresh_in(search_path.as_deref())
+    }
+
+    fn refresh_in(&self, search_path: Option<&OsStr>) -> Result<()> {
+        let report = self.check_health_in(search_path);
+        let mut catalog = Catalog::default();
+
+        let unmet_tools = |capability: &CapabilityRecord| {
+            let requires = &capability.requires;
+            let unmet: Vec<MissingRequirement> = requires
+                .tools
+                .iter()
+                .filter_map(|tool| unmet_tool(requires, tool, report.tools.get(tool)))
+                .collect();
+            if !unmet.is_empty() {
+                let reasons: Vec<String> =
+                    unmet.iter().map(MissingRequirement::to_string).collect();
+                tracing::info!(
+                    "Skipping capability {}: unmet tool requirements {:?}",
+                    capability.id,
+                    reasons
+                );
+            }
+            unmet
+        };
+
+        let mut declared: Vec<CapabilityRecord> = self
+            .declared
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        declared.sort_by(|a, b| a.id.cmp(&b.id));
+
+        for mut capability in declared {
+            if !unmet_tools(&capability).is_empty() {
+                catalog
+                    .unavailable
+                    .insert(capability.id.clone(), capability);
+                continue;
+            }
+
+            // 记录实际探测到的主工具路径
+            if capability.binary_path.is_none() {
+                capability.binary_path = capability
+                    .primary_tool()
+                    .and_then(|tool| report.tools.get(tool))
+                    .and_then(|health| health.path.clone());
+            }
 
-        // TODO: 扫描和注册所有可用工具
-        tracing::info!("Registry refreshed");
+            catalog.insert(capability);
+        }
+
+        // 插件可能在探测期间增删，持锁读取后再整体替换
+        let mut current = self.catalog.write().unwrap();
+        for entry in self.plugins.iter() {
+            let capability = entry.value().capability();
+            if unmet_tools(&capability).is_empty() {
+                catalog.insert_plugin(entry.value().clone());
+            } else {
+                catalog
+                    .unavailable
+                    .insert(capability.id.clone(), capability);
+            }
+        }
+        *current = catalog;
+
+        tracing::info!(
+            "Registry refreshed with {} capabilities",
+            current.capabilities.len()
+        );
 
         Ok(())
     }
 
     /// 检查工具健康状态
+    ///
+    /// 探测所有已知工具及能力依赖的工具（能力声明了 `binary_path` 时主工具以其为准），
+    /// 并列出已安装的 OCR 语言包与系统字体。结果会被缓存，供规划时判断依赖是否满足。
     pub fn check_health(&self) -> HealthReport {
-        let mut tools = HashMap::new();
+        let search_path = std::env::var_os("PATH");
+        self.check_health_in(search_path.as_deref())
+    }
+
+    fn check_health_in(&self, search_path: Option<&OsStr>) -> HealthReport {
         let timestamp = chrono::Utc::now().timestamp_millis();
 
-        // TODO: 实际检查各个工具的可用性
-        // 这里只是示例
-        tools.insert(
-            "pandoc".to_string(),
-            ToolHealth {
-                name: "Pandoc".to_string(),
-                available: false,
-                version: None,
-                path: None,
-                error: Some("未安装".to_string()),
-            },
-        );
+        let mut overrides: HashMap<String, String> = HashMap::new();
+        let mut required: Vec<String> = KNOWN_TOOLS.iter().map(|t| t.id.to_string()).collect();
+        let registered = self.list_all();
+        let declared: Vec<CapabilityRecord> = self
+            .declared
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        for capability in declared.iter().chain(registered.iter()) {
+            for tool in &capability.requires.tools {
+                if !required.contains(tool) {
+                    required.push(tool.clone());
+                }
+            }
+
+            if let (Some(tool), Some(path)) = (capability.primary_tool(), &capability.binary_path) {
+                overrides
+                    .entry(tool.clone())
+                    .or_insert_with(|| path.clone());
+            }
+        }
+
+        let tools = required
+            .into_iter()
+            .map(|tool| {
+                let binary_path = overrides.get(&tool).map(String::as_str);
+                let health = Adapter::probe_tool(&tool, binary_path, search_path);
+                (tool, health)
+            })
+            .collect();
+
+        let report = HealthReport {
+            tools,
+            lang_packs: Adapter::list_ocr_languages(),
+            fonts: Adapter::list_fonts(),
+            isolation: sandbox::isolation_level(),
+            timestamp,
+        };
+
+        *self.health.write().unwrap() = Some(report.clone());
+        report
+    }
+
+    /// 获取最近一次健康检查结果，尚未检查时立即检查
+    pub fn health(&self) -> HealthReport {
+        if let Some(report) = self.health.read().unwrap().as_ref() {
+            return report.clone();
+        }
+        self.check_health()
+    }
 
-        HealthReport { tools, timestamp }
+    /// 以给定的健康检查结果代替实际探测
+    #[cfg(test)]
+    pub fn set_health(&self, report: HealthReport) {
+        *self.health.write().unwrap() = Some(report);
+    }
+
+    /// 列出未满足的依赖（工具、语言包、字体）
+    pub fn missing_requirements(&self, requires: &Requirements) -> Vec<MissingRequirement> {
+        let health = self.health();
+        let mut missing = Vec::new();
+
+        for tool in &requires.tools {
+            let probed;
+            let tool_health = match health.tools.get(tool) {
+                Some(tool_health) => tool_health,
+                None => {
+                    probed = Adapter::probe_tool(tool, None, std::env::var_os("PATH").as_deref());
+                    &probed
+                }
+            };
+            missing.extend(unmet_tool(requires, tool, Some(tool_health)));
+        }
+
+        for lang in &requires.lang_packs {
+            if !health.lang_packs.contains(lang) {
+                missing.push(MissingRequirement::not_installed(
+                    RequirementKind::LangPack,
+                    lang,
+                ));
+            }
+        }
+
+        // 无法枚举系统字体时不做字体检查，避免误报
+        if let Some(fonts) = &health.fonts {
+            for font in &requires.fonts {
+                if !fonts.iter().any(|f| f.eq_ignore_ascii_case(font)) {
+                    missing.push(MissingRequirement::not_installed(
+                        RequirementKind::Font,
+                        font,
+                    ));
+                }
+            }
+        }
+
+        missing
     }
 }
 
+/// 工具不满足依赖要求的原因：未安装，或版本不在要求范围内
+fn unmet_tool(
+    requires: &Requirements,
+    tool: &str,
+    health: Option<&ToolHealth>,
+) -> Option<MissingRequirement> {
+    let range = VersionRange::for_tool(requires, tool);
+    let mut missing = MissingRequirement::not_installed(RequirementKind::Tool, tool);
+    missing.required = range
+        .as_ref()
+        .ok()
+        .filter(|range| !range.is_any())
+        .map(VersionRange::to_string);
+
+    let Some(health) = health.filter(|health| health.available) else {
+        return Some(missing);
+    };
+    missing.installed = health.version.clone();
+    missing.reason = match range {
+        Ok(range) if range.is_any() => return None,
+        Ok(range) => match health.version.as_deref().and_then(ToolVersion::parse) {
+            Some(version) if range.contains(version) => return None,
+            Some(_) => MissingReason::VersionMismatch,
+            None => MissingReason::UnknownVersion,
+        },
+        Err(e) => {
+            tracing::warn!("Invalid version requirement for {}: {}", tool, e);
+            MissingReason::InvalidRequirement
+        }
+    };
+    Some(missing)
+}
+
 /// 初始化注册表
-pub fn initialize(_app_handle: &AppHandle) -> Result<()> {
+pub fn initialize(app_handle: &AppHandle) -> Result<()> {
     let registry = Registry::global();
 
-    // 注册内置转换器
-    register_builtin_converters(&registry)?;
+    // 先加载内置清单，再加载用户清单，同 ID 的用户清单覆盖内置声明
+    let dirs = [
+        ManifestLoader::builtin_dir(app_handle)?,
+        ManifestLoader::user_dir(app_handle)?,
+    ];
+    for dir in dirs {
+        let (records, errors) = ManifestLoader::load_dir(&dir);
+        for error in errors {
+            tracing::warn!("Invalid converter manifest: {}", error);
+        }
 
-    // TODO: 扫描并注册外部工具
+        tracing::info!(
+            "Loaded {} converter manifests from {:?}",
+            records.len(),
+            dir
+        );
+        for record in records {
+            if let Err(e) = registry.declare(record) {
+                tracing::warn!("Skipping converter manifest: {}", e);
+            }
+        }
+    }
+
+    // 扫描外部工具并注册可用的转换器
+    registry.refresh()?;
 
-    tracing::info!("Registry initialized with {} capabilities", registry.capabilities.len());
+    tracing::info!(
+        "Registry initialized with {} capabilities",
+        registry.list_all().len()
+    );
 
     Ok(())
 }
 
-/// 注册内置转换器（示例）
-fn register_builtin_converters(registry: &Registry) -> Result<()> {
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::core::planner::Planner;
     use crate::types::*;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    fn capability(id: &str, tool: &str) -> CapabilityRecord {
+        let mut record = test_support::capability(id, "text/markdown", "text/html");
+        record.inputs[0].extensions = vec!["md".to_string()];
+        record.requires.tools = vec![tool.to_string()];
+        record
+    }
 
-    // 1. Pandoc DOCX -> Markdown
-    registry.register(CapabilityRecord {
-        id: "pandoc-docx-md".to_string(),
-        name: "Pandoc DOCX to Markdown".to_string(),
-        version: "3.0.0".to_string(),
-        license: "GPL-2.0".to_string(),
-        vendor: "John MacFarlane".to_string(),
-        binary_path: None,
-        inputs: vec![FormatSpec {
-            mime: "application/vnd.openxmlformats-officedocument.wordprocessingml.document".to_string(),
-            extensions: vec!["docx".to_string()],
-        }],
-        outputs: vec![FormatSpec {
-            mime: "text/markdown".to_string(),
-            extensions: vec!["md".to_string()],
-        }],
-        quality: 0.9,
-        speed: 0.8,
-        cost: ResourceCost {
-            cpu: 0.5,
-            memory: 0.3,
-            gpu: 0.0,
-        },
-        requires: Requirements {
-            tools: vec!["pandoc".to_string()],
-            lang_packs: vec![],
-            fonts: vec![],
-        },
-        preserve: PreserveFeatures {
-            styles: PreserveLevel::Partial,
-            links: PreserveLevel::True,
-            footnotes: PreserveLevel::True,
-            headers: PreserveLevel::True,
-            lists: PreserveLevel::True,
-            tables: PreserveLevel::True,
-            images: PreserveLevel::True,
-            formulas: PreserveLevel::Partial,
-        },
-        risks: vec!["样式可能丢失".to_string()],
-        timeout_s: Some(300),
-        max_mem_mb: Some(512),
-        parallelism: 1,
-        streaming: false,
-    })?;
-
-    // 2. Pandoc DOCX -> PDF
-    registry.register(CapabilityRecord {
-        id: "pandoc-docx-pdf".to_string(),
-        name: "Pandoc DOCX to PDF".to_string(),
-        version: "3.0.0".to_string(),
-        license: "GPL-2.0".to_string(),
-        vendor: "John MacFarlane".to_string(),
-        binary_path: None,
-        inputs: vec![FormatSpec {
-            mime: "application/vnd.openxmlformats-officedocument.wordprocessingml.document".to_string(),
-            extensions: vec!["docx".to_string()],
-        }],
-        outputs: vec![FormatSpec {
-            mime: "application/pdf".to_string(),
-            extensions: vec!["pdf".to_string()],
-        }],
-        quality: 0.85,
-        speed: 0.7,
-        cost: ResourceCost {
-            cpu: 0.7,
-            memory: 0.5,
-            gpu: 0.0,
-        },
-        requires: Requirements {
-            tools: vec!["pandoc".to_string()],
-            lang_packs: vec![],
-            fonts: vec![],
-        },
-        preserve: PreserveFeatures {
-            styles: PreserveLevel::Partial,
-            links: PreserveLevel::True,
-            footnotes: PreserveLevel::True,
-            headers: PreserveLevel::True,
-            lists: PreserveLevel::True,
-            tables: PreserveLevel::True,
-            images: PreserveLevel::True,
-            formulas: PreserveLevel::Partial,
-        },
-        risks: vec!["复杂排版可能失真".to_string()],
-        timeout_s: Some(600),
-        max_mem_mb: Some(1024),
-        parallelism: 1,
-        streaming: false,
-    })?;
-
-    // 3. Pandoc Markdown -> DOCX
-    registry.register(CapabilityRecord {
-        id: "pandoc-md-docx".to_string(),
-        name: "Pandoc Markdown to DOCX".to_string(),
-        version: "3.0.0".to_string(),
-        license: "GPL-2.0".to_string(),
-        vendor: "John MacFarlane".to_string(),
-        binary_path: None,
-        inputs: vec![FormatSpec {
-            mime: "text/markdown".to_string(),
-            extensions: vec!["md".to_string(), "markdown".to_string()],
-        }],
-        outputs: vec![FormatSpec {
-            mime: "application/vnd.openxmlformats-officedocument.wordprocessingml.document".to_string(),
-            extensions: vec!["docx".to_string()],
-        }],
-        quality: 0.88,
-        speed: 0.85,
-        cost: ResourceCost {
-            cpu: 0.4,
-            memory: 0.3,
-            gpu: 0.0,
-        },
-        requires: Requirements {
-            tools: vec!["pandoc".to_string()],
-            lang_packs: vec![],
-            fonts: vec![],
-        },
-        preserve: PreserveFeatures {
-            styles: PreserveLevel::Partial,
-            links: PreserveLevel::True,
-            footnotes: PreserveLevel::True,
-            headers: PreserveLevel::True,
-            lists: PreserveLevel::True,
-            tables: PreserveLevel::True,
-            images: PreserveLevel::True,
-            formulas: PreserveLevel::False,
-        },
-        risks: vec![],
-        timeout_s: Some(300),
-        max_mem_mb: Some(512),
-        parallelism: 1,
-        streaming: false,
-    })?;
-
-    // 4. Pandoc Markdown -> HTML
-    registry.register(CapabilityRecord {
-        id: "pandoc-md-html".to_string(),
-        name: "Pandoc Markdown to HTML".to_string(),
-        version: "3.0.0".to_string(),
-        license: "GPL-2.0".to_string(),
-        vendor: "John MacFarlane".to_string(),
-        binary_path: None,
-        inputs: vec![FormatSpec {
-            mime: "text/markdown".to_string(),
-            extensions: vec!["md".to_string(), "markdown".to_string()],
-        }],
-        outputs: vec![FormatSpec {
-            mime: "text/html".to_string(),
-            extensions: vec!["html".to_string()],
-        }],
-        quality: 0.95,
-        speed: 0.9,
-        cost: ResourceCost {
-            cpu: 0.3,
-            memory: 0.2,
-            gpu: 0.0,
-        },
-        requires: Requirements {
-            tools: vec!["pandoc".to_string()],
-            lang_packs: vec![],
-            fonts: vec![],
-        },
-        preserve: PreserveFeatures {
-            styles: PreserveLevel::True,
-            links: PreserveLevel::True,
-            footnotes: PreserveLevel::True,
-            headers: PreserveLevel::True,
-            lists: PreserveLevel::True,
-            tables: PreserveLevel::True,
-            images: PreserveLevel::True,
-            formulas: PreserveLevel::Partial,
-        },
-        risks: vec![],
-        timeout_s: Some(180),
-        max_mem_mb: Some(256),
-        parallelism: 1,
-        streaming: false,
-    })?;
-
-    // 5. ImageMagick PNG -> WebP
-    registry.register(CapabilityRecord {
-        id: "imagemagick-png-webp".to_string(),
-        name: "ImageMagick PNG to WebP".to_string(),
-        version: "7.1.0".to_string(),
-        license: "Apache-2.0".to_string(),
-        vendor: "ImageMagick Studio".to_string(),
-        binary_path: None,
-        inputs: vec![FormatSpec {
-            mime: "image/png".to_string(),
-            extensions: vec!["png".to_string()],
-        }],
-        outputs: vec![FormatSpec {
-            mime: "image/webp".to_string(),
-            extensions: vec!["webp".to_string()],
-        }],
-        quality: 0.92,
-        speed: 0.85,
-        cost: ResourceCost {
-            cpu: 0.6,
-            memory: 0.4,
-            gpu: 0.0,
-        },
-        requires: Requirements {
-            tools: vec!["magick".to_string()],
-            lang_packs: vec![],
-            fonts: vec![],
-        },
-        preserve: PreserveFeatures {
-            styles: PreserveLevel::True,
-            links: PreserveLevel::False,
-            footnotes: PreserveLevel::False,
-            headers: PreserveLevel::False,
-            lists: PreserveLevel::False,
-            tables: PreserveLevel::False,
-            images: PreserveLevel::True,
-            formulas: PreserveLevel::False,
-        },
-        risks: vec!["有损压缩".to_string()],
-        timeout_s: Some(120),
-        max_mem_mb: Some(2048),
-        parallelism: 4,
-        streaming: false,
-    })?;
-
-    // 6. ImageMagick JPG -> PNG
-    registry.register(CapabilityRecord {
-        id: "imagemagick-jpg-png".to_string(),
-        name: "ImageMagick JPG to PNG".to_string(),
-        version: "7.1.0".to_string(),
-        license: "Apache-2.0".to_string(),
-        vendor: "ImageMagick Studio".to_string(),
-        binary_path: None,
-        inputs: vec![FormatSpec {
-            mime: "image/jpeg".to_string(),
-            extensions: vec!["jpg".to_string(), "jpeg".to_string()],
-        }],
-        outputs: vec![FormatSpec {
-            mime: "image/png".to_string(),
-            extensions: vec!["png".to_string()],
-        }],
-        quality: 1.0,
-        speed: 0.9,
-        cost: ResourceCost {
-            cpu: 0.4,
-            memory: 0.3,
-            gpu: 0.0,
-        },
-        requires: Requirements {
-            tools: vec!["magick".to_string()],
-            lang_packs: vec![],
-            fonts: vec![],
-        },
-        preserve: PreserveFeatures {
-            styles: PreserveLevel::True,
-            links: PreserveLevel::False,
-            footnotes: PreserveLevel::False,
-            headers: PreserveLevel::False,
-            lists: PreserveLevel::False,
-            tables: PreserveLevel::False,
-            images: PreserveLevel::True,
-            formulas: PreserveLevel::False,
-        },
-        risks: vec![],
-        timeout_s: Some(120),
-        max_mem_mb: Some(2048),
-        parallelism: 4,
-        streaming: false,
-    })?;
+    /// 在目录中写入一个输出固定版本信息的假工具
+    fn fake_tool(dir: &Path, name: &str, version_output: &str) -> String {
+        let path = dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\necho '{}'\n", version_output)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path.to_string_lossy().to_string()
+    }
 
-    tracing::info!("Registered {} converters", 6);
+    #[test]
+    fn test_refresh_registers_only_available_tools() {
+        let bin_dir = tempfile::tempdir().unwrap();
+        let pandoc = fake_tool(
+            bin_dir.path(),
+            "pandoc",
+            "pandoc 3.1.2\nCompiled with pandoc-types",
+        );
 
-    Ok(())
+        let registry = Registry::new();
+        registry
+            .declare(capability("pandoc-md-html", "pandoc"))
+            .unwrap();
+        registry
+            .declare(capability("magick-md-html", "magick"))
+            .unwrap();
+        registry
+            .refresh_in(Some(bin_dir.path().as_os_str()))
+            .unwrap();
+
+        let registered = registry.get("pandoc-md-html").unwrap();
+        assert_eq!(registered.binary_path.as_deref(), Some(pandoc.as_str()));
+        assert!(registry.get("magick-md-html").is_none());
+        assert_eq!(
+            registry.find_converters("md", "html"),
+            vec!["pandoc-md-html"]
+        );
+
+        let health = registry.health();
+        let pandoc_health = &health.tools["pandoc"];
+        assert!(pandoc_health.available);
+        assert_eq!(pandoc_health.version.as_deref(), Some("3.1.2"));
+        assert!(!health.tools["magick"].available);
+        assert!(!health.tools["ffmpeg"].available);
+    }
+
+    struct NoopPlugin(CapabilityRecord);
+
+    impl ConverterPlugin for NoopPlugin {
+        fn capability(&self) -> CapabilityRecord {
+            self.0.clone()
+        }
+
+        fn convert(
+            &self,
+            _input: &Path,
+            _output: &Path,
+            _params: &serde_json::Value,
+        ) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_plugins_survive_refresh() {
+        let bin_dir = tempfile::tempdir().unwrap();
+        let mut record = capability("builtin-md-html", "pandoc");
+        record.requires.tools.clear();
+
+        let registry = Registry::new();
+        registry
+            .register_plugin(Arc::new(NoopPlugin(record)))
+            .unwrap();
+        registry
+            .declare(capability("pandoc-md-html", "pandoc"))
+            .unwrap();
+        registry
+            .refresh_in(Some(bin_dir.path().as_os_str()))
+            .unwrap();
+
+        assert!(registry.get("builtin-md-html").is_some());
+        assert!(registry.converter("builtin-md-html").is_some());
+        assert!(registry.converter("pandoc-md-html").is_none());
+    }
+
+    #[test]
+    fn test_register_is_idempotent() {
+        let bin_dir = tempfile::tempdir().unwrap();
+        let mut record = capability("builtin-md-html", "pandoc");
+        record.requires.tools.clear();
+
+        let registry = Registry::new();
+        registry
+            .register_plugin(Arc::new(NoopPlugin(record.clone())))
+            .unwrap();
+        registry
+            .register_plugin(Arc::new(NoopPlugin(record.clone())))
+            .unwrap();
+        registry
+            .refresh_in(Some(bin_dir.path().as_os_str()))
+            .unwrap();
+        assert_eq!(
+            registry.find_converters("md", "html"),
+            vec!["builtin-md-html"]
+        );
+        assert_eq!(registry.edges_from("text/markdown").len(), 1);
+
+        // 重新注册时按新的能力更新格式图
+        record.outputs[0].extensions = vec!["htm".to_string()];
+        registry
+            .register_plugin(Arc::new(NoopPlugin(record)))
+            .unwrap();
+        assert!(registry.find_converters("md", "html").is_empty());
+        assert_eq!(
+            registry.find_converters("md", "htm"),
+            vec!["builtin-md-html"]
+        );
+
+        // 插件与转换器清单不能使用相同的 ID
+        registry
+            .declare(capability("pandoc-md-html", "pandoc"))
+            .unwrap();
+        let mut clash = capability("pandoc-md-html", "pandoc");
+        clash.requires.tools.clear();
+        assert!(registry
+            .register_plugin(Arc::new(NoopPlugin(clash)))
+            .is_err());
+        assert!(registry
+            .declare(capability("builtin-md-html", "pandoc"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_refresh_honours_binary_path_override() {
+        let bin_dir = tempfile::tempdir().unwrap();
+        let custom = fake_tool(
+            bin_dir.path(),
+            "magick-7",
+            "Version: ImageMagick 7.1.1-15 Q16",
+        );
+
+        let mut record = capability("magick-png-webp", "magick");
+        record.binary_path = Some(custom.clone());
+
+        let registry = Registry::new();
+        registry.declare(record).unwrap();
+        registry.refresh_in(None).unwrap();
+
+        assert!(registry.get("magick-png-webp").is_some());
+        let health = registry.health();
+        assert_eq!(
+            health.tools["magick"].path.as_deref(),
+            Some(custom.as_str())
+        );
+        assert_eq!(health.tools["magick"].version.as_deref(), Some("7.1.1-15"));
+    }
+
+    #[test]
+    fn test_binary_path_resolves_command_tool() {
+        let bin_dir = tempfile::tempdir().unwrap();
+        fake_tool(bin_dir.path(), "gs", "10.02.1");
+        let qpdf = fake_tool(bin_dir.path(), "qpdf", "qpdf version 11.9.0");
+
+        // 命令模板调用的是第二个依赖工具
+        let mut record = capability("qpdf-md-html", "gs");
+        record.requires.tools.push("qpdf".to_string());
+        record.command = Some(CommandTemplate {
+            tool: "qpdf".to_string(),
+            args: vec!["{input}".to_string(), "{output}".to_string()],
+            options: Default::default(),
+        });
+        ManifestLoader::validate(&record).unwrap();
+
+        let registry = Registry::new();
+        registry.declare(record.clone()).unwrap();
+        registry
+            .refresh_in(Some(bin_dir.path().as_os_str()))
+            .unwrap();
+        let registered = registry.get("qpdf-md-html").unwrap();
+        assert_eq!(registered.binary_path.as_deref(), Some(qpdf.as_str()));
+
+        // 声明的 binary_path 覆盖命令模板调用的工具
+        let custom_dir = tempfile::tempdir().unwrap();
+        let custom = fake_tool(custom_dir.path(), "qpdf-custom", "qpdf version 11.9.0");
+        record.binary_path = Some(custom.clone());
+        registry.declare(record).unwrap();
+        registry
+            .refresh_in(Some(bin_dir.path().as_os_str()))
+            .unwrap();
+        let health = registry.health();
+        assert_eq!(health.tools["qpdf"].path.as_deref(), Some(custom.as_str()));
+        assert_ne!(health.tools["gs"].path.as_deref(), Some(custom.as_str()));
+        assert_eq!(
+            registry.get("qpdf-md-html").unwrap().binary_path.as_deref(),
+            Some(custom.as_str())
+        );
+    }
+
+    #[test]
+    fn test_targets_with_missing_tools_are_listed() {
+        let bin_dir = tempfile::tempdir().unwrap();
+        fake_tool(bin_dir.path(), "pandoc", "pandoc 3.1.2");
+        let mut needs_missing = capability("missing-md-txt", "f2f-test-missing-tool");
+        needs_missing.outputs = vec![FormatSpec {
+            mime: "text/plain".to_string(),
+            extensions: vec!["txt".to_string()],
+        }];
+
+        let registry = Arc::new(Registry::new());
+        registry
+            .declare(capability("pandoc-md-html", "pandoc"))
+            .unwrap();
+        registry.declare(needs_missing).unwrap();
+        registry
+            .refresh_in(Some(bin_dir.path().as_os_str()))
+            .unwrap();
+        assert!(registry.converter("missing-md-txt").is_none());
+
+        let targets = Planner::with_registry(registry.clone())
+            .get_available_targets("text/markdown", &ConversionOptions::default());
+        assert_eq!(targets.len(), 2);
+        assert!(targets[0].available && targets[0].mime_type == "text/html");
+        assert!(!targets[1].available && targets[1].mime_type == "text/plain");
+        assert_eq!(
+            targets[1].missing_requirements,
+            vec![MissingRequirement::not_installed(
+                RequirementKind::Tool,
+                "f2f-test-missing-tool"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_refresh_checks_tool_version_range() {
+        let bin_dir = tempfile::tempdir().unwrap();
+        fake_tool(
+            bin_dir.path(),
+            "pandoc",
+            "pandoc 2.19.2\nCompiled with pandoc-types",
+        );
+
+        let mut needs_new = capability("pandoc-new-md-txt", "pandoc");
+        needs_new
+            .requires
+            .min_version
+            .insert("pandoc".to_string(), "3.0".to_string());
+        needs_new.outputs = vec![FormatSpec {
+            mime: "text/plain".to_string(),
+            extensions: vec!["txt".to_string()],
+        }];
+        let mut needs_old = capability("pandoc-old-md-html", "pandoc");
+        needs_old
+            .requires
+            .max_version
+            .insert("pandoc".to_string(), "2".to_string());
+
+        let registry = Arc::new(Registry::new());
+        registry.declare(needs_new.clone()).unwrap();
+        registry.declare(needs_old).unwrap();
+        registry
+            .refresh_in(Some(bin_dir.path().as_os_str()))
+            .unwrap();
+
+        // 版本不符的能力不可执行，但目标仍会列出并给出原因
+        assert!(registry.get("pandoc-new-md-txt").is_none());
+        assert!(registry.converter("pandoc-new-md-txt").is_none());
+        assert!(registry.get("pandoc-old-md-html").is_some());
+        let targets = Planner::with_registry(registry.clone())
+            .get_available_targets("text/markdown", &ConversionOptions::default());
+        let html = targets.iter().find(|t| t.mime_type == "text/html").unwrap();
+        assert!(html.available);
+        let txt = targets
+            .iter()
+            .find(|t| t.mime_type == "text/plain")
+            .unwrap();
+        assert!(!txt.available);
+        assert_eq!(txt.paths[0].steps[0].converter_id, "pandoc-new-md-txt");
+
+        let missing = &txt.missing_requirements;
+        assert_eq!(missing.len(), 1);
+        assert_eq!(
+            (missing[0].kind, missing[0].reason),
+            (RequirementKind::Tool, MissingReason::VersionMismatch)
+        );
+        assert_eq!(missing[0].required.as_deref(), Some(">= 3.0"));
+        assert_eq!(missing[0].installed.as_deref(), Some("2.19.2"));
+        assert_eq!(missing[0].to_string(), "pandoc >= 3.0 (已安装 2.19.2)");
+        assert_eq!(registry.missing_requirements(&needs_new.requires), *missing);
+    }
 }
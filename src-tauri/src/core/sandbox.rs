@@ -0,0 +1,619 @@
+use crate::error::{AppError, Result};
+use crate::types::IsolationLevel;
+use std::path::Path;
+use std::process::ExitStatus;
+use std::time::Duration;
+
+/// 传给外部工具的环境变量，其余一律清除
+const ENV_ALLOWLIST: &[&str] = &["PATH", "LANG", "LC_ALL", "LC_CTYPE", "TZ", "SYSTEMROOT", "WINDIR"];
+
+/// CPU 时间软限制与硬限制的差值，超过软限制时工具先收到 SIGXCPU
+#[cfg(unix)]
+const CPU_HARD_LIMIT_GRACE_S: u64 = 5;
+
+/// 内存分配失败时工具常见的错误输出
+const OUT_OF_MEMORY_MARKERS: &[&str] = &[
+    "out of memory",
+    "cannot allocate memory",
+    "bad_alloc",
+    "memoryallocationfailed",
+    "xmalloc",
+];
+
+/// 外部工具的资源限制，取自转换器的 `timeout_s` 与 `max_mem_mb`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResourceLimits {
+    /// 运行时间上限，同时作为 CPU 时间上限
+    pub timeout_s: Option<u32>,
+    pub max_mem_mb: Option<u32>,
+}
+
+impl ResourceLimits {
+    pub fn new(timeout_s: Option<u32>, max_mem_mb: Option<u32>) -> Self {
+        Self { timeout_s, max_mem_mb }
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_s.map(|s| Duration::from_secs(s as u64))
+    }
+
+    /// 为命令设置资源限制
+    ///
+    /// 内存优先使用 cgroup v2 子组限制整个进程树，不可用时对工具进程设置 RLIMIT_AS；
+    /// CPU 时间通过 RLIMIT_CPU 限制。返回的 cgroup 在工具结束后释放。
+    pub fn apply(&self, command: &mut tokio::process::Command) -> Option<MemoryCgroup> {
+        let memory_bytes = self.max_mem_mb.map(|mb| mb as u64 * 1024 * 1024);
+        let cgroup = memory_bytes.and_then(MemoryCgroup::create);
+
+        #[cfg(unix)]
+        {
+            let cgroup_procs = cgroup.as_ref().and_then(MemoryCgroup::procs_path);
+            let cpu_s = self.timeout_s.map(u64::from);
+
+            // 在 fork 之后、exec 之前执行，只调用 async-signal-safe 的系统调用
+            unsafe {
+                command.pre_exec(move || {
+                    let in_cgroup = cgroup_procs.as_deref().is_some_and(join_cgroup);
+                    if let Some(bytes) = memory_bytes.filter(|_| !in_cgroup) {
+                        set_rlimit(libc::RLIMIT_AS as _, bytes, bytes)?;
+                    }
+                    if let Some(seconds) = cpu_s {
+                        set_rlimit(libc::RLIMIT_CPU as _, seconds, seconds + CPU_HARD_LIMIT_GRACE_S)?;
+                    }
+                    Ok(())
+                });
+            }
+        }
+
+        cgroup
+    }
+
+    /// 根据工具的退出状态判断是否超出了资源限制
+    pub fn violation(
+        &self,
+        tool: &str,
+        status: &ExitStatus,
+        stderr: &str,
+        cgroup: Option<&MemoryCgroup>,
+    ) -> Option<AppError> {
+        if status.success() {
+            return None;
+        }
+
+        if let Some(mb) = self.max_mem_mb {
+            if cgroup.is_some_and(MemoryCgroup::oom_killed) {
+                return Some(AppError::ResourceLimit(format!(
+                    "工具 {} 内存占用超过 {} MB 上限，已被终止",
+                    tool, mb
+                )));
+            }
+
+            // RLIMIT_AS 下分配失败通常表现为错误输出或 SIGSEGV/SIGABRT
+            let stderr = stderr.to_lowercase();
+            if OUT_OF_MEMORY_MARKERS.iter().any(|marker| stderr.contains(marker))
+                || (cgroup.is_none() && crashed_on_allocation(status))
+            {
+                return Some(AppError::ResourceLimit(format!(
+                    "工具 {} 内存分配失败，可能超过 {} MB 上限",
+                    tool, mb
+                )));
+            }
+        }
+
+        #[cfg(unix)]
+        if let Some(seconds) = self.timeout_s {
+            use std::os::unix::process::ExitStatusExt;
+            if status.signal() == Some(libc::SIGXCPU) {
+                return Some(AppError::ResourceLimit(format!(
+                    "工具 {} CPU 时间超过 {} 秒上限，已被终止",
+                    tool, seconds
+                )));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(unix)]
+fn crashed_on_allocation(status: &ExitStatus) -> bool {
+    use std::os::unix::process::ExitStatusExt;
+    matches!(status.signal(), Some(libc::SIGSEGV | libc::SIGABRT | libc::SIGBUS))
+}
+
+#[cfg(not(unix))]
+fn crashed_on_allocation(_status: &ExitStatus) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn set_rlimit(resource: i32, soft: u64, hard: u64) -> std::io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: soft as libc::rlim_t,
+        rlim_max: hard as libc::rlim_t,
+    };
+    if unsafe { libc::setrlimit(resource as _, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// 将当前进程加入 cgroup
+#[cfg(unix)]
+fn join_cgroup(procs: &std::ffi::CStr) -> bool {
+    unsafe {
+        let fd = libc::open(procs.as_ptr(), libc::O_WRONLY);
+        if fd < 0 {
+            return false;
+        }
+        let written = libc::write(fd, b"0".as_ptr().cast(), 1);
+        libc::close(fd);
+        written == 1
+    }
+}
+
+/// 单次工具调用的 cgroup v2 子组，限制工具及其子进程的内存总量
+///
+/// 只在应用所在的 cgroup 已向子组开放 memory 控制器时可用。
+#[cfg(target_os = "linux")]
+pub struct MemoryCgroup {
+    path: std::path::PathBuf,
+}
+
+#[cfg(target_os = "linux")]
+impl MemoryCgroup {
+    const ROOT: &'static str = "/sys/fs/cgroup";
+
+    fn create(max_bytes: u64) -> Option<Self> {
+        use std::path::Path;
+        use std::sync::atomic::{AtomicU64, Ordering};
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+
+        // 只支持纯 cgroup v2 挂载
+        let root = Path::new(Self::ROOT);
+        if !root.join("cgroup.controllers").exists() {
+            return None;
+        }
+        let own = std::fs::read_to_string("/proc/self/cgroup").ok()?;
+        let relative = own.lines().find_map(|line| line.strip_prefix("0::"))?;
+        let parent = root.join(relative.trim_start_matches('/'));
+        let controllers = std::fs::read_to_string(parent.join("cgroup.subtree_control")).ok()?;
+        if !controllers.split_whitespace().any(|c| c == "memory") {
+            return None;
+        }
+
+        let name = format!("f2f-{}-{}", std::process::id(), NEXT.fetch_add(1, Ordering::Relaxed));
+        let path = parent.join(name);
+        std::fs::create_dir(&path).ok()?;
+        let cgroup = Self { path };
+        std::fs::write(cgroup.path.join("memory.max"), max_bytes.to_string()).ok()?;
+        let _ = std::fs::write(cgroup.path.join("memory.swap.max"), "0");
+        Some(cgroup)
+    }
+
+    fn procs_path(&self) -> Option<std::ffi::CString> {
+        use std::os::unix::ffi::OsStrExt;
+        std::ffi::CString::new(self.path.join("cgroup.procs").as_os_str().as_bytes()).ok()
+    }
+
+    /// 子组内是否有进程因超出内存上限被终止
+    fn oom_killed(&self) -> bool {
+        std::fs::read_to_string(self.path.join("memory.events"))
+            .ok()
+            .and_then(|events| {
+                events.lines().find_map(|line| {
+                    line.strip_prefix("oom_kill ")
+                        .and_then(|count| count.trim().parse::<u64>().ok())
+                })
+            })
+            .is_some_and(|count| count > 0)
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for MemoryCgroup {
+    fn drop(&mut self) {
+        // 结束残留进程后删除子组
+        let _ = std::fs::write(self.path.join("cgroup.kill"), "1");
+        for _ in 0..20 {
+            if std::fs::remove_dir(&self.path).is_ok() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        tracing::warn!("Failed to remove cgroup {}", self.path.display());
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub struct MemoryCgroup;
+
+#[cfg(not(target_os = "linux"))]
+impl MemoryCgroup {
+    fn create(_max_bytes: u64) -> Option<Self> {
+        None
+    }
+
+    #[cfg(unix)]
+    fn procs_path(&self) -> Option<std::ffi::CString> {
+        None
+    }
+
+    fn oom_killed(&self) -> bool {
+        false
+    }
+}
+
+/// 当前系统上外部工具的隔离级别
+pub fn isolation_level() -> IsolationLevel {
+    #[cfg(target_os = "linux")]
+    if landlock::abi_version().is_some() {
+        return IsolationLevel::Landlock;
+    }
+    IsolationLevel::Restricted
+}
+
+/// 单次工具调用的隔离环境
+///
+/// 工具在私有临时目录中运行 (同时作为 HOME 与 TMPDIR)，环境变量只保留 `ENV_ALLOWLIST`。
+/// 支持 Landlock 时，工具只能读取系统目录、自身所在目录与输入文件，只能写入输出目录与私有目录。
+pub struct ToolIsolation {
+    level: IsolationLevel,
+    _workdir: tempfile::TempDir,
+    #[cfg(target_os = "linux")]
+    _ruleset: Option<landlock::Ruleset>,
+}
+
+impl ToolIsolation {
+    pub fn apply(
+        command: &mut tokio::process::Command,
+        program: &Path,
+        inputs: &[&Path],
+        output_dir: &Path,
+    ) -> Result<Self> {
+        let workdir = tempfile::Builder::new().prefix("f2f-tool-").tempdir()?;
+
+        command.env_clear().current_dir(workdir.path());
+        for key in ENV_ALLOWLIST {
+            if let Some(value) = std::env::var_os(key) {
+                command.env(key, value);
+            }
+        }
+        for key in ["HOME", "TMPDIR", "TMP", "TEMP"] {
+            command.env(key, workdir.path());
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let ruleset = landlock::abi_version().and_then(|abi| {
+                match landlock::Ruleset::for_tool(abi, program, inputs, output_dir, workdir.path()) {
+                    Ok(ruleset) => Some(ruleset),
+                    Err(e) => {
+                        tracing::warn!("Landlock unavailable for {}: {}", program.display(), e);
+                        None
+                    }
+                }
+            });
+            if let Some(ruleset) = &ruleset {
+                let fd = ruleset.raw_fd();
+                unsafe {
+                    command.pre_exec(move || landlock::restrict_self(fd));
+                }
+            }
+
+            let level = if ruleset.is_some() {
+                IsolationLevel::Landlock
+            } else {
+                IsolationLevel::Restricted
+            };
+            Ok(Self {
+                level,
+                _workdir: workdir,
+                _ruleset: ruleset,
+            })
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = (program, inputs, output_dir);
+            Ok(Self {
+                level: IsolationLevel::Restricted,
+                _workdir: workdir,
+            })
+        }
+    }
+
+    pub fn level(&self) -> IsolationLevel {
+        self.level
+    }
+}
+
+/// Landlock 文件系统访问控制 (Linux 5.13+)，通过系统调用直接使用
+#[cfg(target_os = "linux")]
+mod landlock {
+    use once_cell::sync::Lazy;
+    use std::fs::OpenOptions;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::path::Path;
+
+    const CREATE_RULESET_VERSION: libc::c_uint = 1 << 0;
+    const RULE_PATH_BENEATH: libc::c_int = 1;
+
+    const EXECUTE: u64 = 1 << 0;
+    const WRITE_FILE: u64 = 1 << 1;
+    const READ_FILE: u64 = 1 << 2;
+    const READ_DIR: u64 = 1 << 3;
+    /// ABI v1 中的全部权限 (执行、读写、删除与创建各类文件)
+    const ABI_V1: u64 = (1 << 13) - 1;
+    const REFER: u64 = 1 << 13;
+    const TRUNCATE: u64 = 1 << 14;
+    const IOCTL_DEV: u64 = 1 << 15;
+
+    /// 可以授予单个文件的权限
+    const FILE_ACCESS: u64 = EXECUTE | WRITE_FILE | READ_FILE | TRUNCATE | IOCTL_DEV;
+    const READ: u64 = EXECUTE | READ_FILE | READ_DIR;
+    const DEVICES: u64 = READ_FILE | WRITE_FILE | READ_DIR | TRUNCATE | IOCTL_DEV;
+
+    /// 工具运行所需的只读系统目录，不存在的目录会被跳过
+    const SYSTEM_DIRS: &[&str] = &[
+        "/usr",
+        "/bin",
+        "/sbin",
+        "/lib",
+        "/lib32",
+        "/lib64",
+        "/etc",
+        "/opt",
+        "/nix/store",
+        "/proc",
+        "/sys",
+        "/var/cache/fontconfig",
+    ];
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    static ABI_VERSION: Lazy<Option<u32>> = Lazy::new(|| {
+        let version = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<RulesetAttr>(),
+                0usize,
+                CREATE_RULESET_VERSION,
+            )
+        };
+        (version > 0).then_some(version as u32)
+    });
+
+    /// 内核支持的 Landlock ABI 版本，不支持时为 None
+    pub fn abi_version() -> Option<u32> {
+        *ABI_VERSION
+    }
+
+    /// Landlock 规则集
+    pub struct Ruleset {
+        fd: OwnedFd,
+        handled: u64,
+    }
+
+    impl Ruleset {
+        fn new(abi: u32) -> std::io::Result<Self> {
+            let mut handled = ABI_V1;
+            if abi >= 2 {
+                handled |= REFER;
+            }
+            if abi >= 3 {
+                handled |= TRUNCATE;
+            }
+            if abi >= 5 {
+                handled |= IOCTL_DEV;
+            }
+
+            let attr = RulesetAttr {
+                handled_access_fs: handled,
+            };
+            let fd = unsafe {
+                libc::syscall(
+                    libc::SYS_landlock_create_ruleset,
+                    &attr as *const RulesetAttr,
+                    std::mem::size_of::<RulesetAttr>(),
+                    0u32,
+                )
+            };
+            if fd < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+
+            Ok(Self {
+                fd: unsafe { OwnedFd::from_raw_fd(fd as RawFd) },
+                handled,
+            })
+        }
+
+        /// 单次工具调用的规则集
+        pub fn for_tool(
+            abi: u32,
+            program: &Path,
+            inputs: &[&Path],
+            output_dir: &Path,
+            workdir: &Path,
+        ) -> std::io::Result<Self> {
+            let mut ruleset = Self::new(abi)?;
+            for dir in SYSTEM_DIRS {
+                ruleset.allow_if_exists(Path::new(dir), READ)?;
+            }
+            ruleset.allow_if_exists(Path::new("/dev"), DEVICES)?;
+            if let Some(dir) = program.parent().filter(|dir| dir.is_absolute()) {
+                ruleset.allow_if_exists(dir, READ)?;
+            }
+            for input in inputs {
+                ruleset.allow(input, READ)?;
+            }
+            ruleset.allow(output_dir, u64::MAX)?;
+            ruleset.allow(workdir, u64::MAX)?;
+            Ok(ruleset)
+        }
+
+        /// 允许访问 `path` 及其下的文件，`path` 为文件时只授予文件相关的权限
+        fn allow(&mut self, path: &Path, access: u64) -> std::io::Result<()> {
+            let file = OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_PATH | libc::O_CLOEXEC)
+                .open(path)?;
+            let mut access = access & self.handled;
+            if !file.metadata()?.is_dir() {
+                access &= FILE_ACCESS;
+            }
+
+            let attr = PathBeneathAttr {
+                allowed_access: access,
+                parent_fd: file.as_raw_fd(),
+            };
+            let result = unsafe {
+                libc::syscall(
+                    libc::SYS_landlock_add_rule,
+                    self.fd.as_raw_fd(),
+                    RULE_PATH_BENEATH,
+                    &attr as *const PathBeneathAttr,
+                    0u32,
+                )
+            };
+            if result < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        fn allow_if_exists(&mut self, path: &Path, access: u64) -> std::io::Result<()> {
+            match self.allow(path, access) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                result => result,
+            }
+        }
+
+        pub fn raw_fd(&self) -> RawFd {
+            self.fd.as_raw_fd()
+        }
+    }
+
+    /// 在子进程 exec 之前调用，使规则集对该进程及其后代生效
+    pub fn restrict_self(ruleset_fd: RawFd) -> std::io::Result<()> {
+        unsafe {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            if libc::syscall(libc::SYS_landlock_restrict_self, ruleset_fd, 0u32) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::core::adapter::Adapter;
+    use crate::core::invocation::ToolInvocation;
+    use std::time::Instant;
+
+    fn run_with(
+        script: &str,
+        input: &Path,
+        output: &Path,
+        timeout_s: Option<u32>,
+        max_mem_mb: Option<u32>,
+    ) -> Result<std::process::Output> {
+        let invocation = ToolInvocation::new("sh", ["-c", script]);
+        Adapter::execute_sandboxed(&invocation, input, output, timeout_s, max_mem_mb)
+    }
+
+    fn run(script: &str, timeout_s: Option<u32>, max_mem_mb: Option<u32>) -> Result<std::process::Output> {
+        run_with(script, Path::new("/dev/null"), Path::new("/dev/null"), timeout_s, max_mem_mb)
+    }
+
+    #[test]
+    fn test_timeout_kills_process_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("child-finished");
+
+        // 后台子进程同样会被结束，不会在超时后写入标记文件
+        let started = Instant::now();
+        let script = format!("(sleep 2; touch {}) & sleep 30", marker.display());
+        let result = run_with(&script, Path::new("/dev/null"), &marker, Some(1), None);
+        assert!(matches!(&result, Err(AppError::ResourceLimit(msg)) if msg.contains("1 秒")), "{:?}", result);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        std::thread::sleep(Duration::from_millis(1500));
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    fn test_memory_limit_is_enforced() {
+        let script = "x=$(head -c 200000000 /dev/zero | tr '\\0' a); echo ${#x}";
+        let result = run(script, None, Some(64));
+        assert!(matches!(&result, Err(AppError::ResourceLimit(msg)) if msg.contains("64 MB")), "{:?}", result);
+
+        // 未超出限制时正常返回输出
+        let output = run("echo ok", Some(10), Some(64)).unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "ok");
+    }
+
+    #[test]
+    fn test_tool_isolation() {
+        let inputs = tempfile::tempdir().unwrap();
+        let outputs = tempfile::tempdir().unwrap();
+        let input = inputs.path().join("doc.txt");
+        let sibling = inputs.path().join("secret.txt");
+        let output = outputs.path().join("doc.out");
+        std::fs::write(&input, "content").unwrap();
+        std::fs::write(&sibling, "secret").unwrap();
+
+        // 输入可读、输出目录可写，工作目录为私有目录
+        let script = format!("cat {} > {} && pwd", input.display(), output.display());
+        let result = run_with(&script, &input, &output, None, None).unwrap();
+        let stdout = String::from_utf8_lossy(&result.stdout);
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "content");
+        assert!(!stdout.contains(&*inputs.path().to_string_lossy()));
+
+        if isolation_level() == IsolationLevel::Landlock {
+            let read_sibling = format!("cat {}", sibling.display());
+            assert!(run_with(&read_sibling, &input, &output, None, None).is_err());
+
+            let write_input_dir = format!("echo x > {}", inputs.path().join("new.txt").display());
+            assert!(run_with(&write_input_dir, &input, &output, None, None).is_err());
+            assert!(!inputs.path().join("new.txt").exists());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_environment_is_cleared() {
+        let program = Path::new("/usr/bin/env");
+        let mut command = tokio::process::Command::new(program);
+        command.env("F2F_SANDBOX_SECRET", "leaked");
+        let outputs = tempfile::tempdir().unwrap();
+        let _isolation = ToolIsolation::apply(&mut command, program, &[], outputs.path()).unwrap();
+
+        // 只保留白名单中的变量，HOME 与临时目录指向私有目录
+        let output = command.output().await.unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let workdir = command.as_std().get_current_dir().unwrap().to_string_lossy().to_string();
+        for line in stdout.lines() {
+            let (key, value) = line.split_once('=').unwrap();
+            match key {
+                "HOME" | "TMPDIR" | "TMP" | "TEMP" => assert_eq!(value, workdir),
+                _ => assert!(ENV_ALLOWLIST.contains(&key), "{}", line),
+            }
+        }
+        assert!(stdout.contains(&format!("HOME={}", workdir)), "{}", stdout);
+    }
+}
@@ -0,0 +1,338 @@
+use crate::types::{AppSettings, CapabilityRecord, ResourceCost};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::Notify;
+
+/// 未声明 `max_mem_mb` 的步骤按此内存占用计入预算
+const DEFAULT_STEP_MEMORY_MB: u64 = 256;
+
+/// 资源开销达到此值时视为该资源密集型
+const HEAVY_COST: f32 = 0.5;
+
+/// 步骤的资源类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceClass {
+    Cpu,
+    Io,
+    Gpu,
+}
+
+impl ResourceClass {
+    /// 根据资源开销分类：使用 GPU 的归为 GPU，CPU 开销高的归为 CPU，其余为 IO
+    pub fn classify(cost: &ResourceCost) -> Self {
+        if cost.gpu >= HEAVY_COST {
+            ResourceClass::Gpu
+        } else if cost.cpu >= HEAVY_COST {
+            ResourceClass::Cpu
+        } else {
+            ResourceClass::Io
+        }
+    }
+}
+
+/// 调度限制
+#[derive(Debug, Clone)]
+pub struct SchedulerLimits {
+    /// 同时执行的任务数
+    pub max_concurrent_jobs: usize,
+    /// 各资源类别同时运行的步骤数
+    pub cpu_slots: u32,
+    pub io_slots: u32,
+    pub gpu_slots: u32,
+    /// 运行中步骤的内存预算
+    pub memory_budget_mb: u64,
+}
+
+impl SchedulerLimits {
+    /// 由设置推导限制，CPU 类步骤数不超过可用核心数
+    pub fn from_settings(settings: &AppSettings) -> Self {
+        let cores = std::thread::available_parallelism()
+            .map(|n| n.get() as u32)
+            .unwrap_or(1);
+
+        Self {
+            max_concurrent_jobs: settings.max_concurrent_jobs.max(1) as usize,
+            cpu_slots: cores,
+            io_slots: cores * 2,
+            gpu_slots: 1,
+            memory_budget_mb: settings.memory_budget_mb as u64,
+        }
+    }
+
+    fn slots(&self, class: ResourceClass) -> u32 {
+        match class {
+            ResourceClass::Cpu => self.cpu_slots,
+            ResourceClass::Io => self.io_slots,
+            ResourceClass::Gpu => self.gpu_slots,
+        }
+    }
+}
+
+impl Default for SchedulerLimits {
+    fn default() -> Self {
+        Self::from_settings(&AppSettings::default())
+    }
+}
+
+/// 单个步骤的资源需求
+#[derive(Debug, Clone)]
+pub struct StepDemand {
+    pub capability_id: String,
+    pub class: ResourceClass,
+    /// 该转换器允许同时运行的步骤数
+    pub parallelism: u32,
+    pub memory_mb: u64,
+}
+
+impl StepDemand {
+    pub fn for_capability(cap: &CapabilityRecord) -> Self {
+        Self {
+            capability_id: cap.id.clone(),
+            class: ResourceClass::classify(&cap.cost),
+            parallelism: cap.parallelism.max(1),
+            memory_mb: cap.max_mem_mb.map(u64::from).unwrap_or(DEFAULT_STEP_MEMORY_MB),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Usage {
+    classes: HashMap<ResourceClass, u32>,
+    capabilities: HashMap<String, u32>,
+    memory_mb: u64,
+}
+
+/// 资源调度器 - 步骤执行前申请资源许可，按资源类别、转换器并发度和内存预算限流
+pub struct Scheduler {
+    limits: RwLock<SchedulerLimits>,
+    usage: Mutex<Usage>,
+    released: Notify,
+}
+
+impl Scheduler {
+    pub fn new(limits: SchedulerLimits) -> Self {
+        Self {
+            limits: RwLock::new(limits),
+            usage: Mutex::new(Usage::default()),
+            released: Notify::new(),
+        }
+    }
+
+    pub fn limits(&self) -> SchedulerLimits {
+        self.limits.read().unwrap().clone()
+    }
+
+    /// 更新限制，已运行的步骤不受影响
+    pub fn set_limits(&self, limits: SchedulerLimits) {
+        *self.limits.write().unwrap() = limits;
+        self.released.notify_waiters();
+    }
+
+    /// 等待直到资源足够运行该步骤，返回的许可在释放时归还资源
+    pub async fn acquire(self: &Arc<Self>, demand: StepDemand) -> StepPermit {
+        loop {
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+
+            if self.try_reserve(&demand) {
+                return StepPermit {
+                    scheduler: self.clone(),
+                    demand,
+                    suspended: false,
+                };
+            }
+            released.await;
+        }
+    }
+
+    /// 资源足够时占用资源
+    ///
+    /// 单个步骤的内存需求超过整个预算时，只在没有其他步骤占用内存时运行，避免永远等待。
+    fn try_reserve(&self, demand: &StepDemand) -> bool {
+        let limits = self.limits();
+        let mut usage = self.usage.lock().unwrap();
+
+        let class_used = usage.classes.get(&demand.class).copied().unwrap_or(0);
+        let capability_used = usage.capabilities.get(&demand.capability_id).copied().unwrap_or(0);
+        let memory_fits = usage.memory_mb == 0
+            || usage.memory_mb + demand.memory_mb <= limits.memory_budget_mb;
+        if class_used >= limits.slots(demand.class).max(1)
+            || capability_used >= demand.parallelism
+            || !memory_fits
+        {
+            return false;
+        }
+
+        *usage.classes.entry(demand.class).or_default() += 1;
+        *usage.capabilities.entry(demand.capability_id.clone()).or_default() += 1;
+        usage.memory_mb += demand.memory_mb;
+        true
+    }
+
+    /// 归还步骤占用的资源类别与转换器并发 (`slots`) 及内存预算 (`memory`)
+    fn release(&self, demand: &StepDemand, slots: bool, memory: bool) {
+        let mut usage = self.usage.lock().unwrap();
+        if slots {
+            if let Some(count) = usage.classes.get_mut(&demand.class) {
+                *count = count.saturating_sub(1);
+            }
+            if let Some(count) = usage.capabilities.get_mut(&demand.capability_id) {
+                *count = count.saturating_sub(1);
+            }
+            usage.capabilities.retain(|_, count| *count > 0);
+        }
+        if memory {
+            usage.memory_mb = usage.memory_mb.saturating_sub(demand.memory_mb);
+        }
+        drop(usage);
+
+        self.released.notify_waiters();
+    }
+}
+
+/// 资源许可，离开作用域时归还资源并唤醒等待中的步骤
+pub struct StepPermit {
+    scheduler: Arc<Scheduler>,
+    demand: StepDemand,
+    /// 任务暂停中，已让出资源类别与转换器并发的占用
+    suspended: bool,
+}
+
+impl StepPermit {
+    /// 任务暂停时让出资源类别与转换器并发的占用，挂起的进程仍占用内存，因此保留内存预算
+    pub fn suspend(&mut self) {
+        if !self.suspended {
+            self.suspended = true;
+            self.scheduler.release(&self.demand, true, false);
+        }
+    }
+
+    /// 任务恢复时重新占用；进程已经恢复运行，因此不等待空闲资源，期间可能暂时超出限制
+    pub fn resume(&mut self) {
+        if self.suspended {
+            self.suspended = false;
+            let mut usage = self.scheduler.usage.lock().unwrap();
+            *usage.classes.entry(self.demand.class).or_default() += 1;
+            *usage.capabilities.entry(self.demand.capability_id.clone()).or_default() += 1;
+        }
+    }
+}
+
+impl Drop for StepPermit {
+    fn drop(&mut self) {
+        self.scheduler.release(&self.demand, !self.suspended, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn demand(id: &str, class: ResourceClass, parallelism: u32, memory_mb: u64) -> StepDemand {
+        StepDemand {
+            capability_id: id.to_string(),
+            class,
+            parallelism,
+            memory_mb,
+        }
+    }
+
+    /// 断言许可在短时间内无法获得
+    async fn assert_blocked(scheduler: &Arc<Scheduler>, demand: StepDemand) {
+        let result = tokio::time::timeout(Duration::from_millis(50), scheduler.acquire(demand)).await;
+        assert!(result.is_err(), "permit should not be granted");
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_limits() {
+        assert_eq!(
+            ResourceClass::classify(&ResourceCost { cpu: 0.2, memory: 0.1, gpu: 0.8 }),
+            ResourceClass::Gpu
+        );
+        assert_eq!(
+            ResourceClass::classify(&ResourceCost { cpu: 0.7, memory: 0.3, gpu: 0.0 }),
+            ResourceClass::Cpu
+        );
+        assert_eq!(
+            ResourceClass::classify(&ResourceCost { cpu: 0.1, memory: 0.1, gpu: 0.0 }),
+            ResourceClass::Io
+        );
+
+        let scheduler = Arc::new(Scheduler::new(SchedulerLimits {
+            max_concurrent_jobs: 4,
+            cpu_slots: 2,
+            io_slots: 8,
+            gpu_slots: 1,
+            memory_budget_mb: 1000,
+        }));
+
+        // 转换器并发度
+        let magick = scheduler.acquire(demand("magick", ResourceClass::Io, 1, 10)).await;
+        assert_blocked(&scheduler, demand("magick", ResourceClass::Io, 1, 10)).await;
+        drop(magick);
+        let _magick = scheduler.acquire(demand("magick", ResourceClass::Io, 1, 10)).await;
+
+        // 资源类别
+        let _cpu = scheduler.acquire(demand("a", ResourceClass::Cpu, 4, 10)).await;
+        let _cpu2 = scheduler.acquire(demand("a", ResourceClass::Cpu, 4, 10)).await;
+        assert_blocked(&scheduler, demand("a", ResourceClass::Cpu, 4, 10)).await;
+        let gpu = scheduler.acquire(demand("b", ResourceClass::Gpu, 4, 10)).await;
+        assert_blocked(&scheduler, demand("b", ResourceClass::Gpu, 4, 10)).await;
+        drop(gpu);
+
+        // 内存预算
+        let big = scheduler.acquire(demand("c", ResourceClass::Io, 4, 900)).await;
+        assert_blocked(&scheduler, demand("d", ResourceClass::Io, 4, 100)).await;
+        let waiting = tokio::spawn({
+            let scheduler = scheduler.clone();
+            async move { scheduler.acquire(demand("d", ResourceClass::Io, 4, 100)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        drop(big);
+        let _small = tokio::time::timeout(Duration::from_secs(1), waiting).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_suspended_permit_yields_slots() {
+        let scheduler = Arc::new(Scheduler::new(SchedulerLimits {
+            max_concurrent_jobs: 4,
+            cpu_slots: 1,
+            io_slots: 4,
+            gpu_slots: 1,
+            memory_budget_mb: 500,
+        }));
+
+        // 暂停的步骤让出 CPU 槽位，但内存预算仍然占用
+        let mut paused = scheduler.acquire(demand("a", ResourceClass::Cpu, 4, 400)).await;
+        assert_blocked(&scheduler, demand("b", ResourceClass::Cpu, 4, 50)).await;
+        paused.suspend();
+        let other = scheduler.acquire(demand("b", ResourceClass::Cpu, 4, 50)).await;
+        assert_blocked(&scheduler, demand("c", ResourceClass::Io, 4, 200)).await;
+
+        // 恢复后重新占用槽位，两者都结束后资源全部归还
+        paused.resume();
+        drop(other);
+        assert_blocked(&scheduler, demand("b", ResourceClass::Cpu, 4, 50)).await;
+        drop(paused);
+        let _all = scheduler.acquire(demand("d", ResourceClass::Cpu, 4, 500)).await;
+    }
+
+    #[tokio::test]
+    async fn test_oversized_step_runs_alone() {
+        let scheduler = Arc::new(Scheduler::new(SchedulerLimits {
+            max_concurrent_jobs: 4,
+            cpu_slots: 4,
+            io_slots: 4,
+            gpu_slots: 1,
+            memory_budget_mb: 500,
+        }));
+
+        let small = scheduler.acquire(demand("a", ResourceClass::Io, 4, 100)).await;
+        assert_blocked(&scheduler, demand("huge", ResourceClass::Io, 1, 2000)).await;
+        drop(small);
+        let _huge = scheduler.acquire(demand("huge", ResourceClass::Io, 1, 2000)).await;
+        assert_blocked(&scheduler, demand("a", ResourceClass::Io, 4, 100)).await;
+    }
+}
@@ -0,0 +1,131 @@
+use crate::error::{AppError, Result};
+use crate::types::AppSettings;
+use once_cell::sync::Lazy;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tauri::{AppHandle, Manager};
+
+/// 全局设置实例
+static SETTINGS: Lazy<Arc<SettingsStore>> = Lazy::new(|| Arc::new(SettingsStore::new()));
+
+/// 设置存储 - 内存中的当前设置，并持久化到 settings.json
+pub struct SettingsStore {
+    path: RwLock<Option<PathBuf>>,
+    settings: RwLock<AppSettings>,
+}
+
+impl SettingsStore {
+    pub fn new() -> Self {
+        Self {
+            path: RwLock::new(None),
+            settings: RwLock::new(AppSettings::default()),
+        }
+    }
+
+    /// 获取全局设置实例
+    pub fn global() -> Arc<SettingsStore> {
+        SETTINGS.clone()
+    }
+
+    /// 从文件加载设置，文件不存在时使用默认值
+    pub fn load(&self, path: PathBuf) -> Result<()> {
+        if path.exists() {
+            let content = std::fs::read_to_string(&path)?;
+            let settings: AppSettings = serde_json::from_str(&content)?;
+            *self.settings.write().unwrap() = settings;
+        }
+
+        *self.path.write().unwrap() = Some(path);
+        Ok(())
+    }
+
+    /// 获取当前设置
+    pub fn get(&self) -> AppSettings {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// 合并部分设置并保存
+    ///
+    /// 嵌套对象逐字段合并，只修改补丁中出现的字段；数组等其他值整体替换。
+    pub fn update(&self, patch: serde_json::Value) -> Result<AppSettings> {
+        let serde_json::Value::Object(patch) = patch else {
+            return Err(AppError::ValidationError("设置必须是对象".to_string()));
+        };
+
+        let mut merged = serde_json::to_value(self.get())?;
+        merge(&mut merged, serde_json::Value::Object(patch));
+        let settings: AppSettings = serde_json::from_value(merged)
+            .map_err(|e| AppError::ValidationError(format!("无效的设置: {}", e)))?;
+
+        if let Some(path) = self.path.read().unwrap().as_ref() {
+            std::fs::write(path, serde_json::to_string_pretty(&settings)?)?;
+        }
+
+        *self.settings.write().unwrap() = settings.clone();
+        Ok(settings)
+    }
+}
+
+/// 把补丁递归合并到当前值
+fn merge(current: &mut serde_json::Value, patch: serde_json::Value) {
+    match (current, patch) {
+        (serde_json::Value::Object(current), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                match current.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        current.insert(key, value);
+                    }
+                }
+            }
+        }
+        (current, patch) => *current = patch,
+    }
+}
+
+impl Default for SettingsStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 初始化设置
+pub fn initialize(app_handle: &AppHandle) -> Result<()> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    std::fs::create_dir_all(&app_data_dir)?;
+
+    SettingsStore::global().load(app_data_dir.join("settings.json"))?;
+
+    tracing::info!("Settings loaded");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ScoringPresets;
+    use serde_json::json;
+
+    #[test]
+    fn test_update_merges_nested_settings() {
+        let store = SettingsStore::new();
+        let defaults = ScoringPresets::default();
+
+        let settings = store
+            .update(json!({ "scoring_weights": { "speed": { "quality": 0.1 } }, "ocr_languages": ["eng"] }))
+            .unwrap();
+        let weights = &settings.scoring_weights;
+        assert_eq!(weights.speed.quality, 0.1);
+        assert_eq!(weights.speed.speed, defaults.speed.speed);
+        assert_eq!(weights.speed.cost, defaults.speed.cost);
+        assert_eq!(weights.quality.quality, defaults.quality.quality);
+        assert_eq!(weights.balanced.quality, defaults.balanced.quality);
+        assert_eq!(settings.ocr_languages, vec!["eng"]);
+
+        assert!(store.update(json!({ "scoring_weights": { "speed": { "quality": "high" } } })).is_err());
+        assert_eq!(store.get().scoring_weights.speed.quality, 0.1);
+    }
+}
@@ -1,7 +1,8 @@
+use super::job_store::JobStore;
 use crate::error::Result;
 use once_cell::sync::Lazy;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use tauri::{AppHandle, Manager};
 
 /// 全局存储实例
@@ -11,15 +12,17 @@ static STORAGE: Lazy<Arc<Storage>> = Lazy::new(|| {
 
 /// 存储管理器 - 工作区、缓存、数据库
 pub struct Storage {
-    workspace_dir: Option<PathBuf>,
-    cache_dir: Option<PathBuf>,
+    workspace_dir: RwLock<Option<PathBuf>>,
+    cache_dir: RwLock<Option<PathBuf>>,
+    job_store: RwLock<Option<Arc<JobStore>>>,
 }
 
 impl Storage {
     pub fn new() -> Self {
         Self {
-            workspace_dir: None,
-            cache_dir: None,
+            workspace_dir: RwLock::new(None),
+            cache_dir: RwLock::new(None),
+            job_store: RwLock::new(None),
         }
     }
 
@@ -29,19 +32,26 @@ impl Storage {
     }
 
     /// 初始化存储目录
-    pub fn init(&mut self, app_handle: &AppHandle) -> Result<()> {
+    pub fn init(&self, app_handle: &AppHandle) -> Result<()> {
         // 获取应用数据目录
         let app_data_dir = app_handle
             .path()
             .app_data_dir()
             .map_err(|e| crate::error::AppError::Internal(e.to_string()))?;
 
-        self.workspace_dir = Some(app_data_dir.join("workspace"));
-        self.cache_dir = Some(app_data_dir.join("cache"));
+        let workspace_dir = app_data_dir.join("workspace");
+        let cache_dir = app_data_dir.join("cache");
 
         // 创建目录
-        std::fs::create_dir_all(self.workspace_dir.as_ref().unwrap())?;
-        std::fs::create_dir_all(self.cache_dir.as_ref().unwrap())?;
+        std::fs::create_dir_all(&workspace_dir)?;
+        std::fs::create_dir_all(&cache_dir)?;
+
+        *self.workspace_dir.write().unwrap() = Some(workspace_dir);
+        *self.cache_dir.write().unwrap() = Some(cache_dir);
+
+        // 任务数据库
+        let job_store = JobStore::open(&app_data_dir.join("jobs.db"))?;
+        *self.job_store.write().unwrap() = Some(Arc::new(job_store));
 
         tracing::info!("Storage initialized at {:?}", app_data_dir);
 
@@ -49,13 +59,31 @@ impl Storage {
     }
 
     /// 获取工作区目录
-    pub fn workspace_dir(&self) -> Option<&PathBuf> {
-        self.workspace_dir.as_ref()
+    pub fn workspace_dir(&self) -> Option<PathBuf> {
+        self.workspace_dir.read().unwrap().clone()
     }
 
     /// 获取缓存目录
-    pub fn cache_dir(&self) -> Option<&PathBuf> {
-        self.cache_dir.as_ref()
+    pub fn cache_dir(&self) -> Option<PathBuf> {
+        self.cache_dir.read().unwrap().clone()
+    }
+
+    /// 获取任务数据库
+    pub fn job_store(&self) -> Option<Arc<JobStore>> {
+        self.job_store.read().unwrap().clone()
+    }
+
+    /// 创建任务工作区，用于存放各步骤的中间文件
+    ///
+    /// 存储尚未初始化时使用系统临时目录。
+    pub fn job_workspace(&self, job_id: &str) -> Result<PathBuf> {
+        let root = self
+            .workspace_dir()
+            .unwrap_or_else(|| std::env::temp_dir().join("f2f-workspace"));
+        let dir = root.join(job_id);
+        std::fs::create_dir_all(&dir)?;
+
+        Ok(dir)
     }
 
     /// 创建临时工作目录
@@ -87,10 +115,5 @@ impl Default for Storage {
 
 /// 初始化存储
 pub fn initialize(app_handle: &AppHandle) -> Result<()> {
-    let mut storage = Storage::new();
-    storage.init(app_handle)?;
-
-    // TODO: 初始化 SQLite 数据库
-
-    Ok(())
+    Storage::global().init(app_handle)
 }
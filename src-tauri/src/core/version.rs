@@ -0,0 +1,171 @@
+use crate::types::Requirements;
+use std::fmt;
+
+/// 工具版本号
+///
+/// 只比较前三段数字，缺省的段视为 0；`n6.1.1`、`7.1.1-15` 之类的前缀与后缀被忽略。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ToolVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl ToolVersion {
+    pub fn parse(text: &str) -> Option<Self> {
+        parse_parts(text).map(|(version, _)| version)
+    }
+
+    /// 只保留前 `parts` 段，其余置 0
+    fn truncate(self, parts: usize) -> Self {
+        Self {
+            major: self.major,
+            minor: if parts > 1 { self.minor } else { 0 },
+            patch: if parts > 2 { self.patch } else { 0 },
+        }
+    }
+}
+
+impl fmt::Display for ToolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// 解析版本号，同时返回实际给出的段数
+fn parse_parts(text: &str) -> Option<(ToolVersion, usize)> {
+    let text = text.trim();
+    let text = text
+        .strip_prefix(['v', 'V', 'n'])
+        .filter(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        .unwrap_or(text);
+    let end = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+
+    let mut parts = [0u64; 3];
+    let mut count = 0;
+    for part in text[..end].split('.').take(3) {
+        if part.is_empty() {
+            break;
+        }
+        parts[count] = part.parse().ok()?;
+        count += 1;
+    }
+
+    let [major, minor, patch] = parts;
+    (count > 0).then_some((ToolVersion { major, minor, patch }, count))
+}
+
+/// 版本范围，两端都包含
+///
+/// 上限只比较给出的段，`max_version = "2"` 允许所有 2.x 版本。
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VersionRange {
+    min: Option<(ToolVersion, usize)>,
+    max: Option<(ToolVersion, usize)>,
+}
+
+impl VersionRange {
+    pub fn new(min: Option<&str>, max: Option<&str>) -> std::result::Result<Self, String> {
+        let parse = |text: &str| parse_parts(text).ok_or_else(|| format!("无效的版本号 \"{}\"", text));
+        let range = Self {
+            min: min.map(parse).transpose()?,
+            max: max.map(parse).transpose()?,
+        };
+        if let (Some((min, _)), Some((max, parts))) = (range.min, range.max) {
+            if min.truncate(parts) > max {
+                return Err(format!("最低版本 {} 高于最高版本 {}", range.bound(range.min), range.bound(range.max)));
+            }
+        }
+        Ok(range)
+    }
+
+    /// 依赖要求中对某个工具的版本范围
+    pub fn for_tool(requires: &Requirements, tool: &str) -> std::result::Result<Self, String> {
+        Self::new(
+            requires.min_version.get(tool).map(String::as_str),
+            requires.max_version.get(tool).map(String::as_str),
+        )
+    }
+
+    pub fn is_any(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    pub fn contains(&self, version: ToolVersion) -> bool {
+        let above_min = self.min.is_none_or(|(min, _)| version >= min);
+        let below_max = self.max.is_none_or(|(max, parts)| version.truncate(parts) <= max);
+        above_min && below_max
+    }
+
+    /// 按声明时的段数输出边界，如 "3.0"
+    fn bound(&self, bound: Option<(ToolVersion, usize)>) -> String {
+        let Some((version, parts)) = bound else {
+            return String::new();
+        };
+        [version.major, version.minor, version.patch][..parts]
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+impl fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min, self.max) {
+            (Some(_), Some(_)) => write!(f, "{} ~ {}", self.bound(self.min), self.bound(self.max)),
+            (Some(_), None) => write!(f, ">= {}", self.bound(self.min)),
+            (None, Some(_)) => write!(f, "<= {}", self.bound(self.max)),
+            (None, None) => write!(f, "任意版本"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(text: &str) -> ToolVersion {
+        ToolVersion::parse(text).unwrap()
+    }
+
+    #[test]
+    fn test_parse_tool_versions() {
+        let cases = [
+            ("3.1.2", (3, 1, 2)),
+            ("7.1.1-15", (7, 1, 1)),
+            ("n6.1.1-3ubuntu5", (6, 1, 1)),
+            ("v5.3", (5, 3, 0)),
+            ("7.6.4.1", (7, 6, 4)),
+            ("3", (3, 0, 0)),
+        ];
+        for (text, (major, minor, patch)) in cases {
+            assert_eq!(version(text), ToolVersion { major, minor, patch }, "{}", text);
+        }
+        assert!(ToolVersion::parse("N-112345-gabc").is_none());
+        assert!(ToolVersion::parse("version").is_none());
+        assert_eq!(version("7.1.1-15").to_string(), "7.1.1");
+    }
+
+    #[test]
+    fn test_version_range() {
+        let range = VersionRange::new(Some("3.0"), Some("3.1")).unwrap();
+        assert!(!range.contains(version("2.19.2")));
+        assert!(range.contains(version("3.0")));
+        assert!(range.contains(version("3.1.9")));
+        assert!(!range.contains(version("3.2")));
+        assert_eq!(range.to_string(), "3.0 ~ 3.1");
+
+        let range = VersionRange::new(None, Some("2")).unwrap();
+        assert!(range.contains(version("2.19.2")));
+        assert!(!range.contains(version("3.0")));
+        assert_eq!(range.to_string(), "<= 2");
+
+        assert!(VersionRange::new(None, None).unwrap().is_any());
+        assert!(VersionRange::new(Some("x.y"), None).unwrap_err().contains("x.y"));
+        assert!(VersionRange::new(Some("3.2"), Some("3.1")).unwrap_err().contains("高于"));
+        assert!(VersionRange::new(Some("3.1.5"), Some("3.1")).is_ok());
+    }
+}
@@ -0,0 +1,294 @@
+use crate::core::converter::ConverterPlugin;
+use crate::core::manifest::ManifestLoader;
+use crate::error::{AppError, Result};
+use crate::types::CapabilityRecord;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::path::Path;
+use wasmtime::{Config, Engine, Linker, Module, ResourceLimiter, Store, Trap};
+use wasmtime_wasi::pipe::{MemoryInputPipe, MemoryOutputPipe};
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::{DirPerms, FilePerms, I32Exit, WasiCtxBuilder};
+
+/// 插件目录中的能力清单文件
+pub const MANIFEST_FILE: &str = "plugin.toml";
+/// 插件目录中的 WASM 模块文件
+pub const MODULE_FILE: &str = "plugin.wasm";
+
+/// 每秒超时折算的燃料（约等于执行的指令数）
+const FUEL_PER_SECOND: u64 = 100_000_000;
+/// 未声明 `timeout_s` 时的默认超时
+const DEFAULT_TIMEOUT_S: u32 = 300;
+/// 未声明 `max_mem_mb` 时的默认内存上限
+const DEFAULT_MAX_MEM_MB: u32 = 512;
+/// stdout/stderr 最多保留的字节数
+const OUTPUT_CAPTURE_BYTES: usize = 64 * 1024;
+
+/// 所有插件共享的 WASM 引擎，开启燃料计量
+static ENGINE: Lazy<Engine> = Lazy::new(|| {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    Engine::new(&config).expect("failed to create WASM engine")
+});
+
+/// WASM 转换器插件 - 在 WASI 沙箱中运行第三方转换器
+///
+/// 插件只能看到两个预打开目录：只读的 `/input`（仅包含输入文件）与可写的
+/// `/output`。命令行参数为 `[id, /input/<输入文件名>, /output/<输出文件名>]`，
+/// 转换参数以 JSON 写入 stdin；没有环境变量与网络访问。
+pub struct WasmConverter {
+    capability: CapabilityRecord,
+    module: Module,
+}
+
+impl WasmConverter {
+    /// 由能力声明与 WASM 模块（二进制或文本格式）创建
+    pub fn new(capability: CapabilityRecord, wasm: &[u8]) -> Result<Self> {
+        let module = Module::new(&ENGINE, wasm).map_err(|e| {
+            AppError::ValidationError(format!("插件 {} 的 WASM 模块无效: {}", capability.id, e))
+        })?;
+
+        Ok(Self { capability, module })
+    }
+
+    /// 从插件目录加载 `plugin.toml` 与 `plugin.wasm`
+    pub fn load_dir(dir: &Path) -> Result<Self> {
+        let capability = ManifestLoader::load_file(&dir.join(MANIFEST_FILE))?;
+        if capability.command.is_some() {
+            return Err(AppError::ValidationError(format!(
+                "{}: WASM 插件不能声明 command",
+                dir.display()
+            )));
+        }
+
+        let wasm = std::fs::read(dir.join(MODULE_FILE))
+            .map_err(|e| AppError::IoError(format!("{}: {}", dir.join(MODULE_FILE).display(), e)))?;
+
+        Self::new(capability, &wasm)
+    }
+
+    /// 将运行时错误映射为应用错误，资源超限优先于普通失败
+    fn map_error(&self, error: anyhow::Error, state: &HostState, stderr: &MemoryOutputPipe) -> AppError {
+        let id = &self.capability.id;
+        let stderr = String::from_utf8_lossy(&stderr.contents()).trim().to_string();
+
+        if state.memory_exceeded {
+            return AppError::ResourceLimit(format!(
+                "插件 {} 超出内存限制 {} MB",
+                id,
+                self.capability.max_mem_mb.unwrap_or(DEFAULT_MAX_MEM_MB)
+            ));
+        }
+        if error.downcast_ref::<Trap>() == Some(&Trap::OutOfFuel) {
+            return AppError::ResourceLimit(format!(
+                "插件 {} 超出执行时间限制 {} 秒",
+                id,
+                self.capability.timeout_s.unwrap_or(DEFAULT_TIMEOUT_S)
+            ));
+        }
+        if let Some(exit) = error.downcast_ref::<I32Exit>() {
+            return AppError::ToolError(format!("插件 {} 退出码 {}: {}", id, exit.0, stderr));
+        }
+
+        AppError::ToolError(format!("插件 {} 执行失败: {}: {}", id, error, stderr))
+    }
+}
+
+impl ConverterPlugin for WasmConverter {
+    fn capability(&self) -> CapabilityRecord {
+        self.capability.clone()
+    }
+
+    fn convert(&self, input: &Path, output: &Path, params: &Value) -> Result<()> {
+        let input_name = file_name(input)?;
+        let output_name = file_name(output)?;
+
+        // 只把输入文件放进插件可见的目录，避免暴露其同级文件
+        let input_dir = tempfile::tempdir()?;
+        let staged_input = input_dir.path().join(&input_name);
+        if std::fs::hard_link(input, &staged_input).is_err() {
+            std::fs::copy(input, &staged_input)?;
+        }
+        let output_dir = tempfile::tempdir()?;
+
+        let stdout = MemoryOutputPipe::new(OUTPUT_CAPTURE_BYTES);
+        let stderr = MemoryOutputPipe::new(OUTPUT_CAPTURE_BYTES);
+        let wasi = WasiCtxBuilder::new()
+            .args(&[
+                self.capability.id.clone(),
+                format!("/input/{}", input_name),
+                format!("/output/{}", output_name),
+            ])
+            .stdin(MemoryInputPipe::new(serde_json::to_vec(params)?))
+            .stdout(stdout)
+            .stderr(stderr.clone())
+            .preopened_dir(input_dir.path(), "/input", DirPerms::READ, FilePerms::READ)
+            .and_then(|b| b.preopened_dir(output_dir.path(), "/output", DirPerms::all(), FilePerms::all()))
+            .map_err(|e| AppError::Internal(e.to_string()))?
+            .build_p1();
+
+        let mut linker: Linker<HostState> = Linker::new(&ENGINE);
+        preview1::add_to_linker_sync(&mut linker, |state: &mut HostState| &mut state.wasi)
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+
+        let max_mem_mb = self.capability.max_mem_mb.unwrap_or(DEFAULT_MAX_MEM_MB);
+        let mut store = Store::new(
+            &ENGINE,
+            HostState {
+                wasi,
+                memory_limit: max_mem_mb as usize * 1024 * 1024,
+                memory_exceeded: false,
+            },
+        );
+        store.limiter(|state| state);
+        let timeout_s = self.capability.timeout_s.unwrap_or(DEFAULT_TIMEOUT_S);
+        store
+            .set_fuel(timeout_s as u64 * FUEL_PER_SECOND)
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+
+        tracing::info!("Running WASM plugin {} on {:?}", self.capability.id, input);
+
+        let result = linker
+            .instantiate(&mut store, &self.module)
+            .and_then(|instance| instance.get_typed_func::<(), ()>(&mut store, "_start"))
+            .and_then(|start| start.call(&mut store, ()));
+        if let Err(error) = result {
+            // WASI 程序调用 proc_exit(0) 视为正常结束
+            if error.downcast_ref::<I32Exit>().map(|exit| exit.0) != Some(0) {
+                return Err(self.map_error(error, store.data(), &stderr));
+            }
+        }
+
+        let produced = output_dir.path().join(&output_name);
+        if !produced.is_file() {
+            return Err(AppError::ToolError(format!(
+                "插件 {} 未生成输出文件 {}",
+                self.capability.id, output_name
+            )));
+        }
+        std::fs::copy(&produced, output)?;
+
+        Ok(())
+    }
+}
+
+/// 单次执行的宿主状态
+struct HostState {
+    wasi: WasiP1Ctx,
+    memory_limit: usize,
+    memory_exceeded: bool,
+}
+
+impl ResourceLimiter for HostState {
+    fn memory_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        if desired > self.memory_limit {
+            self.memory_exceeded = true;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        _desired: usize,
+        _maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        Ok(true)
+    }
+}
+
+fn file_name(path: &Path) -> Result<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| AppError::ValidationError(format!("无效的文件路径: {}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::*;
+
+    fn capability(timeout_s: Option<u32>, max_mem_mb: Option<u32>) -> CapabilityRecord {
+        CapabilityRecord {
+            timeout_s,
+            max_mem_mb,
+            ..test_support::capability("wasm-a-b", "text/a", "text/b")
+        }
+    }
+
+    /// 把 /input/doc.a 复制到 /output/doc.b 的插件
+    const COPY_PLUGIN: &str = r#"
+(module
+  (import "wasi_snapshot_preview1" "path_open"
+    (func $path_open (param i32 i32 i32 i32 i32 i64 i64 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 0) "doc.a")
+  (data (i32.const 32) "doc.b")
+  (func (export "_start")
+    ;; 预打开目录: 3 = /input, 4 = /output；权限位 2 = fd_read, 64 = fd_write
+    (if (call $path_open (i32.const 3) (i32.const 0) (i32.const 0) (i32.const 5) (i32.const 0)
+          (i64.const 2) (i64.const 0) (i32.const 0) (i32.const 100))
+      (then unreachable))
+    (if (call $path_open (i32.const 4) (i32.const 0) (i32.const 32) (i32.const 5) (i32.const 9)
+          (i64.const 64) (i64.const 0) (i32.const 0) (i32.const 104))
+      (then unreachable))
+    (i32.store (i32.const 200) (i32.const 1024))
+    (i32.store (i32.const 204) (i32.const 4096))
+    (if (call $fd_read (i32.load (i32.const 100)) (i32.const 200) (i32.const 1) (i32.const 208))
+      (then unreachable))
+    (i32.store (i32.const 204) (i32.load (i32.const 208)))
+    (if (call $fd_write (i32.load (i32.const 104)) (i32.const 200) (i32.const 1) (i32.const 212))
+      (then unreachable))))
+"#;
+
+    fn run(plugin: &WasmConverter) -> (tempfile::TempDir, Result<()>) {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("doc.a");
+        std::fs::write(&input, "hello wasm").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "hidden").unwrap();
+
+        let result = plugin.convert(&input, &dir.path().join("doc.b"), &Value::Null);
+        (dir, result)
+    }
+
+    #[test]
+    fn test_wasm_plugin_converts_through_preopened_dirs() {
+        let plugin = WasmConverter::new(capability(None, None), COPY_PLUGIN.as_bytes()).unwrap();
+
+        let (dir, result) = run(&plugin);
+        result.unwrap();
+        assert_eq!(std::fs::read_to_string(dir.path().join("doc.b")).unwrap(), "hello wasm");
+
+        // 输入文件的同级文件不会出现在 /input 中
+        let peek = COPY_PLUGIN
+            .replace("\"doc.a\"", "\"secret.txt\"")
+            .replace("(i32.const 0) (i32.const 5)", "(i32.const 0) (i32.const 10)");
+        let plugin = WasmConverter::new(capability(None, None), peek.as_bytes()).unwrap();
+        let (dir, result) = run(&plugin);
+        assert!(matches!(result, Err(AppError::ToolError(_))), "{:?}", result);
+        assert!(!dir.path().join("doc.b").exists());
+    }
+
+    #[test]
+    fn test_wasm_plugin_limits() {
+        let spin = r#"(module (func (export "_start") (loop br 0)))"#;
+        let plugin = WasmConverter::new(capability(Some(1), None), spin.as_bytes()).unwrap();
+        let (_dir, result) = run(&plugin);
+        assert!(matches!(result, Err(AppError::ResourceLimit(msg)) if msg.contains("时间")));
+
+        let grow = r#"(module (memory 1)
+          (func (export "_start")
+            (if (i32.lt_s (memory.grow (i32.const 100)) (i32.const 0)) (then unreachable))))"#;
+        let plugin = WasmConverter::new(capability(None, Some(1)), grow.as_bytes()).unwrap();
+        let (_dir, result) = run(&plugin);
+        assert!(matches!(result, Err(AppError::ResourceLimit(msg)) if msg.contains("内存")));
+    }
+}
@@ -13,6 +13,9 @@ pub enum AppError {
     #[error("Tool error: {0}")]
     ToolError(String),
 
+    #[error("Tool crashed: {0}")]
+    ToolCrashed(String),
+
     #[error("IO error: {0}")]
     IoError(String),
 
@@ -79,6 +82,11 @@ impl From<AppError> for ErrorResponse {
                 msg,
                 Some("请检查工具是否正确安装".to_string()),
             ),
+            AppError::ToolCrashed(msg) => (
+                ErrorKind::ToolError,
+                msg,
+                Some("工具异常退出，可稍后重试".to_string()),
+            ),
             AppError::IoError(e) => (
                 ErrorKind::IoError,
                 e.to_string(),
@@ -87,7 +95,7 @@ impl From<AppError> for ErrorResponse {
             AppError::Cancelled => (
                 ErrorKind::Canceled,
                 "操作已取消".to_string(),
-                Some("可从断点继续运行".to_string()),
+                Some("中间文件已清理，如需转换请重新提交任务".to_string()),
             ),
             AppError::DatabaseError(msg) => (
                 ErrorKind::DatabaseError,
@@ -114,4 +122,11 @@ impl From<AppError> for ErrorResponse {
     }
 }
 
+impl AppError {
+    /// 是否为可自动重试的暂时性错误 (超时、资源超限、工具崩溃)
+    pub fn is_retriable(&self) -> bool {
+        matches!(self, AppError::ResourceLimit(_) | AppError::ToolCrashed(_))
+    }
+}
+
 pub type Result<T> = std::result::Result<T, AppError>;
@@ -7,6 +7,7 @@ mod core;
 mod error;
 
 // use tauri::Manager;
+use std::sync::Arc;
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 fn main() {
@@ -25,16 +26,42 @@ fn main() {
             // Initialize core systems
             let app_handle = app.handle().clone();
 
+            // Load user settings
+            if let Err(e) = core::settings::initialize(&app_handle) {
+                tracing::error!("Failed to load settings: {}", e);
+            }
+
             // Initialize capability registry
             if let Err(e) = core::registry::initialize(&app_handle) {
                 tracing::error!("Failed to initialize capability registry: {}", e);
             }
 
+            // Load installed plugins
+            if let Err(e) = core::plugin::initialize(&app_handle) {
+                tracing::error!("Failed to load plugins: {}", e);
+            }
+
             // Initialize database
             if let Err(e) = core::storage::initialize(&app_handle) {
                 tracing::error!("Failed to initialize storage: {}", e);
             }
 
+            // Start the job worker
+            let pipeline = core::Pipeline::global();
+            pipeline.set_event_sink(Arc::new(core::events::TauriEventSink::new(app_handle.clone())));
+            let settings = core::SettingsStore::global().get();
+            pipeline.set_limits(core::scheduler::SchedulerLimits::from_settings(&settings));
+            let recovery = settings.running_job_recovery;
+            tauri::async_runtime::spawn(async move {
+                // 恢复上次运行留下的任务后再开始处理队列
+                if let Some(store) = core::Storage::global().job_store() {
+                    if let Err(e) = pipeline.attach_store(store, recovery).await {
+                        tracing::error!("Failed to restore jobs: {}", e);
+                    }
+                }
+                pipeline.run_worker().await
+            });
+
             tracing::info!("F2F Converter initialized successfully");
             Ok(())
         })
@@ -46,11 +73,17 @@ fn main() {
             commands::list_jobs,
             commands::get_job,
             commands::control_job,
+            commands::set_job_priority,
             commands::get_artifacts,
             commands::get_logs,
             commands::get_settings,
             commands::set_settings,
             commands::run_health_check,
+            commands::install_plugin,
+            commands::list_plugins,
+            commands::enable_plugin,
+            commands::disable_plugin,
+            commands::uninstall_plugin,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
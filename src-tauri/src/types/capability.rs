@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// 能力注册记录
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,6 +9,7 @@ pub struct CapabilityRecord {
     pub version: String,
     pub license: String,
     pub vendor: String,
+    /// 主工具的可执行文件路径，未声明时刷新注册表后填入探测到的路径
     pub binary_path: Option<String>,
     pub inputs: Vec<FormatSpec>,
     pub outputs: Vec<FormatSpec>,
@@ -22,6 +23,101 @@ pub struct CapabilityRecord {
     pub max_mem_mb: Option<u32>,
     pub parallelism: u32,
     pub streaming: bool,
+    /// 调用外部工具的命令模板
+    #[serde(default)]
+    pub command: Option<CommandTemplate>,
+    /// 覆盖全局设置的重试策略
+    #[serde(default)]
+    pub retry: Option<RetryPolicy>,
+}
+
+impl CapabilityRecord {
+    /// 主工具：命令模板调用的工具，没有命令模板时为第一个依赖工具
+    pub fn primary_tool(&self) -> Option<&String> {
+        match &self.command {
+            Some(command) => Some(&command.tool),
+            None => self.requires.tools.first(),
+        }
+    }
+}
+
+/// 重试策略 - 可重试错误的自动重试次数与指数退避
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// 首次失败后的最多重试次数，0 表示不自动重试
+    pub max_retries: u32,
+    /// 第一次重试前的等待时间
+    pub initial_backoff_ms: u64,
+    /// 等待时间上限
+    pub max_backoff_ms: u64,
+    /// 每次重试等待时间的增长倍数
+    pub multiplier: f32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 30_000,
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 第 `retry` 次重试 (从 1 开始) 前的等待时间
+    pub fn backoff(&self, retry: u32) -> std::time::Duration {
+        let factor = self.multiplier.max(1.0).powi(retry.saturating_sub(1) as i32);
+        let delay = (self.initial_backoff_ms as f64 * factor as f64).min(self.max_backoff_ms as f64);
+        std::time::Duration::from_millis(delay as u64)
+    }
+}
+
+/// 命令模板 - 每个参数模板渲染为恰好一个进程参数，不经过 shell
+///
+/// 参数中可使用的占位符:
+/// - `{input}` / `{output}`: 输入与输出文件路径
+/// - `{opt:name}`: `custom_params` 中的参数值，按 `options` 中的声明做类型检查
+/// - 以 `{if:name}` 开头的参数只在参数 `name` 有值 (布尔参数为 true) 时保留
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandTemplate {
+    /// 使用的工具，必须出现在 `requires.tools` 中
+    pub tool: String,
+    pub args: Vec<String>,
+    /// 模板引用的参数声明
+    #[serde(default)]
+    pub options: BTreeMap<String, OptionSpec>,
+}
+
+/// 命令模板参数声明
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionSpec {
+    #[serde(rename = "type")]
+    pub kind: OptionType,
+    /// 未在 `custom_params` 中提供时使用的值
+    #[serde(default)]
+    pub default: Option<serde_json::Value>,
+    /// 数值参数的取值范围
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// 枚举参数的可选值
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+/// 命令模板参数类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OptionType {
+    Bool,
+    Integer,
+    Number,
+    String,
+    Enum,
 }
 
 /// 格式规格
@@ -40,15 +136,21 @@ pub struct ResourceCost {
 }
 
 /// 依赖要求
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Requirements {
     pub tools: Vec<String>,
     pub lang_packs: Vec<String>,
     pub fonts: Vec<String>,
+    /// 工具的最低版本 (包含)，键为 `tools` 中的工具 ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub min_version: BTreeMap<String, String>,
+    /// 工具的最高版本 (包含)，只比较给出的段，如 "2" 允许所有 2.x
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub max_version: BTreeMap<String, String>,
 }
 
 /// 保留特性
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PreserveFeatures {
     pub styles: PreserveLevel,
     pub links: PreserveLevel,
@@ -60,8 +162,54 @@ pub struct PreserveFeatures {
     pub formulas: PreserveLevel,
 }
 
+/// 默认所有特性完整保留，即逐项取最弱级别时的初始值
+impl Default for PreserveFeatures {
+    fn default() -> Self {
+        Self {
+            styles: PreserveLevel::True,
+            links: PreserveLevel::True,
+            footnotes: PreserveLevel::True,
+            headers: PreserveLevel::True,
+            lists: PreserveLevel::True,
+            tables: PreserveLevel::True,
+            images: PreserveLevel::True,
+            formulas: PreserveLevel::True,
+        }
+    }
+}
+
+impl PreserveFeatures {
+    /// 按特性名列出各项保留级别
+    pub fn levels(&self) -> [(&'static str, PreserveLevel); 8] {
+        [
+            ("styles", self.styles),
+            ("links", self.links),
+            ("footnotes", self.footnotes),
+            ("headers", self.headers),
+            ("lists", self.lists),
+            ("tables", self.tables),
+            ("images", self.images),
+            ("formulas", self.formulas),
+        ]
+    }
+
+    /// 逐项取两者中较弱的级别（路径的保留能力取决于最弱的一步）
+    pub fn weakest(&self, other: &PreserveFeatures) -> PreserveFeatures {
+        PreserveFeatures {
+            styles: self.styles.min(other.styles),
+            links: self.links.min(other.links),
+            footnotes: self.footnotes.min(other.footnotes),
+            headers: self.headers.min(other.headers),
+            lists: self.lists.min(other.lists),
+            tables: self.tables.min(other.tables),
+            images: self.images.min(other.images),
+            formulas: self.formulas.min(other.formulas),
+        }
+    }
+}
+
 /// 保留级别
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum PreserveLevel {
     True,
@@ -69,6 +217,85 @@ pub enum PreserveLevel {
     Partial,
 }
 
+impl PreserveLevel {
+    /// 级别强弱: False < Partial < True
+    fn rank(self) -> u8 {
+        match self {
+            PreserveLevel::False => 0,
+            PreserveLevel::Partial => 1,
+            PreserveLevel::True => 2,
+        }
+    }
+
+    /// 取两者中较弱的级别
+    pub fn min(self, other: PreserveLevel) -> PreserveLevel {
+        if other.rank() < self.rank() {
+            other
+        } else {
+            self
+        }
+    }
+
+    /// 是否达到要求的最低级别
+    pub fn satisfies(self, required: PreserveLevel) -> bool {
+        self.rank() >= required.rank()
+    }
+}
+
+/// 保留约束 - 各特性必须达到的最低保留级别，未指定的特性不做要求
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreserveConstraints {
+    pub styles: Option<PreserveLevel>,
+    pub links: Option<PreserveLevel>,
+    pub footnotes: Option<PreserveLevel>,
+    pub headers: Option<PreserveLevel>,
+    pub lists: Option<PreserveLevel>,
+    pub tables: Option<PreserveLevel>,
+    pub images: Option<PreserveLevel>,
+    pub formulas: Option<PreserveLevel>,
+}
+
+impl PreserveConstraints {
+    /// 列出不满足约束的特性
+    pub fn violations(&self, features: &PreserveFeatures) -> Vec<&'static str> {
+        let required = [
+            self.styles,
+            self.links,
+            self.footnotes,
+            self.headers,
+            self.lists,
+            self.tables,
+            self.images,
+            self.formulas,
+        ];
+
+        features
+            .levels()
+            .iter()
+            .zip(required)
+            .filter_map(|((name, level), required)| match required {
+                Some(required) if !level.satisfies(required) => Some(*name),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// 是否满足全部约束
+    pub fn allows(&self, features: &PreserveFeatures) -> bool {
+        self.violations(features).is_empty()
+    }
+}
+
+/// 特性损失 - 路径中某特性未能完整保留
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureLoss {
+    pub feature: String,
+    pub level: PreserveLevel,
+    /// 首个将该特性降到此级别的转换器
+    pub converter_id: String,
+}
+
 /// 转换路径
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConversionPath {
@@ -76,6 +303,17 @@ pub struct ConversionPath {
     pub total_quality: f32,
     pub estimated_time_ms: u64,
     pub risks: Vec<String>,
+    /// 按质量优先级加权后的综合得分
+    #[serde(default)]
+    pub score: f32,
+    /// 路径整体的保留能力（各步最弱者）
+    #[serde(default)]
+    pub preserve: PreserveFeatures,
+    #[serde(default)]
+    pub losses: Vec<FeatureLoss>,
+    /// 各步尚未满足的依赖，为空表示路径可直接执行
+    #[serde(default)]
+    pub missing_requirements: Vec<MissingRequirement>,
 }
 
 /// 转换步骤
@@ -95,7 +333,78 @@ pub struct ConversionTarget {
     pub paths: Vec<ConversionPath>,
     pub recommended: bool,
     pub available: bool,
-    pub missing_requirements: Vec<String>,
+    pub missing_requirements: Vec<MissingRequirement>,
+}
+
+/// 未满足的依赖，由前端按类型与原因本地化展示
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MissingRequirement {
+    pub kind: RequirementKind,
+    /// 工具 ID、语言包或字体名
+    pub name: String,
+    pub reason: MissingReason,
+    /// 要求的版本范围，如 ">= 3.0"
+    #[serde(default)]
+    pub required: Option<String>,
+    /// 已安装的版本
+    #[serde(default)]
+    pub installed: Option<String>,
+}
+
+impl MissingRequirement {
+    /// 未安装的依赖
+    pub fn not_installed(kind: RequirementKind, name: &str) -> Self {
+        Self {
+            kind,
+            name: name.to_string(),
+            reason: MissingReason::NotInstalled,
+            required: None,
+            installed: None,
+        }
+    }
+}
+
+impl std::fmt::Display for MissingRequirement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            RequirementKind::Tool => write!(f, "{}", self.name)?,
+            RequirementKind::LangPack => write!(f, "语言包 {}", self.name)?,
+            RequirementKind::Font => write!(f, "字体 {}", self.name)?,
+        }
+        if let Some(required) = &self.required {
+            write!(f, " {}", required)?;
+        }
+        match self.reason {
+            MissingReason::NotInstalled => Ok(()),
+            MissingReason::VersionMismatch => {
+                write!(f, " (已安装 {})", self.installed.as_deref().unwrap_or_default())
+            }
+            MissingReason::UnknownVersion => write!(f, " (无法识别已安装版本)"),
+            MissingReason::InvalidRequirement => write!(f, " (版本要求无效)"),
+        }
+    }
+}
+
+/// 依赖的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequirementKind {
+    Tool,
+    LangPack,
+    Font,
+}
+
+/// 依赖未满足的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingReason {
+    NotInstalled,
+    /// 已安装的版本不在要求范围内
+    VersionMismatch,
+    /// 有版本要求，但无法识别已安装的版本
+    UnknownVersion,
+    /// 能力声明的版本要求无法解析
+    InvalidRequirement,
 }
 
 /// 工具健康状态
@@ -112,5 +421,21 @@ pub struct ToolHealth {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HealthReport {
     pub tools: HashMap<String, ToolHealth>,
+    /// 已安装的 OCR 语言包
+    pub lang_packs: Vec<String>,
+    /// 系统字体族，无法枚举时为 None
+    pub fonts: Option<Vec<String>>,
+    /// 外部工具的隔离级别
+    pub isolation: IsolationLevel,
     pub timestamp: i64,
 }
+
+/// 外部工具的隔离级别
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IsolationLevel {
+    /// Landlock 限制文件系统访问，并使用私有工作目录与精简的环境变量
+    Landlock,
+    /// 仅使用私有工作目录与精简的环境变量
+    Restricted,
+}
@@ -1,3 +1,4 @@
+use super::capability::PreserveConstraints;
 use serde::{Deserialize, Serialize};
 
 /// 文件探测结果
@@ -13,6 +14,7 @@ pub struct FileDetection {
 
 /// 转换选项
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ConversionOptions {
     pub quality_priority: QualityPriority,
     pub max_file_size_mb: Option<u32>,
@@ -20,6 +22,11 @@ pub struct ConversionOptions {
     pub ocr_languages: Vec<String>,
     pub preserve_metadata: bool,
     pub custom_params: serde_json::Value,
+    /// 必须满足的特性保留约束
+    #[serde(default)]
+    pub preserve: PreserveConstraints,
+    /// 产物输出目录，未指定时与输入文件同目录
+    pub output_dir: Option<String>,
 }
 
 impl Default for ConversionOptions {
@@ -31,12 +38,14 @@ impl Default for ConversionOptions {
             ocr_languages: vec!["eng".to_string(), "chi_sim".to_string()],
             preserve_metadata: true,
             custom_params: serde_json::Value::Object(Default::default()),
+            preserve: PreserveConstraints::default(),
+            output_dir: None,
         }
     }
 }
 
 /// 质量优先级
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum QualityPriority {
     Quality,
@@ -44,6 +53,67 @@ pub enum QualityPriority {
     Speed,
 }
 
+/// 路径评分权重: score = w_quality * quality + w_speed * speed - w_cost * cost
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ScoringWeights {
+    pub quality: f32,
+    pub speed: f32,
+    pub cost: f32,
+}
+
+impl ScoringWeights {
+    /// 各质量优先级的默认权重
+    pub fn preset(priority: QualityPriority) -> Self {
+        match priority {
+            QualityPriority::Quality => Self {
+                quality: 0.8,
+                speed: 0.1,
+                cost: 0.1,
+            },
+            QualityPriority::Balanced => Self {
+                quality: 0.5,
+                speed: 0.3,
+                cost: 0.2,
+            },
+            QualityPriority::Speed => Self {
+                quality: 0.2,
+                speed: 0.6,
+                cost: 0.2,
+            },
+        }
+    }
+}
+
+/// 各质量优先级对应的评分权重，可在设置中覆盖
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringPresets {
+    pub quality: ScoringWeights,
+    pub balanced: ScoringWeights,
+    pub speed: ScoringWeights,
+}
+
+impl ScoringPresets {
+    /// 获取给定优先级的权重
+    pub fn weights_for(&self, priority: QualityPriority) -> ScoringWeights {
+        match priority {
+            QualityPriority::Quality => self.quality,
+            QualityPriority::Balanced => self.balanced,
+            QualityPriority::Speed => self.speed,
+        }
+    }
+}
+
+impl Default for ScoringPresets {
+    fn default() -> Self {
+        Self {
+            quality: ScoringWeights::preset(QualityPriority::Quality),
+            balanced: ScoringWeights::preset(QualityPriority::Balanced),
+            speed: ScoringWeights::preset(QualityPriority::Speed),
+        }
+    }
+}
+
 /// 食谱定义
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Recipe {
@@ -7,11 +7,19 @@ use uuid::Uuid;
 pub enum JobState {
     Queued,
     Running,
+    Paused,
     Succeeded,
     Failed,
     Canceled,
 }
 
+impl JobState {
+    /// 是否为终态，终态任务只能通过重试 (Failed -> Queued) 重新执行
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, JobState::Succeeded | JobState::Failed | JobState::Canceled)
+    }
+}
+
 /// 任务记录
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Job {
@@ -28,6 +36,39 @@ pub struct Job {
     pub started_at: Option<i64>,
     pub finished_at: Option<i64>,
     pub error: Option<String>,
+    /// 各条转换路线的执行记录，按尝试顺序排列
+    #[serde(default)]
+    pub attempts: Vec<PathAttempt>,
+    /// 优先级，数值越大越先执行
+    #[serde(default)]
+    pub priority: i32,
+    /// 同一优先级内的排队顺序，数值越小越先执行，提交时由流水线分配
+    #[serde(default)]
+    pub queue_position: i64,
+}
+
+/// 一次转换路线的执行记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathAttempt {
+    /// 路线在候选路径中的序号，从 1 开始
+    pub route: u32,
+    /// 路线依次使用的转换器
+    pub converters: Vec<String>,
+    pub outcome: AttemptOutcome,
+    pub error: Option<String>,
+    pub started_at: i64,
+    pub finished_at: i64,
+}
+
+/// 路线执行结果
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AttemptOutcome {
+    Succeeded,
+    Failed,
+    /// 输出未通过质量检查
+    Rejected,
+    Canceled,
 }
 
 impl Job {
@@ -37,6 +78,7 @@ impl Job {
         recipe_id: Option<String>,
         options: serde_json::Value,
     ) -> Self {
+        let created_at = chrono::Utc::now().timestamp_millis();
         Self {
             id: Uuid::new_v4().to_string(),
             state: JobState::Queued,
@@ -47,22 +89,39 @@ impl Job {
             progress: 0.0,
             stage: None,
             eta_ms: None,
-            created_at: chrono::Utc::now().timestamp_millis(),
+            created_at,
             started_at: None,
             finished_at: None,
             error: None,
+            attempts: Vec::new(),
+            priority: 0,
+            queue_position: 0,
         }
     }
 }
 
+/// 转换步骤状态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StepState {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+}
+
 /// 任务控制动作
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "snake_case")]
 pub enum JobAction {
     Pause,
     Resume,
     Cancel,
     Retry,
+    /// 移到队列最前
+    MoveToTop,
+    /// 移到队列末尾
+    Defer,
 }
 
 /// 任务进度事件
@@ -1,7 +1,13 @@
 pub mod capability;
 pub mod job;
 pub mod conversion;
+pub mod settings;
+pub mod plugin;
+#[cfg(test)]
+pub mod test_support;
 
 pub use capability::*;
 pub use job::*;
 pub use conversion::*;
+pub use settings::*;
+pub use plugin::*;
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+
+/// 已安装插件信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginInfo {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub vendor: String,
+    pub license: String,
+    /// 安装时是否通过受信任密钥的签名验证
+    pub signed: bool,
+    pub enabled: bool,
+    pub installed_at: i64,
+}
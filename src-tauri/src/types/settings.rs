@@ -0,0 +1,57 @@
+use super::capability::RetryPolicy;
+use super::conversion::{QualityPriority, ScoringPresets};
+use serde::{Deserialize, Serialize};
+
+/// 应用设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub theme: String,
+    pub language: String,
+    pub quality_priority: QualityPriority,
+    pub max_concurrent_jobs: u32,
+    /// 同时运行的转换步骤可使用的内存总量
+    pub memory_budget_mb: u32,
+    pub cache_size_mb: u32,
+    pub ocr_enabled: bool,
+    pub ocr_languages: Vec<String>,
+    pub scoring_weights: ScoringPresets,
+    /// 受信任的插件签名公钥 (ed25519，十六进制)
+    pub trusted_plugin_keys: Vec<String>,
+    /// 是否允许安装未签名插件
+    pub allow_unsigned_plugins: bool,
+    /// 应用重启时对仍处于运行中的任务的处理方式
+    pub running_job_recovery: RunningJobRecovery,
+    /// 默认重试策略，转换器可在清单中覆盖
+    pub retry: RetryPolicy,
+}
+
+/// 重启恢复策略
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RunningJobRecovery {
+    /// 标记为失败，保留工作区以便重试
+    Fail,
+    /// 重新排队执行
+    Requeue,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            theme: "system".to_string(),
+            language: "zh-CN".to_string(),
+            quality_priority: QualityPriority::Balanced,
+            max_concurrent_jobs: 4,
+            memory_budget_mb: 4096,
+            cache_size_mb: 1024,
+            ocr_enabled: false,
+            ocr_languages: vec!["eng".to_string(), "chi_sim".to_string()],
+            scoring_weights: ScoringPresets::default(),
+            trusted_plugin_keys: vec![],
+            allow_unsigned_plugins: false,
+            running_job_recovery: RunningJobRecovery::Fail,
+            retry: RetryPolicy::default(),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! 测试用的能力记录与转换路径
+
+use super::*;
+
+/// 所有特性都完整保留
+pub fn preserve_all() -> PreserveFeatures {
+    PreserveFeatures {
+        styles: PreserveLevel::True,
+        links: PreserveLevel::True,
+        footnotes: PreserveLevel::True,
+        headers: PreserveLevel::True,
+        lists: PreserveLevel::True,
+        tables: PreserveLevel::True,
+        images: PreserveLevel::True,
+        formulas: PreserveLevel::True,
+    }
+}
+
+/// 从 `from` 转换到 `to` 的能力，扩展名取 MIME 的子类型，如 `text/a` 对应 `a`
+pub fn capability(id: &str, from: &str, to: &str) -> CapabilityRecord {
+    let spec = |mime: &str| FormatSpec {
+        mime: mime.to_string(),
+        extensions: vec![mime.rsplit('/').next().unwrap().to_string()],
+    };
+    CapabilityRecord {
+        id: id.to_string(),
+        name: id.to_string(),
+        version: "1.0.0".to_string(),
+        license: "MIT".to_string(),
+        vendor: "test".to_string(),
+        binary_path: None,
+        inputs: vec![spec(from)],
+        outputs: vec![spec(to)],
+        quality: 0.9,
+        speed: 0.9,
+        cost: ResourceCost { cpu: 0.1, memory: 0.1, gpu: 0.0 },
+        requires: Requirements::default(),
+        preserve: preserve_all(),
+        risks: vec![],
+        timeout_s: None,
+        max_mem_mb: None,
+        parallelism: 1,
+        streaming: false,
+        command: None,
+        retry: None,
+    }
+}
+
+/// 由指定转换器执行的一步转换
+pub fn step(converter_id: &str, from: &str, to: &str) -> ConversionStep {
+    ConversionStep {
+        converter_id: converter_id.to_string(),
+        from_format: from.to_string(),
+        to_format: to.to_string(),
+        quality: 0.9,
+    }
+}
+
+/// 依次执行 `steps` 的转换路径，不带风险与依赖缺失
+pub fn conversion_path(steps: Vec<ConversionStep>) -> ConversionPath {
+    ConversionPath {
+        total_quality: steps.iter().map(|step| step.quality).product(),
+        steps,
+        estimated_time_ms: 0,
+        risks: vec![],
+        score: 0.0,
+        preserve: preserve_all(),
+        losses: vec![],
+        missing_requirements: vec![],
+    }
+}
@@ -2,6 +2,7 @@
  * Tauri IPC 封装 - 类型安全的命令调用
  */
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import type {
   CapabilityRecord,
   ConversionTarget,
@@ -11,6 +12,12 @@ import type {
   LogEntry,
   HealthReport,
   ErrorResponse,
+  ConversionOptions,
+  AppSettings,
+  PluginInfo,
+  JobProgress,
+  JobStateChanged,
+  ArtifactReady,
 } from "@/types/ipc";
 
 /**
@@ -23,8 +30,11 @@ export async function detectCapabilities(): Promise<CapabilityRecord[]> {
 /**
  * 规划目标格式
  */
-export async function planTargets(filePath: string): Promise<ConversionTarget[]> {
-  return invoke<ConversionTarget[]>("plan_targets", { filePath });
+export async function planTargets(
+  filePath: string,
+  options?: ConversionOptions,
+): Promise<ConversionTarget[]> {
+  return invoke<ConversionTarget[]>("plan_targets", { filePath, options });
 }
 
 /**
@@ -65,6 +75,13 @@ export async function controlJob(jobId: string, action: JobAction): Promise<void
   return invoke<void>("control_job", { jobId, action });
 }
 
+/**
+ * 调整任务优先级
+ */
+export async function setJobPriority(jobId: string, priority: number): Promise<void> {
+  return invoke<void>("set_job_priority", { jobId, priority });
+}
+
 /**
  * 获取产物列表
  */
@@ -82,14 +99,14 @@ export async function getLogs(jobId: string, cursor?: number): Promise<LogEntry[
 /**
  * 获取设置
  */
-export async function getSettings(): Promise<Record<string, unknown>> {
-  return invoke<Record<string, unknown>>("get_settings");
+export async function getSettings(): Promise<AppSettings> {
+  return invoke<AppSettings>("get_settings");
 }
 
 /**
  * 设置配置
  */
-export async function setSettings(settings: Record<string, unknown>): Promise<void> {
+export async function setSettings(settings: Partial<AppSettings>): Promise<void> {
   return invoke<void>("set_settings", { settings });
 }
 
@@ -100,6 +117,64 @@ export async function runHealthCheck(): Promise<HealthReport> {
   return invoke<HealthReport>("run_health_check");
 }
 
+/**
+ * 从本地插件包安装插件
+ */
+export async function installPlugin(packagePath: string): Promise<PluginInfo> {
+  return invoke<PluginInfo>("install_plugin", { packagePath });
+}
+
+/**
+ * 列出已安装插件
+ */
+export async function listPlugins(): Promise<PluginInfo[]> {
+  return invoke<PluginInfo[]>("list_plugins");
+}
+
+/**
+ * 启用插件
+ */
+export async function enablePlugin(pluginId: string): Promise<PluginInfo> {
+  return invoke<PluginInfo>("enable_plugin", { pluginId });
+}
+
+/**
+ * 禁用插件
+ */
+export async function disablePlugin(pluginId: string): Promise<PluginInfo> {
+  return invoke<PluginInfo>("disable_plugin", { pluginId });
+}
+
+/**
+ * 卸载插件
+ */
+export async function uninstallPlugin(pluginId: string): Promise<void> {
+  return invoke<void>("uninstall_plugin", { pluginId });
+}
+
+/**
+ * 监听任务进度
+ */
+export async function onJobProgress(handler: (event: JobProgress) => void): Promise<UnlistenFn> {
+  return listen<JobProgress>("job_progress", (event) => handler(event.payload));
+}
+
+/**
+ * 监听任务状态变更
+ */
+export async function onJobStateChanged(
+  handler: (event: JobStateChanged) => void,
+): Promise<UnlistenFn> {
+  return listen<JobStateChanged>("job_state_changed", (event) => handler(event.payload));
+}
+
+/**
+ * 监听产物就绪
+ */
+export async function onArtifactReady(handler: (event: ArtifactReady) => void): Promise<UnlistenFn> {
+  return listen<ArtifactReady>("artifact_ready", (event) => handler(event.payload));
+}
+
 /**
  * 错误处理辅助函数
  */
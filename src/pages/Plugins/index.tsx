@@ -1,10 +1,150 @@
+import { useEffect, useState } from "react";
+import { Loader2, ShieldAlert, ShieldCheck, Trash2 } from "lucide-react";
+import {
+  disablePlugin,
+  enablePlugin,
+  installPlugin,
+  listPlugins,
+  uninstallPlugin,
+} from "@/lib/tauri";
+import type { PluginInfo } from "@/types/ipc";
+
 export function Plugins() {
+  const [plugins, setPlugins] = useState<PluginInfo[]>([]);
+  const [packagePath, setPackagePath] = useState("");
+  const [isLoading, setIsLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const refresh = async () => {
+    try {
+      setPlugins(await listPlugins());
+    } catch (err) {
+      setError(`获取插件列表失败: ${err}`);
+    }
+  };
+
+  useEffect(() => {
+    refresh();
+  }, []);
+
+  // 执行插件操作后刷新列表
+  const run = async (action: () => Promise<unknown>, failure: string) => {
+    try {
+      setIsLoading(true);
+      setError(null);
+      await action();
+      await refresh();
+    } catch (err) {
+      setError(`${failure}: ${err}`);
+      console.error(failure, err);
+    } finally {
+      setIsLoading(false);
+    }
+  };
+
+  const handleInstall = () => {
+    if (!packagePath.trim()) {
+      setError("请输入插件包路径");
+      return;
+    }
+    run(async () => {
+      await installPlugin(packagePath.trim());
+      setPackagePath("");
+    }, "安装插件失败");
+  };
+
   return (
-    <div className="p-8">
-      <h2 className="text-2xl font-bold mb-4">插件管理</h2>
-      <p className="text-muted-foreground">
-        安装和管理转换插件...
-      </p>
+    <div className="p-8 max-w-4xl mx-auto">
+      <div className="mb-8">
+        <h2 className="text-3xl font-bold mb-2">插件管理</h2>
+        <p className="text-muted-foreground">
+          安装和管理转换插件，未签名插件需在设置中允许后才能安装
+        </p>
+      </div>
+
+      <div className="space-y-6">
+        {/* 安装插件 */}
+        <section className="flex gap-3">
+          <input
+            value={packagePath}
+            onChange={(e) => setPackagePath(e.target.value)}
+            placeholder="插件包路径 (.f2fplugin)"
+            className="flex-1 px-3 py-2 rounded-lg border border-border bg-background"
+          />
+          <button
+            onClick={handleInstall}
+            disabled={isLoading}
+            className="
+              px-4 py-2 rounded-lg bg-primary text-primary-foreground font-medium
+              flex items-center gap-2 hover:bg-primary/90 transition-colors
+              disabled:opacity-50 disabled:cursor-not-allowed
+            "
+          >
+            {isLoading && <Loader2 className="h-4 w-4 animate-spin" />}
+            安装
+          </button>
+        </section>
+
+        {/* 错误提示 */}
+        {error && (
+          <div className="p-4 rounded-lg bg-destructive/10 border border-destructive/20">
+            <p className="text-sm text-destructive">{error}</p>
+          </div>
+        )}
+
+        {/* 已安装插件 */}
+        <section className="space-y-3">
+          {plugins.length === 0 && (
+            <p className="text-sm text-muted-foreground">尚未安装插件</p>
+          )}
+          {plugins.map((plugin) => (
+            <div
+              key={plugin.id}
+              className={`
+                p-4 rounded-lg border-2 border-border flex items-center justify-between
+                ${plugin.enabled ? "" : "opacity-60"}
+              `}
+            >
+              <div>
+                <div className="flex items-center gap-2">
+                  <p className="font-medium">{plugin.name}</p>
+                  <span className="text-xs text-muted-foreground">v{plugin.version}</span>
+                  {plugin.signed ? (
+                    <ShieldCheck className="h-4 w-4 text-primary" />
+                  ) : (
+                    <ShieldAlert className="h-4 w-4 text-destructive" />
+                  )}
+                </div>
+                <p className="text-xs text-muted-foreground mt-1">
+                  {plugin.id} · {plugin.vendor} · {plugin.license}
+                </p>
+              </div>
+              <div className="flex items-center gap-2">
+                <button
+                  onClick={() =>
+                    run(
+                      () => (plugin.enabled ? disablePlugin(plugin.id) : enablePlugin(plugin.id)),
+                      plugin.enabled ? "禁用插件失败" : "启用插件失败",
+                    )
+                  }
+                  disabled={isLoading}
+                  className="px-3 py-1 rounded border border-border text-sm hover:border-primary/50"
+                >
+                  {plugin.enabled ? "禁用" : "启用"}
+                </button>
+                <button
+                  onClick={() => run(() => uninstallPlugin(plugin.id), "卸载插件失败")}
+                  disabled={isLoading}
+                  className="p-1 rounded text-destructive hover:bg-destructive/10"
+                  title="卸载"
+                >
+                  <Trash2 className="h-4 w-4" />
+                </button>
+              </div>
+            </div>
+          ))}
+        </section>
+      </div>
     </div>
   );
 }
@@ -1,10 +1,245 @@
+import { useEffect, useState } from "react";
+import { ArrowDownToLine, ArrowUpToLine, ChevronDown, ChevronUp, Pause, Play, RotateCcw, X } from "lucide-react";
+import { controlJob, listJobs, onJobProgress, onJobStateChanged, setJobPriority } from "@/lib/tauri";
+import type { AttemptOutcome, Job, JobAction, JobState } from "@/types/ipc";
+
+const STATE_LABELS: Record<JobState, string> = {
+  queued: "排队中",
+  running: "运行中",
+  paused: "已暂停",
+  succeeded: "已完成",
+  failed: "失败",
+  canceled: "已取消",
+};
+
+const STATE_STYLES: Record<JobState, string> = {
+  queued: "bg-muted text-muted-foreground",
+  running: "bg-primary/10 text-primary",
+  paused: "bg-yellow-500/10 text-yellow-600",
+  succeeded: "bg-green-500/10 text-green-600",
+  failed: "bg-destructive/10 text-destructive",
+  canceled: "bg-muted text-muted-foreground",
+};
+
+const OUTCOME_LABELS: Record<AttemptOutcome, string> = {
+  succeeded: "成功",
+  failed: "失败",
+  rejected: "未通过质量检查",
+  canceled: "已取消",
+};
+
+function fileName(path: string) {
+  return path.split(/[\\/]/).pop() ?? path;
+}
+
+/** 排队中的任务按执行顺序排在前面，其余任务按提交时间倒序 */
+function displayOrder(jobs: Job[]) {
+  const queued = jobs
+    .filter((job) => job.state === "queued")
+    .sort((a, b) => b.priority - a.priority || a.queue_position - b.queue_position);
+  const others = jobs.filter((job) => job.state !== "queued").reverse();
+  return [...queued, ...others];
+}
+
 export function Queue() {
+  const [jobs, setJobs] = useState<Job[]>([]);
+  const [error, setError] = useState<string | null>(null);
+
+  const refresh = async () => {
+    try {
+      setJobs(await listJobs());
+    } catch (err) {
+      setError(`获取任务列表失败: ${err}`);
+    }
+  };
+
+  useEffect(() => {
+    refresh();
+
+    // 进度事件直接更新对应任务，状态变更时重新拉取完整记录
+    const unlisteners = [
+      onJobProgress((event) =>
+        setJobs((current) =>
+          current.map((job) =>
+            job.id === event.job_id
+              ? { ...job, progress: event.progress, stage: event.stage, eta_ms: event.eta_ms }
+              : job,
+          ),
+        ),
+      ),
+      onJobStateChanged(() => refresh()),
+    ];
+
+    return () => {
+      unlisteners.forEach((unlisten) => unlisten.then((fn) => fn()));
+    };
+  }, []);
+
+  const handleAction = async (job: Job, action: JobAction) => {
+    try {
+      setError(null);
+      await controlJob(job.id, action);
+      await refresh();
+    } catch (err) {
+      setError(`操作失败: ${err}`);
+      console.error("Control job failed:", err);
+    }
+  };
+
+  const handlePriority = async (job: Job, priority: number) => {
+    try {
+      setError(null);
+      await setJobPriority(job.id, priority);
+      await refresh();
+    } catch (err) {
+      setError(`调整优先级失败: ${err}`);
+      console.error("Set job priority failed:", err);
+    }
+  };
+
   return (
-    <div className="p-8">
-      <h2 className="text-2xl font-bold mb-4">转换队列</h2>
-      <p className="text-muted-foreground">
-        查看和管理转换任务...
-      </p>
+    <div className="p-8 max-w-4xl mx-auto">
+      <div className="mb-8">
+        <h2 className="text-3xl font-bold mb-2">转换队列</h2>
+        <p className="text-muted-foreground">查看和管理转换任务</p>
+      </div>
+
+      {/* 错误提示 */}
+      {error && (
+        <div className="mb-6 p-4 rounded-lg bg-destructive/10 border border-destructive/20">
+          <p className="text-sm text-destructive">{error}</p>
+        </div>
+      )}
+
+      <section className="space-y-3">
+        {jobs.length === 0 && <p className="text-sm text-muted-foreground">暂无任务</p>}
+        {displayOrder(jobs).map((job) => {
+          const active = job.state === "queued" || job.state === "running";
+          return (
+            <div key={job.id} className="p-4 rounded-lg border-2 border-border">
+              <div className="flex items-center justify-between gap-4">
+                <div className="min-w-0">
+                  <p className="font-medium truncate">
+                    {job.inputs.map(fileName).join(", ")} → {job.target_format.toUpperCase()}
+                  </p>
+                  <p className="text-xs text-muted-foreground mt-1">
+                    {job.stage ?? "等待执行"}
+                    {job.state === "running" && job.eta_ms != null && job.eta_ms > 0 &&
+                      ` · 剩余约 ${Math.ceil(job.eta_ms / 1000)} 秒`}
+                  </p>
+                </div>
+                <div className="flex items-center gap-2 shrink-0">
+                  {job.priority !== 0 && (
+                    <span className="text-xs text-muted-foreground">优先级 {job.priority}</span>
+                  )}
+                  <span className={`px-2 py-0.5 rounded text-xs ${STATE_STYLES[job.state]}`}>
+                    {STATE_LABELS[job.state]}
+                  </span>
+                  {job.state === "queued" && (
+                    <>
+                      <button
+                        onClick={() => handleAction(job, "move_to_top")}
+                        className="p-1 rounded hover:bg-muted"
+                        title="移到队首"
+                      >
+                        <ArrowUpToLine className="h-4 w-4" />
+                      </button>
+                      <button
+                        onClick={() => handleAction(job, "defer")}
+                        className="p-1 rounded hover:bg-muted"
+                        title="推迟"
+                      >
+                        <ArrowDownToLine className="h-4 w-4" />
+                      </button>
+                    </>
+                  )}
+                  {(job.state === "queued" || job.state === "paused") && (
+                    <>
+                      <button
+                        onClick={() => handlePriority(job, job.priority + 1)}
+                        className="p-1 rounded hover:bg-muted"
+                        title="提高优先级"
+                      >
+                        <ChevronUp className="h-4 w-4" />
+                      </button>
+                      <button
+                        onClick={() => handlePriority(job, job.priority - 1)}
+                        className="p-1 rounded hover:bg-muted"
+                        title="降低优先级"
+                      >
+                        <ChevronDown className="h-4 w-4" />
+                      </button>
+                    </>
+                  )}
+                  {active && (
+                    <button
+                      onClick={() => handleAction(job, "pause")}
+                      className="p-1 rounded hover:bg-muted"
+                      title="暂停"
+                    >
+                      <Pause className="h-4 w-4" />
+                    </button>
+                  )}
+                  {job.state === "paused" && (
+                    <button
+                      onClick={() => handleAction(job, "resume")}
+                      className="p-1 rounded hover:bg-muted"
+                      title="继续"
+                    >
+                      <Play className="h-4 w-4" />
+                    </button>
+                  )}
+                  {job.state === "failed" && (
+                    <button
+                      onClick={() => handleAction(job, "retry")}
+                      className="p-1 rounded hover:bg-muted"
+                      title="重试"
+                    >
+                      <RotateCcw className="h-4 w-4" />
+                    </button>
+                  )}
+                  {(active || job.state === "paused") && (
+                    <button
+                      onClick={() => handleAction(job, "cancel")}
+                      className="p-1 rounded text-destructive hover:bg-destructive/10"
+                      title="取消"
+                    >
+                      <X className="h-4 w-4" />
+                    </button>
+                  )}
+                </div>
+              </div>
+
+              {/* 进度条 */}
+              {(job.state === "running" || job.state === "paused") && (
+                <div className="mt-3 h-1.5 rounded-full bg-muted overflow-hidden">
+                  <div
+                    className={`h-full transition-all ${
+                      job.state === "paused" ? "bg-yellow-500" : "bg-primary"
+                    }`}
+                    style={{ width: `${Math.round(job.progress * 100)}%` }}
+                  />
+                </div>
+              )}
+
+              {job.error && <p className="mt-2 text-xs text-destructive">{job.error}</p>}
+
+              {/* 多条路线时展示每次尝试 */}
+              {job.attempts.length > 1 && (
+                <ul className="mt-2 space-y-1 text-xs text-muted-foreground">
+                  {job.attempts.map((attempt, index) => (
+                    <li key={index}>
+                      路线 #{attempt.route} ({attempt.converters.join(" → ")}):{" "}
+                      {OUTCOME_LABELS[attempt.outcome]}
+                      {attempt.error && ` · ${attempt.error}`}
+                    </li>
+                  ))}
+                </ul>
+              )}
+            </div>
+          );
+        })}
+      </section>
     </div>
   );
 }
@@ -2,7 +2,28 @@ import { useState } from "react";
 import { FileDropzone } from "@/components/FileDropzone";
 import { ArrowRight, Loader2 } from "lucide-react";
 import { planTargets, createJob } from "@/lib/tauri";
-import type { ConversionTarget } from "@/types/ipc";
+import type { ConversionTarget, MissingRequirement, RequirementKind } from "@/types/ipc";
+
+const REQUIREMENT_KIND_LABELS: Record<RequirementKind, string> = {
+  tool: "工具",
+  lang_pack: "语言包",
+  font: "字体",
+};
+
+function describeRequirement(requirement: MissingRequirement) {
+  const name = `${REQUIREMENT_KIND_LABELS[requirement.kind]} ${requirement.name}`;
+  const required = requirement.required ? ` ${requirement.required}` : "";
+  switch (requirement.reason) {
+    case "version_mismatch":
+      return `${name}${required} (已安装 ${requirement.installed})`;
+    case "unknown_version":
+      return `${name}${required} (无法识别已安装版本)`;
+    case "invalid_requirement":
+      return `${name} (版本要求无效)`;
+    default:
+      return `${name}${required}`;
+  }
+}
 
 export function QuickConvert() {
   const [files, setFiles] = useState<File[]>([]);
@@ -130,7 +151,7 @@ export function QuickConvert() {
                   </div>
                   {!target.available && target.missing_requirements.length > 0 && (
                     <p className="text-xs text-destructive mt-2">
-                      需要: {target.missing_requirements.join(", ")}
+                      需要: {target.missing_requirements.map(describeRequirement).join(", ")}
                     </p>
                   )}
                 </button>
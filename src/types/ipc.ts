@@ -25,6 +25,33 @@ export interface CapabilityRecord {
   max_mem_mb?: number;
   parallelism: number;
   streaming: boolean;
+  command?: CommandTemplate;
+  /** 覆盖全局设置的重试策略 */
+  retry?: RetryPolicy;
+}
+
+export interface RetryPolicy {
+  max_retries: number;
+  initial_backoff_ms: number;
+  max_backoff_ms: number;
+  multiplier: number;
+}
+
+export interface CommandTemplate {
+  tool: string;
+  /** 参数模板，支持 {input}、{output}、{opt:name} 与 {if:name} 前缀 */
+  args: string[];
+  options: Record<string, OptionSpec>;
+}
+
+export type OptionType = "bool" | "integer" | "number" | "string" | "enum";
+
+export interface OptionSpec {
+  type: OptionType;
+  default?: unknown;
+  min?: number;
+  max?: number;
+  values: string[];
 }
 
 export interface FormatSpec {
@@ -42,6 +69,10 @@ export interface Requirements {
   tools: string[];
   lang_packs: string[];
   fonts: string[];
+  /** 工具最低版本 (包含)，键为工具 ID */
+  min_version?: Record<string, string>;
+  /** 工具最高版本 (包含)，只比较给出的段 */
+  max_version?: Record<string, string>;
 }
 
 export type PreserveLevel = "true" | "false" | "partial";
@@ -57,11 +88,23 @@ export interface PreserveFeatures {
   formulas: PreserveLevel;
 }
 
+export type PreserveConstraints = Partial<Record<keyof PreserveFeatures, PreserveLevel>>;
+
+export interface FeatureLoss {
+  feature: keyof PreserveFeatures;
+  level: PreserveLevel;
+  converter_id: string;
+}
+
 export interface ConversionPath {
   steps: ConversionStep[];
   total_quality: number;
   estimated_time_ms: number;
   risks: string[];
+  score: number;
+  preserve: PreserveFeatures;
+  losses: FeatureLoss[];
+  missing_requirements: MissingRequirement[];
 }
 
 export interface ConversionStep {
@@ -77,7 +120,23 @@ export interface ConversionTarget {
   paths: ConversionPath[];
   recommended: boolean;
   available: boolean;
-  missing_requirements: string[];
+  missing_requirements: MissingRequirement[];
+}
+
+export type RequirementKind = "tool" | "lang_pack" | "font";
+
+export type MissingReason = "not_installed" | "version_mismatch" | "unknown_version" | "invalid_requirement";
+
+/** 未满足的依赖 */
+export interface MissingRequirement {
+  kind: RequirementKind;
+  /** 工具 ID、语言包或字体名 */
+  name: string;
+  reason: MissingReason;
+  /** 要求的版本范围，如 ">= 3.0" */
+  required?: string;
+  /** 已安装的版本 */
+  installed?: string;
 }
 
 export interface ToolHealth {
@@ -90,14 +149,21 @@ export interface ToolHealth {
 
 export interface HealthReport {
   tools: Record<string, ToolHealth>;
+  lang_packs: string[];
+  fonts?: string[];
+  /** 外部工具的隔离级别 */
+  isolation: IsolationLevel;
   timestamp: number;
 }
 
+/** landlock: 限制文件系统访问；restricted: 仅私有工作目录与精简环境变量 */
+export type IsolationLevel = "landlock" | "restricted";
+
 // ============================================================================
 // 任务相关类型
 // ============================================================================
 
-export type JobState = "queued" | "running" | "succeeded" | "failed" | "canceled";
+export type JobState = "queued" | "running" | "paused" | "succeeded" | "failed" | "canceled";
 
 export interface Job {
   id: string;
@@ -113,9 +179,27 @@ export interface Job {
   started_at?: number;
   finished_at?: number;
   error?: string;
+  /** 各条转换路线的执行记录 */
+  attempts: PathAttempt[];
+  /** 优先级，数值越大越先执行 */
+  priority: number;
+  /** 同一优先级内的排队顺序，数值越小越先执行 */
+  queue_position: number;
 }
 
-export type JobAction = "pause" | "resume" | "cancel" | "retry";
+export type AttemptOutcome = "succeeded" | "failed" | "rejected" | "canceled";
+
+export interface PathAttempt {
+  /** 路线在候选路径中的序号，从 1 开始 */
+  route: number;
+  converters: string[];
+  outcome: AttemptOutcome;
+  error?: string;
+  started_at: number;
+  finished_at: number;
+}
+
+export type JobAction = "pause" | "resume" | "cancel" | "retry" | "move_to_top" | "defer";
 
 export interface JobProgress {
   job_id: string;
@@ -172,6 +256,18 @@ export interface FileDetection {
 
 export type QualityPriority = "quality" | "balanced" | "speed";
 
+export interface ScoringWeights {
+  quality: number;
+  speed: number;
+  cost: number;
+}
+
+export interface ScoringPresets {
+  quality: ScoringWeights;
+  balanced: ScoringWeights;
+  speed: ScoringWeights;
+}
+
 export interface ConversionOptions {
   quality_priority: QualityPriority;
   max_file_size_mb?: number;
@@ -179,6 +275,9 @@ export interface ConversionOptions {
   ocr_languages: string[];
   preserve_metadata: boolean;
   custom_params: Record<string, unknown>;
+  preserve?: PreserveConstraints;
+  /** 产物输出目录，未指定时与输入文件同目录 */
+  output_dir?: string;
 }
 
 export interface Recipe {
@@ -258,4 +357,30 @@ export interface AppSettings {
   cache_size_mb: number;
   ocr_enabled: boolean;
   ocr_languages: string[];
+  scoring_weights: ScoringPresets;
+  /** 受信任的插件签名公钥 (ed25519，十六进制) */
+  trusted_plugin_keys: string[];
+  allow_unsigned_plugins: boolean;
+  /** 重启时对仍在运行中的任务的处理方式 */
+  running_job_recovery: RunningJobRecovery;
+  /** 默认重试策略 */
+  retry: RetryPolicy;
+  /** 同时运行的转换步骤的内存预算 (MB) */
+  memory_budget_mb: number;
+}
+
+export type RunningJobRecovery = "fail" | "requeue";
+
+// ========== 插件 ==========
+
+export interface PluginInfo {
+  id: string;
+  name: string;
+  version: string;
+  vendor: string;
+  license: string;
+  /** 安装时是否通过受信任密钥的签名验证 */
+  signed: boolean;
+  enabled: boolean;
+  installed_at: number;
 }